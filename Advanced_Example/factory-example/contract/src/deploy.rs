@@ -1,14 +1,182 @@
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, Promise, PromiseError, PublicKey};
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde_json::Value;
+use near_sdk::{
+    env, log, near_bindgen, AccountId, Balance, CryptoHash, Gas, Promise, PromiseError, PublicKey,
+};
 
-use crate::{Contract, ContractExt, NEAR_PER_STORAGE};
+use crate::fees::DeploymentCost;
+use crate::proxy::{ext_implementation, GAS_FOR_ADD_PROXY};
+use crate::{Contract, ContractExt, DeploymentFailure, StorageKey, Subaccount};
+
+const NO_DEPOSIT: Balance = 0;
+const GAS_FOR_INIT: Gas = Gas(30_000_000_000_000); // 30 TGAS
+
+// Storage the protocol counts for an account, and for each of its records on top of their bytes
+const ACCOUNT_STORAGE: u64 = 100;
+const RECORD_STORAGE_OVERHEAD: u64 = 40;
+// Borsh size of a full access key, its nonce and its permission
+const FULL_ACCESS_KEY_SIZE: u64 = 9;
+// Reserved for the sub-account's records in the factory, the unused part is refunded
+const RECORD_STORAGE: u64 = 1_000;
+
+// Callbacks only learn that the batch failed, the exact error is in the transaction's receipts.
+// Names the factory already created are refused before the batch, so what is left depends on
+// whether the batch called an init method
+const HINT_ACCOUNT_EXISTS: &str = "The account was created outside of the factory's records";
+const HINT_INIT_FAILED: &str = "The init method failed, or the state it wrote needs more deposit \
+    than the version's init_storage covers";
+
+pub(crate) fn storage_cost(bytes: u64) -> Balance {
+    env::storage_byte_cost() * bytes as Balance
+}
+
+/// Storage of a full access key for `public_key`, as counted by the protocol
+fn access_key_storage(public_key: &PublicKey) -> u64 {
+    RECORD_STORAGE_OVERHEAD + public_key.as_bytes().len() as u64 + FULL_ACCESS_KEY_SIZE
+}
 
 #[near_bindgen]
 impl Contract {
+    /// Creates `name.<factory>` and deploys `code_hash` (or the default version) on it,
+    /// calling `init_method` with `init_args` if given.
     #[payable]
     pub fn create_factory_subaccount_and_deploy(
         &mut self,
         name: String,
         public_key: Option<PublicKey>,
+        code_hash: Option<Base58CryptoHash>,
+        init_method: Option<String>,
+        init_args: Option<Value>,
+    ) -> Promise {
+        let code_hash = self.internal_resolve_version(code_hash);
+        let init = init_method.map(|method| {
+            let args = init_args
+                .map(|args| near_sdk::serde_json::to_vec(&args).unwrap())
+                .unwrap_or_default();
+            (method, args)
+        });
+
+        self.internal_create_subaccount_and_deploy(name, public_key, code_hash, init, None)
+    }
+
+    #[private]
+    pub fn create_factory_subaccount_and_deploy_callback(
+        &mut self,
+        account: AccountId,
+        user: AccountId,
+        attached: Balance,
+        code_hash: Base58CryptoHash,
+        init_method: Option<String>,
+        implementation: Option<AccountId>,
+        account_cost: Balance,
+        fee: Balance,
+        #[callback_result] create_deploy_result: Result<(), PromiseError>,
+    ) -> bool {
+        // The bookkeeping below is paid by the user, out of the reserved record storage
+        let initial_storage = env::storage_usage();
+
+        if let Ok(_result) = create_deploy_result {
+            log!(format!("Correctly created and deployed to {account}"));
+            if let Some(implementation) = &implementation {
+                ext_implementation::ext(implementation.clone())
+                    .with_static_gas(GAS_FOR_ADD_PROXY)
+                    .add_proxy(account.clone());
+            }
+            self.failures.remove(&(user.clone(), account.clone()));
+            self.internal_add_subaccount(Subaccount {
+                account_id: account,
+                owner: user.clone(),
+                version: code_hash,
+                created_at: U64(env::block_timestamp()),
+                staged_version: None,
+                implementation,
+            });
+
+            let record_cost = storage_cost(env::storage_usage().saturating_sub(initial_storage));
+            if fee > 0 && self.treasury != env::current_account_id() {
+                Promise::new(self.treasury.clone()).transfer(fee);
+            }
+            let refund = attached.saturating_sub(account_cost + fee + record_cost);
+            if refund > 0 {
+                Promise::new(user).transfer(refund);
+            }
+            return true;
+        };
+
+        let hint = if init_method.is_some() {
+            HINT_INIT_FAILED
+        } else {
+            HINT_ACCOUNT_EXISTS
+        };
+        self.failures.insert(
+            &(user.clone(), account.clone()),
+            &DeploymentFailure {
+                account_id: account.clone(),
+                owner: user.clone(),
+                code_hash,
+                init_method,
+                attached: U128(attached),
+                hint: hint.to_string(),
+                failed_at: U64(env::block_timestamp()),
+            },
+        );
+
+        // The account is not created, so only the failure record is paid
+        let record_cost = storage_cost(env::storage_usage().saturating_sub(initial_storage));
+        let refund = attached.saturating_sub(record_cost);
+        log!(format!(
+            "Error creating {account}, returning {refund}yⓃ to {user}"
+        ));
+        Promise::new(user).transfer(refund);
+        false
+    }
+}
+
+impl Contract {
+    /// Deposit needed to create a sub-account with `code_hash`, calling its init method with
+    /// `init_args_len` bytes of arguments if given, and adding a full access key for `public_key`.
+    pub(crate) fn internal_deployment_cost(
+        &self,
+        code_hash: &CryptoHash,
+        init_args_len: Option<usize>,
+        public_key: Option<&PublicKey>,
+    ) -> DeploymentCost {
+        let version = self
+            .versions
+            .get(code_hash)
+            .unwrap_or_else(|| env::panic_str("Unknown code version"));
+
+        let mut account_storage = ACCOUNT_STORAGE + version.size.0;
+        // The state written by init methods usually grows with their arguments
+        if let Some(init_args_len) = init_args_len {
+            account_storage += version.init_storage.0 + init_args_len as u64;
+        }
+        if let Some(public_key) = public_key {
+            account_storage += access_key_storage(public_key);
+        }
+
+        let total =
+            storage_cost(account_storage) + storage_cost(RECORD_STORAGE) + self.fee;
+        DeploymentCost {
+            account_storage: U64(account_storage),
+            record_storage: U64(RECORD_STORAGE),
+            fee: U128(self.fee),
+            total: U128(total),
+        }
+    }
+
+    /// Creates `name.<factory>` and deploys `code_hash` on it.
+    /// If `init` is given, the method is called with its arguments right after the deploy,
+    /// in the same batch, so the sub-account is never left uninitialized.
+    /// `implementation` is recorded for proxies, see `create_proxy_subaccount`.
+    pub(crate) fn internal_create_subaccount_and_deploy(
+        &mut self,
+        name: String,
+        public_key: Option<PublicKey>,
+        code_hash: CryptoHash,
+        init: Option<(String, Vec<u8>)>,
+        implementation: Option<AccountId>,
     ) -> Promise {
         // Assert the sub-account is valid
         let current_account = env::current_account_id().to_string();
@@ -17,21 +185,35 @@ impl Contract {
             env::is_valid_account_id(subaccount.as_bytes()),
             "Invalid subaccount"
         );
+        assert!(
+            self.subaccounts.get(&subaccount).is_none(),
+            "{subaccount} already exists"
+        );
 
-        // Assert enough money is attached to create the account and deploy the contract
+        // Assert enough money is attached to pay for the account's storage, our records and the fee
         let attached = env::attached_deposit();
-
-        let contract_bytes = self.code.len() as u128;
-        let minimum_needed = NEAR_PER_STORAGE * contract_bytes;
+        let init_args_len = init.as_ref().map(|(_, args)| args.len());
+        let cost = self.internal_deployment_cost(&code_hash, init_args_len, public_key.as_ref());
+        let minimum_needed = cost.total.0;
         assert!(
             attached >= minimum_needed,
             "Attach at least {minimum_needed} yⓃ"
         );
 
+        // The new account only receives what its storage needs, the rest is settled in the callback
+        let account_cost = storage_cost(cost.account_storage.0);
+        let code = self.internal_get_code(&code_hash);
+
         let mut promise = Promise::new(subaccount.clone())
             .create_account()
-            .transfer(attached)
-            .deploy_contract(self.code.clone());
+            .transfer(account_cost)
+            .deploy_contract(code);
+
+        // Initialize the contract in the same batch, if it fails the account is not created
+        let init_method = init.as_ref().map(|(method, _)| method.clone());
+        if let Some((method, args)) = init {
+            promise = promise.function_call(method, args, NO_DEPOSIT, GAS_FOR_INIT);
+        }
 
         // Add full access key is the user passes one
         if let Some(pk) = public_key {
@@ -44,27 +226,26 @@ impl Contract {
                 subaccount,
                 env::predecessor_account_id(),
                 attached,
+                code_hash.into(),
+                init_method,
+                implementation,
+                account_cost,
+                cost.fee.0,
             ),
         )
     }
 
-    #[private]
-    pub fn create_factory_subaccount_and_deploy_callback(
-        &mut self,
-        account: AccountId,
-        user: AccountId,
-        attached: Balance,
-        #[callback_result] create_deploy_result: Result<(), PromiseError>,
-    ) -> bool {
-        if let Ok(_result) = create_deploy_result {
-            log!(format!("Correctly created and deployed to {account}"));
-            return true;
-        };
-
-        log!(format!(
-            "Error creating {account}, returning {attached}yⓃ to {user}"
-        ));
-        Promise::new(user).transfer(attached);
-        false
+    fn internal_add_subaccount(&mut self, subaccount: Subaccount) {
+        let mut owned = self
+            .subaccounts_per_owner
+            .get(&subaccount.owner)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::SubaccountsPerOwnerInner {
+                    owner_hash: env::sha256_array(subaccount.owner.as_bytes()),
+                })
+            });
+        owned.insert(&subaccount.account_id);
+        self.subaccounts_per_owner.insert(&subaccount.owner, &owned);
+        self.subaccounts.insert(&subaccount.account_id, &subaccount);
     }
 }
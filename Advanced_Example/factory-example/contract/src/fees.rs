@@ -0,0 +1,58 @@
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::Value;
+use near_sdk::{near_bindgen, AccountId, PublicKey};
+
+use crate::{Contract, ContractExt};
+
+/// What a deployment costs, `total` is the minimum deposit to attach
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeploymentCost {
+    // Account, access key, code and initial state, transferred to the new account
+    pub account_storage: U64,
+    // Reserved for the factory's records, the unused part is refunded
+    pub record_storage: U64,
+    pub fee: U128,
+    pub total: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_fee(&mut self, fee: U128) {
+        ownable::assert_owner(&self.owner_id);
+        self.fee = fee.0;
+    }
+
+    pub fn set_treasury(&mut self, treasury: AccountId) {
+        ownable::assert_owner(&self.owner_id);
+        self.treasury = treasury;
+    }
+
+    pub fn get_fee(&self) -> U128 {
+        U128(self.fee)
+    }
+
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury.clone()
+    }
+
+    /// Deposit needed by `create_factory_subaccount_and_deploy` with the same arguments.
+    /// For `create_ft` and `create_nft`, pass `new` and the arguments of the token's `new` method.
+    pub fn get_deployment_cost(
+        &self,
+        public_key: Option<PublicKey>,
+        code_hash: Option<Base58CryptoHash>,
+        init_method: Option<String>,
+        init_args: Option<Value>,
+    ) -> DeploymentCost {
+        let code_hash = self.internal_resolve_version(code_hash);
+        let init_args_len = init_method.map(|_| {
+            init_args
+                .map(|args| near_sdk::serde_json::to_vec(&args).unwrap().len())
+                .unwrap_or_default()
+        });
+
+        self.internal_deployment_cost(&code_hash, init_args_len, public_key.as_ref())
+    }
+}
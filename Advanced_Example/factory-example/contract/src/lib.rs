@@ -1,22 +1,176 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{near_bindgen, Balance};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{
+    env, near_bindgen, AccountId, Balance, BorshStorageKey, CryptoHash, PanicOnDefault,
+};
 
 mod deploy;
+mod fees;
 mod manager;
+mod proxy;
+mod subaccounts;
+mod tokens;
+mod upgrade;
 
-const NEAR_PER_STORAGE: Balance = 10_000_000_000_000_000_000; // 10e19yⓃ
 const DEFAULT_CONTRACT: &[u8] = include_bytes!("./contract/hello_near.wasm");
+// Room for the keys and prefixes written by an init method, on top of its arguments
+const DEFAULT_INIT_STORAGE: u64 = 500;
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Codes,
+    Versions,
+    Subaccounts,
+    SubaccountsPerOwner,
+    SubaccountsPerOwnerInner { owner_hash: CryptoHash },
+    Failures,
+    Implementations,
+}
+
+/// A contract stored in the factory, identified by the sha256 of its code
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CodeVersion {
+    pub code_hash: Base58CryptoHash,
+    pub size: U64,
+    pub registered_at: U64,
+    // Bytes written by the contract's init method, on top of the size of its arguments
+    pub init_storage: U64,
+}
+
+/// A sub-account created by the factory
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Subaccount {
+    pub account_id: AccountId,
+    pub owner: AccountId,
+    pub version: Base58CryptoHash,
+    pub created_at: U64,
+    // Code sent to the sub-account with `upgrade_subaccount`, waiting to be deployed
+    pub staged_version: Option<Base58CryptoHash>,
+    // Account the sub-account forwards its calls to, if it was created as a proxy
+    pub implementation: Option<AccountId>,
+}
+
+/// A sub-account the factory failed to create, kept so its owner can find out what happened
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeploymentFailure {
+    pub account_id: AccountId,
+    pub owner: AccountId,
+    pub code_hash: Base58CryptoHash,
+    pub init_method: Option<String>,
+    pub attached: U128,
+    // The likely cause, the exact error is in the receipts of the transaction
+    pub hint: String,
+    pub failed_at: U64,
+}
 
 #[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
+    codes: LookupMap<CryptoHash, Vec<u8>>,
+    versions: UnorderedMap<CryptoHash, CodeVersion>,
+    default_version: CryptoHash,
+    ft_version: Option<CryptoHash>,
+    nft_version: Option<CryptoHash>,
+    proxy_version: Option<CryptoHash>,
+    implementations: UnorderedSet<AccountId>,
+    subaccounts: UnorderedMap<AccountId, Subaccount>,
+    subaccounts_per_owner: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    // Keyed by owner and account, so a failed attempt only overwrites its caller's record
+    failures: LookupMap<(AccountId, AccountId), DeploymentFailure>,
+    // Charged on every successful deployment and sent to the treasury
+    fee: Balance,
+    treasury: AccountId,
+    // Manages the codes, fees and upgrades. `set_owner` hands it over, e.g. to a `dao` contract
+    owner_id: AccountId,
+}
+
+// The layout of the factory before the code registry, with the code it deployed
+#[derive(BorshDeserialize)]
+struct OldContract {
     code: Vec<u8>,
 }
 
-impl Default for Contract {
-    fn default() -> Self {
+#[near_bindgen]
+impl Contract {
+    #[init]
+    #[private]
+    pub fn new(owner_id: AccountId) -> Self {
+        let mut this = Self::empty(owner_id);
+        this.default_version = this.internal_register_code(DEFAULT_CONTRACT.to_vec());
+        this
+    }
+
+    /// Moves a factory deployed before the code registry to the current layout, its stored
+    /// code becomes the default version
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old: OldContract = env::state_read().expect("No state to migrate");
+        let mut this = Self::empty(owner_id);
+        this.default_version = this.internal_register_code(old.code);
+        this
+    }
+}
+
+impl Contract {
+    fn empty(owner_id: AccountId) -> Self {
         Self {
-            code: DEFAULT_CONTRACT.to_vec(),
+            codes: LookupMap::new(StorageKey::Codes),
+            versions: UnorderedMap::new(StorageKey::Versions),
+            default_version: [0; 32],
+            ft_version: None,
+            nft_version: None,
+            proxy_version: None,
+            implementations: UnorderedSet::new(StorageKey::Implementations),
+            subaccounts: UnorderedMap::new(StorageKey::Subaccounts),
+            subaccounts_per_owner: LookupMap::new(StorageKey::SubaccountsPerOwner),
+            failures: LookupMap::new(StorageKey::Failures),
+            fee: 0,
+            treasury: env::current_account_id(),
+            owner_id,
         }
     }
 }
+
+impl Contract {
+    /// Stores `code` and returns its hash, registering the same code twice is a no-op
+    pub(crate) fn internal_register_code(&mut self, code: Vec<u8>) -> CryptoHash {
+        let code_hash = env::sha256_array(&code);
+        if self.versions.get(&code_hash).is_none() {
+            self.versions.insert(
+                &code_hash,
+                &CodeVersion {
+                    code_hash: code_hash.into(),
+                    size: U64(code.len() as u64),
+                    registered_at: U64(env::block_timestamp()),
+                    init_storage: U64(DEFAULT_INIT_STORAGE),
+                },
+            );
+            self.codes.insert(&code_hash, &code);
+        }
+        code_hash
+    }
+
+    /// Returns the hash of the requested version, or of the default one
+    pub(crate) fn internal_resolve_version(&self, code_hash: Option<Base58CryptoHash>) -> CryptoHash {
+        let code_hash = code_hash.map(CryptoHash::from).unwrap_or(self.default_version);
+        if self.versions.get(&code_hash).is_none() {
+            env::panic_str("Unknown code version");
+        }
+        code_hash
+    }
+
+    pub(crate) fn internal_get_code(&self, code_hash: &CryptoHash) -> Vec<u8> {
+        self.codes
+            .get(code_hash)
+            .unwrap_or_else(|| env::panic_str("Unknown code version"))
+    }
+}
+
+ownable::impl_ownable!(Contract, owner_id);
+nep_330::impl_contract_source_metadata!(Contract);
@@ -1,20 +1,77 @@
-use near_sdk::{env, near_bindgen};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::{env, near_bindgen, require, CryptoHash};
 
-use crate::{Contract, ContractExt};
+use crate::{CodeVersion, Contract, ContractExt};
 
 #[near_bindgen]
 impl Contract {
-    #[private]
-    pub fn update_stored_contract(&mut self) {
+    pub fn update_stored_contract(&mut self) -> Base58CryptoHash {
+        ownable::assert_owner(&self.owner_id);
         // This method receives the code to be stored in the contract directly
         // from the contract's input. In this way, it avoids the overhead of
         // deserializing parameters, which would consume a huge amount of GAS
-        self.code = env::input().expect("Error: No input").to_vec();
+        let code = env::input().expect("Error: No input").to_vec();
+
+        // The new code is registered and becomes the one deployed by default
+        self.default_version = self.internal_register_code(code);
+        self.default_version.into()
+    }
+
+    pub fn register_code(&mut self) -> Base58CryptoHash {
+        ownable::assert_owner(&self.owner_id);
+        // Same as `update_stored_contract`, but keeps the default version
+        let code = env::input().expect("Error: No input").to_vec();
+        self.internal_register_code(code).into()
+    }
+
+    pub fn set_default_version(&mut self, code_hash: Base58CryptoHash) {
+        ownable::assert_owner(&self.owner_id);
+        self.default_version = self.internal_resolve_version(Some(code_hash));
+    }
+
+    /// Bytes the init method of `code_hash` writes besides its arguments, charged on deployment
+    pub fn set_init_storage(&mut self, code_hash: Base58CryptoHash, init_storage: U64) {
+        ownable::assert_owner(&self.owner_id);
+        let code_hash = self.internal_resolve_version(Some(code_hash));
+        let mut version = self.versions.get(&code_hash).unwrap();
+        version.init_storage = init_storage;
+        self.versions.insert(&code_hash, &version);
+    }
+
+    pub fn remove_code(&mut self, code_hash: Base58CryptoHash) {
+        ownable::assert_owner(&self.owner_id);
+        let code_hash = CryptoHash::from(code_hash);
+        require!(
+            code_hash != self.default_version,
+            "Cannot remove the default version"
+        );
+        require!(
+            self.versions.remove(&code_hash).is_some(),
+            "Unknown code version"
+        );
+        self.codes.remove(&code_hash);
     }
 
-    pub fn get_code(&self) -> &Vec<u8> {
+    pub fn get_code(&self) -> Vec<u8> {
         // If a contract wants to update themselves, they can ask us for the code needed
-        &self.code
+        self.internal_get_code(&self.default_version)
     }
-}
 
+    pub fn get_default_version(&self) -> Base58CryptoHash {
+        self.default_version.into()
+    }
+
+    pub fn get_code_version(&self, code_hash: Base58CryptoHash) -> Option<CodeVersion> {
+        self.versions.get(&code_hash.into())
+    }
+
+    pub fn get_code_versions(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<CodeVersion> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.versions
+            .values()
+            .skip(from as usize)
+            .take(limit.unwrap_or(10) as usize)
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::{
+    env, ext_contract, near_bindgen, require, AccountId, CryptoHash, Gas, Promise, PublicKey,
+};
+
+use crate::fees::DeploymentCost;
+use crate::{Contract, ContractExt};
+
+pub(crate) const GAS_FOR_ADD_PROXY: Gas = Gas(5_000_000_000_000); // 5 TGAS
+
+// Implementations only serve the proxies the factory adds to them, see `../hello-shared`
+#[ext_contract(ext_implementation)]
+pub trait Implementation {
+    fn add_proxy(&mut self, account_id: AccountId);
+}
+
+// Proxy mode: instead of a full copy of a contract, sub-accounts get the forwarder of `../proxy`,
+// which sends every call to a shared implementation. The implementation keeps the state of each
+// proxy under the proxy's account, so an instance only pays for the forwarder and a few bytes.
+#[near_bindgen]
+impl Contract {
+    /// Selects the registered code of the forwarder
+    pub fn set_proxy_version(&mut self, code_hash: Base58CryptoHash) {
+        ownable::assert_owner(&self.owner_id);
+        self.proxy_version = Some(self.internal_resolve_version(Some(code_hash)));
+    }
+
+    /// Allows proxies to forward their calls to `account_id`
+    pub fn register_implementation(&mut self, account_id: AccountId) {
+        ownable::assert_owner(&self.owner_id);
+        self.implementations.insert(&account_id);
+    }
+
+    /// Existing proxies keep forwarding to `account_id`, new ones can not be created
+    pub fn remove_implementation(&mut self, account_id: AccountId) {
+        ownable::assert_owner(&self.owner_id);
+        require!(
+            self.implementations.remove(&account_id),
+            "Unknown implementation"
+        );
+    }
+
+    pub fn get_proxy_version(&self) -> Option<Base58CryptoHash> {
+        self.proxy_version.map(Base58CryptoHash::from)
+    }
+
+    pub fn get_implementations(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.implementations
+            .iter()
+            .skip(from as usize)
+            .take(limit.unwrap_or(10) as usize)
+            .collect()
+    }
+
+    /// Deposit needed by `create_proxy_subaccount` with the same arguments
+    pub fn get_proxy_deployment_cost(
+        &self,
+        implementation: AccountId,
+        public_key: Option<PublicKey>,
+    ) -> DeploymentCost {
+        let code_hash = self.internal_proxy_version();
+        self.internal_deployment_cost(
+            &code_hash,
+            Some(implementation.as_str().len()),
+            public_key.as_ref(),
+        )
+    }
+
+    /// Creates `name.<factory>` with a forwarder to `implementation`
+    #[payable]
+    pub fn create_proxy_subaccount(
+        &mut self,
+        name: String,
+        implementation: AccountId,
+        public_key: Option<PublicKey>,
+    ) -> Promise {
+        require!(
+            self.implementations.contains(&implementation),
+            "Unknown implementation"
+        );
+        let code_hash = self.internal_proxy_version();
+
+        // The forwarder's `init` takes the implementation as raw input
+        let args = implementation.as_bytes().to_vec();
+        self.internal_create_subaccount_and_deploy(
+            name,
+            public_key,
+            code_hash,
+            Some(("init".to_string(), args)),
+            Some(implementation),
+        )
+    }
+}
+
+impl Contract {
+    fn internal_proxy_version(&self) -> CryptoHash {
+        self.proxy_version
+            .unwrap_or_else(|| env::panic_str("No proxy code registered"))
+    }
+}
@@ -0,0 +1,53 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::{Contract, ContractExt, DeploymentFailure, Subaccount};
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_subaccount(&self, account_id: AccountId) -> Option<Subaccount> {
+        self.subaccounts.get(&account_id)
+    }
+
+    pub fn total_subaccounts(&self) -> u64 {
+        self.subaccounts.len()
+    }
+
+    pub fn get_subaccounts(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Subaccount> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.subaccounts
+            .values()
+            .skip(from as usize)
+            .take(limit.unwrap_or(10) as usize)
+            .collect()
+    }
+
+    pub fn get_subaccounts_for_owner(
+        &self,
+        owner: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Subaccount> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        match self.subaccounts_per_owner.get(&owner) {
+            Some(owned) => owned
+                .iter()
+                .skip(from as usize)
+                .take(limit.unwrap_or(10) as usize)
+                .filter_map(|account_id| self.subaccounts.get(&account_id))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Why the last attempt of `owner` to create `account_id` failed, cleared once they create it
+    pub fn get_deployment_failure(
+        &self,
+        owner: AccountId,
+        account_id: AccountId,
+    ) -> Option<DeploymentFailure> {
+        self.failures.get(&(owner, account_id))
+    }
+}
@@ -0,0 +1,110 @@
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, Promise, PublicKey};
+use nep_148::FungibleTokenMetadata;
+use nep_177::NFTContractMetadata;
+
+use crate::{Contract, ContractExt};
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct FtInitArgs<'a> {
+    owner_id: &'a AccountId,
+    total_supply: U128,
+    metadata: &'a FungibleTokenMetadata,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct NftInitArgs<'a> {
+    owner_id: &'a AccountId,
+    metadata: &'a NFTContractMetadata,
+}
+
+// Launches instances of the `ft` and `nft` contracts of this repository. Their code must be
+// registered first (`register_code`) and selected with `set_ft_version` / `set_nft_version`.
+#[near_bindgen]
+impl Contract {
+    pub fn set_ft_version(&mut self, code_hash: Base58CryptoHash) {
+        ownable::assert_owner(&self.owner_id);
+        self.ft_version = Some(self.internal_resolve_version(Some(code_hash)));
+    }
+
+    pub fn set_nft_version(&mut self, code_hash: Base58CryptoHash) {
+        ownable::assert_owner(&self.owner_id);
+        self.nft_version = Some(self.internal_resolve_version(Some(code_hash)));
+    }
+
+    pub fn get_ft_version(&self) -> Option<Base58CryptoHash> {
+        self.ft_version.map(Base58CryptoHash::from)
+    }
+
+    pub fn get_nft_version(&self) -> Option<Base58CryptoHash> {
+        self.nft_version.map(Base58CryptoHash::from)
+    }
+
+    /// Creates `name.<factory>` with a fungible token owned by `owner_id` (the caller by default),
+    /// which receives the whole `total_supply`.
+    #[payable]
+    pub fn create_ft(
+        &mut self,
+        name: String,
+        owner_id: Option<AccountId>,
+        total_supply: U128,
+        metadata: FungibleTokenMetadata,
+        public_key: Option<PublicKey>,
+    ) -> Promise {
+        // Fail here, while the deposit is still refunded, rather than in the `new` call
+        metadata.assert_valid();
+        let code_hash = self
+            .ft_version
+            .unwrap_or_else(|| env::panic_str("No fungible token code registered"));
+
+        let owner_id = owner_id.unwrap_or_else(env::predecessor_account_id);
+        let args = near_sdk::serde_json::to_vec(&FtInitArgs {
+            owner_id: &owner_id,
+            total_supply,
+            metadata: &metadata,
+        })
+        .unwrap();
+
+        self.internal_create_subaccount_and_deploy(
+            name,
+            public_key,
+            code_hash,
+            Some(("new".to_string(), args)),
+            None,
+        )
+    }
+
+    /// Creates `name.<factory>` with a non-fungible token owned by `owner_id` (the caller by default).
+    #[payable]
+    pub fn create_nft(
+        &mut self,
+        name: String,
+        owner_id: Option<AccountId>,
+        metadata: NFTContractMetadata,
+        public_key: Option<PublicKey>,
+    ) -> Promise {
+        // Fail here, while the deposit is still refunded, rather than in the `new` call
+        metadata.assert_valid();
+        let code_hash = self
+            .nft_version
+            .unwrap_or_else(|| env::panic_str("No non-fungible token code registered"));
+
+        let owner_id = owner_id.unwrap_or_else(env::predecessor_account_id);
+        let args = near_sdk::serde_json::to_vec(&NftInitArgs {
+            owner_id: &owner_id,
+            metadata: &metadata,
+        })
+        .unwrap();
+
+        self.internal_create_subaccount_and_deploy(
+            name,
+            public_key,
+            code_hash,
+            Some(("new".to_string(), args)),
+            None,
+        )
+    }
+}
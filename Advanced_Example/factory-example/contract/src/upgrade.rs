@@ -0,0 +1,114 @@
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, log, near_bindgen, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::{Contract, ContractExt, Subaccount};
+
+const NO_DEPOSIT: u128 = 0;
+const GAS_FOR_STAGE_CODE: Gas = Gas(100_000_000_000_000); // 100 TGAS
+const GAS_FOR_DEPLOY_STAGED_CODE: Gas = Gas(230_000_000_000_000); // 230 TGAS
+const GAS_FOR_UPGRADE_CALLBACK: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct DeployStagedCodeArgs {
+    migrate_args: Option<Base64VecU8>,
+}
+
+// The factory has no control over the sub-accounts it creates, so it can only push
+// upgrades to children that cooperate: contracts implementing the staged upgrade methods
+// (`stage_code`, `deploy_staged_code`) with the factory as their manager, as the
+// self-updates example in update-migrate-rust does.
+#[near_bindgen]
+impl Contract {
+    /// Sends the code of `code_hash` to the sub-account, which starts its timelock.
+    pub fn upgrade_subaccount(&mut self, account_id: AccountId, code_hash: Base58CryptoHash) -> Promise {
+        ownable::assert_owner(&self.owner_id);
+        let subaccount = self.internal_unwrap_subaccount(&account_id);
+        require!(
+            subaccount.implementation.is_none(),
+            "Proxies are upgraded by upgrading their implementation"
+        );
+        let code = self.internal_get_code(&self.internal_resolve_version(Some(code_hash)));
+
+        Promise::new(account_id.clone())
+            .function_call("stage_code".to_string(), code, NO_DEPOSIT, GAS_FOR_STAGE_CODE)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_UPGRADE_CALLBACK)
+                    .upgrade_subaccount_callback(account_id, code_hash),
+            )
+    }
+
+    #[private]
+    pub fn upgrade_subaccount_callback(
+        &mut self,
+        account_id: AccountId,
+        code_hash: Base58CryptoHash,
+        #[callback_result] stage_result: Result<(), PromiseError>,
+    ) -> bool {
+        let mut subaccount = self.internal_unwrap_subaccount(&account_id);
+        if stage_result.is_err() {
+            log!(format!("Error staging code on {account_id}"));
+            return false;
+        }
+
+        subaccount.staged_version = Some(code_hash);
+        self.subaccounts.insert(&account_id, &subaccount);
+        true
+    }
+
+    /// Deploys the code staged by `upgrade_subaccount` once the sub-account's timelock passed.
+    pub fn deploy_subaccount_upgrade(
+        &mut self,
+        account_id: AccountId,
+        migrate_args: Option<Base64VecU8>,
+    ) -> Promise {
+        ownable::assert_owner(&self.owner_id);
+        let subaccount = self.internal_unwrap_subaccount(&account_id);
+        if subaccount.staged_version.is_none() {
+            env::panic_str("No code staged on the sub-account");
+        }
+        let args = near_sdk::serde_json::to_vec(&DeployStagedCodeArgs { migrate_args }).unwrap();
+
+        Promise::new(account_id.clone())
+            .function_call(
+                "deploy_staged_code".to_string(),
+                args,
+                NO_DEPOSIT,
+                GAS_FOR_DEPLOY_STAGED_CODE,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_UPGRADE_CALLBACK)
+                    .deploy_subaccount_upgrade_callback(account_id),
+            )
+    }
+
+    #[private]
+    pub fn deploy_subaccount_upgrade_callback(
+        &mut self,
+        account_id: AccountId,
+        #[callback_result] deploy_result: Result<bool, PromiseError>,
+    ) -> bool {
+        let mut subaccount = self.internal_unwrap_subaccount(&account_id);
+        if !matches!(deploy_result, Ok(true)) {
+            log!(format!("Error deploying the staged code on {account_id}"));
+            return false;
+        }
+
+        if let Some(version) = subaccount.staged_version.take() {
+            subaccount.version = version;
+        }
+        self.subaccounts.insert(&account_id, &subaccount);
+        true
+    }
+}
+
+impl Contract {
+    fn internal_unwrap_subaccount(&self, account_id: &AccountId) -> Subaccount {
+        self.subaccounts
+            .get(account_id)
+            .unwrap_or_else(|| env::panic_str("Unknown subaccount"))
+    }
+}
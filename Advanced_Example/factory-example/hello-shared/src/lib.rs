@@ -0,0 +1,94 @@
+// The hello-near contract, written to be shared by every proxy the factory creates.
+// Each proxy forwards its calls here, and gets its own greeting, keyed by the proxy's account.
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet};
+use near_sdk::{env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault, Promise};
+
+const DEFAULT_MESSAGE: &str = "Hello";
+const MAX_MESSAGE_LEN: usize = 256;
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    factory: AccountId,
+    // Proxies the factory created for this implementation, the only ones served
+    proxies: LookupSet<AccountId>,
+    messages: LookupMap<AccountId, String>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // Called by the factory in the batch that deploys this contract
+    #[init]
+    pub fn init(factory: AccountId) -> Self {
+        Self {
+            factory,
+            proxies: LookupSet::new(b"p"),
+            messages: LookupMap::new(b"m"),
+        }
+    }
+
+    // Called by the factory once it created a proxy to this contract
+    pub fn add_proxy(&mut self, account_id: AccountId) {
+        require!(
+            env::predecessor_account_id() == self.factory,
+            "Only the factory can add proxies"
+        );
+        self.proxies.insert(&account_id);
+    }
+
+    pub fn is_proxy(&self, account_id: AccountId) -> bool {
+        self.proxies.contains(&account_id)
+    }
+
+    pub fn get_greeting(&self, instance: AccountId) -> String {
+        self.messages
+            .get(&instance)
+            .unwrap_or_else(|| DEFAULT_MESSAGE.to_string())
+    }
+
+    // `caller_id` is added by the proxy, which is the predecessor. The forwarder the factory
+    // deploys always sets it to its own caller, so it can be trusted once the proxy is.
+    // The caller pays for the storage the greeting adds, the rest of the deposit is returned.
+    #[payable]
+    pub fn set_greeting(&mut self, caller_id: AccountId, message: String) {
+        let instance = self.assert_instance();
+        require!(
+            message.len() <= MAX_MESSAGE_LEN,
+            format!("The message is longer than {MAX_MESSAGE_LEN} bytes")
+        );
+
+        let initial_storage = env::storage_usage();
+        log!(
+            "Saving greeting {} from {} on {}",
+            message,
+            caller_id,
+            instance
+        );
+        self.messages.insert(&instance, &message);
+
+        let added = env::storage_usage().saturating_sub(initial_storage);
+        let cost = env::storage_byte_cost() * added as Balance;
+        let attached = env::attached_deposit();
+        require!(
+            attached >= cost,
+            format!("Attach at least {cost} yoctoNEAR")
+        );
+        if attached > cost {
+            Promise::new(caller_id).transfer(attached - cost);
+        }
+    }
+}
+
+impl Contract {
+    fn assert_instance(&self) -> AccountId {
+        let instance = env::predecessor_account_id();
+        require!(
+            self.proxies.contains(&instance),
+            "Only proxies created by the factory can call this method"
+        );
+        instance
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Contract);
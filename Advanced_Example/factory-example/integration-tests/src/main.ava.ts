@@ -1,5 +1,6 @@
 import { Worker, NearAccount, NEAR } from "near-workspaces";
 import anyTest, { TestFn } from "ava";
+import * as fs from "fs";
 
 const test = anyTest as TestFn<{
   worker: Worker;
@@ -20,6 +21,7 @@ test.beforeEach(async (t) => {
 
   // Deploy factory contract
   await factory.deploy(process.argv[2]);
+  await factory.call(factory, "new", { owner_id: factory.accountId });
 
   // Save state for test runs, it is unique for each test
   t.context.worker = worker;
@@ -48,3 +50,219 @@ test("create_factory_subaccount_and_deploy tests", async (t) => {
 
   t.is(create, true);
 });
+
+test("records the created sub-accounts", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: `sub` },
+    { gas: "80000000000000", attachedDeposit: NEAR.parse("1.24 N").toString() }
+  );
+
+  const defaultVersion = await factory.view("get_default_version");
+  const subaccount: any = await factory.view("get_subaccount", { account_id: `sub.${factory.accountId}` });
+
+  t.is(subaccount.owner, alice.accountId);
+  t.is(subaccount.version, defaultVersion);
+  t.is(await factory.view("total_subaccounts"), 1);
+  t.deepEqual(await factory.view("get_subaccounts_for_owner", { owner: alice.accountId }), [subaccount]);
+});
+
+test("deploys a registered version and initializes it", async (t) => {
+  const { factory, alice, beneficiary } = t.context.accounts;
+  const code = fs.readFileSync("../contract/src/contract/donation.wasm");
+
+  const codeHash = await factory.call(factory, "register_code", code, { gas: "300000000000000" });
+  const versions: any = await factory.view("get_code_versions");
+  t.is(versions.length, 2);
+  t.is(versions[1].code_hash, codeHash);
+
+  const create = await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    {
+      name: `donation`,
+      code_hash: codeHash,
+      init_method: "init",
+      init_args: { beneficiary: beneficiary.accountId },
+    },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("1.5 N").toString() }
+  );
+  t.is(create, true);
+
+  const subaccount: any = await factory.view("get_subaccount", { account_id: `donation.${factory.accountId}` });
+  t.is(subaccount.version, codeHash);
+  t.is(await factory.getSubAccount("donation").view("get_beneficiary"), beneficiary.accountId);
+});
+
+test("failed deployments are not recorded", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  // the default contract has no `init` method
+  const create = await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: `sub`, init_method: "init", init_args: {} },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("1.24 N").toString() }
+  );
+
+  t.is(create, false);
+  t.is(await factory.view("total_subaccounts"), 0);
+
+  const failure: any = await factory.view("get_deployment_failure", { owner: alice.accountId, account_id: `sub.${factory.accountId}` });
+  t.is(failure.owner, alice.accountId);
+  t.is(failure.init_method, "init");
+  t.is(failure.attached, NEAR.parse("1.24 N").toString());
+  t.regex(failure.hint, /init method failed/);
+});
+
+test("names the factory already created are refused before deploying", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  const cost: any = await factory.view("get_deployment_cost", {});
+  await alice.call(factory, "create_factory_subaccount_and_deploy", { name: "sub" },
+    { gas: "300000000000000", attachedDeposit: cost.total });
+
+  const error = await t.throwsAsync(alice.call(factory, "create_factory_subaccount_and_deploy", { name: "sub" },
+    { gas: "300000000000000", attachedDeposit: cost.total }));
+  t.regex(error!.message, /already exists/);
+  t.is(await factory.view("get_deployment_failure", { owner: alice.accountId, account_id: `sub.${factory.accountId}` }), null);
+});
+
+test("charges the storage of the access key as the protocol counts it", async (t) => {
+  const { factory, alice } = t.context.accounts;
+  const publicKey = (await alice.getKey())!.getPublicKey().toString();
+
+  const noKeyCost: any = await factory.view("get_deployment_cost", {});
+  const keyCost: any = await factory.view("get_deployment_cost", { public_key: publicKey });
+  await alice.call(factory, "create_factory_subaccount_and_deploy", { name: "nokey" },
+    { gas: "300000000000000", attachedDeposit: noKeyCost.total });
+  await alice.call(factory, "create_factory_subaccount_and_deploy", { name: "key", public_key: publicKey },
+    { gas: "300000000000000", attachedDeposit: keyCost.total });
+
+  const noKey = await factory.getSubAccount("nokey").accountView();
+  const key = await factory.getSubAccount("key").accountView();
+  t.is(key.storage_usage - noKey.storage_usage, Number(keyCost.account_storage) - Number(noKeyCost.account_storage));
+  t.true(key.storage_usage <= Number(keyCost.account_storage));
+});
+
+test("charges the deployment cost and refunds the rest", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  const cost: any = await factory.view("get_deployment_cost", {});
+  const before = await alice.availableBalance();
+  await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: `sub` },
+    { gas: "80000000000000", attachedDeposit: NEAR.parse("10 N").toString() }
+  );
+  const after = await alice.availableBalance();
+
+  // at most the cost plus some gas was spent, the unused record storage is refunded too
+  t.true(before.sub(after).lt(NEAR.from(cost.total).add(NEAR.parse("0.05 N"))));
+  t.true(before.sub(after).gt(NEAR.from(cost.total).sub(NEAR.parse("0.01 N"))));
+  t.is(await factory.view("total_subaccounts"), 1);
+});
+
+test("rejects deposits below the deployment cost", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  const cost: any = await factory.view("get_deployment_cost", {});
+  await t.throwsAsync(
+    alice.call(
+      factory,
+      "create_factory_subaccount_and_deploy",
+      { name: `sub` },
+      { gas: "80000000000000", attachedDeposit: NEAR.from(cost.total).sub(NEAR.from(1)).toString() }
+    )
+  );
+  t.is(await factory.view("total_subaccounts"), 0);
+});
+
+test("sends the fee to the treasury", async (t) => {
+  const { factory, alice, beneficiary } = t.context.accounts;
+
+  await factory.call(factory, "set_fee", { fee: NEAR.parse("1 N").toString() });
+  await factory.call(factory, "set_treasury", { treasury: beneficiary.accountId });
+
+  const cost: any = await factory.view("get_deployment_cost", {});
+  t.is(cost.fee, NEAR.parse("1 N").toString());
+
+  const before = await beneficiary.availableBalance();
+  await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: `sub` },
+    { gas: "80000000000000", attachedDeposit: cost.total }
+  );
+  const after = await beneficiary.availableBalance();
+
+  t.is(after.sub(before).toString(), NEAR.parse("1 N").toString());
+});
+
+const FT_WASM = "../../../ft/contract/target/wasm32-unknown-unknown/release/ft.wasm";
+const NFT_WASM = "../../../nft/contract/target/wasm32-unknown-unknown/release/nft.wasm";
+
+const FT_METADATA = { spec: "ft-1.0.0", name: "Example Token", symbol: "EXT", decimals: 24 };
+const NFT_METADATA = { spec: "nft-1.0.0", name: "Example NFT", symbol: "EXNFT" };
+
+test("launches a fungible token", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  const codeHash = await factory.call(factory, "register_code", fs.readFileSync(FT_WASM), { gas: "300000000000000" });
+  await factory.call(factory, "set_ft_version", { code_hash: codeHash });
+
+  const create = await alice.call(
+    factory,
+    "create_ft",
+    { name: "token", total_supply: "1000", metadata: FT_METADATA },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("5 N").toString() }
+  );
+  t.is(create, true);
+
+  const token = factory.getSubAccount("token");
+  t.is(await token.view("ft_balance_of", { account_id: alice.accountId }), "1000");
+  t.like(await token.view("ft_metadata"), FT_METADATA);
+});
+
+test("launches a non-fungible token", async (t) => {
+  const { factory, alice, beneficiary } = t.context.accounts;
+
+  const codeHash = await factory.call(factory, "register_code", fs.readFileSync(NFT_WASM), { gas: "300000000000000" });
+  await factory.call(factory, "set_nft_version", { code_hash: codeHash });
+
+  const create = await alice.call(
+    factory,
+    "create_nft",
+    { name: "nft", owner_id: beneficiary.accountId, metadata: NFT_METADATA },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("5 N").toString() }
+  );
+  t.is(create, true);
+
+  t.like(await factory.getSubAccount("nft").view("nft_metadata"), NFT_METADATA);
+});
+
+test("invalid token metadata is rejected before spending the deposit", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  const codeHash = await factory.call(factory, "register_code", fs.readFileSync(FT_WASM), { gas: "300000000000000" });
+  await factory.call(factory, "set_ft_version", { code_hash: codeHash });
+
+  const before = await alice.availableBalance();
+  await t.throwsAsync(
+    alice.call(
+      factory,
+      "create_ft",
+      { name: "token", total_supply: "1000", metadata: { ...FT_METADATA, spec: "ft-0.0.1" } },
+      { gas: "300000000000000", attachedDeposit: NEAR.parse("5 N").toString() }
+    )
+  );
+  const after = await alice.availableBalance();
+
+  // only the gas was spent
+  t.true(before.sub(after).lt(NEAR.parse("0.1 N")));
+  t.is(await factory.view("total_subaccounts"), 0);
+});
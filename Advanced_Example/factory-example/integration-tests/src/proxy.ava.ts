@@ -0,0 +1,149 @@
+import { Worker, NearAccount, NEAR } from "near-workspaces";
+import anyTest, { TestFn } from "ava";
+import * as fs from "fs";
+
+const test = anyTest as TestFn<{
+  worker: Worker;
+  accounts: Record<string, NearAccount>;
+}>;
+
+const PROXY_WASM = "../proxy/target/wasm32-unknown-unknown/release/proxy.wasm";
+const SHARED_WASM = "../hello-shared/target/wasm32-unknown-unknown/release/hello_shared.wasm";
+
+test.beforeEach(async (t) => {
+  // Init the worker and start a Sandbox server
+  const worker = await Worker.init();
+
+  // Get root account
+  const root = worker.rootAccount;
+
+  // Create test accounts
+  const alice = await root.createSubAccount("alice");
+  const factory = await root.createSubAccount("factory");
+
+  // Deploy factory contract
+  await factory.deploy(process.argv[2]);
+  await factory.call(factory, "new", { owner_id: factory.accountId });
+
+  // Register the forwarder, its `init` only stores the implementation (40 bytes per record + 1 byte key)
+  const proxyHash = await factory.call(factory, "register_code", fs.readFileSync(PROXY_WASM), { gas: "300000000000000" });
+  await factory.call(factory, "set_proxy_version", { code_hash: proxyHash });
+  await factory.call(factory, "set_init_storage", { code_hash: proxyHash, init_storage: "41" });
+
+  // Deploy the shared implementation once, as a full copy
+  const sharedHash = await factory.call(factory, "register_code", fs.readFileSync(SHARED_WASM), { gas: "300000000000000" });
+  await factory.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: "hello", code_hash: sharedHash, init_method: "init", init_args: { factory: factory.accountId } },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("5 N").toString() }
+  );
+  const implementation = factory.getSubAccount("hello");
+  await factory.call(factory, "register_implementation", { account_id: implementation.accountId });
+
+  // Save state for test runs, it is unique for each test
+  t.context.worker = worker;
+  t.context.accounts = { factory, alice, implementation };
+});
+
+test.afterEach(async (t) => {
+  await t.context.worker.tearDown().catch((error) => {
+    console.log("Failed tear down the worker:", error);
+  });
+});
+
+test("proxies forward their calls to the implementation", async (t) => {
+  const { factory, alice, implementation } = t.context.accounts;
+
+  const create = await alice.call(
+    factory,
+    "create_proxy_subaccount",
+    { name: "sub", implementation: implementation.accountId },
+    { gas: "100000000000000", attachedDeposit: NEAR.parse("0.1 N").toString() }
+  );
+  t.is(create, true);
+
+  const proxy = factory.getSubAccount("sub");
+  t.is(await proxy.view("get_implementation"), implementation.accountId);
+  const subaccount: any = await factory.view("get_subaccount", { account_id: proxy.accountId });
+  t.is(subaccount.implementation, implementation.accountId);
+
+  // the factory added the proxy to the implementation
+  t.true(await implementation.view("is_proxy", { account_id: proxy.accountId }));
+
+  // `<method> <json args>`, each proxy has its own greeting in the implementation, paid by the caller
+  const greeting = { gas: "100000000000000", attachedDeposit: NEAR.parse("0.01 N").toString() };
+  await alice.call(proxy, "forward", Buffer.from('set_greeting {"message":"Howdy"}'), greeting);
+  t.is(await implementation.view("get_greeting", { instance: proxy.accountId }), "Howdy");
+  t.is(await implementation.view("get_greeting", { instance: `other.${factory.accountId}` }), "Hello");
+
+  // greetings are capped, so a proxy can't fill the implementation's storage
+  const long = JSON.stringify({ message: "a".repeat(257) });
+  const before = (await proxy.balance()).total;
+  await t.throwsAsync(alice.call(proxy, "forward", Buffer.from(`set_greeting ${long}`), greeting));
+
+  // the deposit of the failed call went back to alice, not to the proxy
+  const kept = (await proxy.balance()).total.sub(before);
+  t.true(kept.lt(NEAR.parse("0.001 N")));
+});
+
+test("the implementation only serves the proxies of the factory", async (t) => {
+  const { factory, alice, implementation } = t.context.accounts;
+
+  await t.throwsAsync(alice.call(implementation, "set_greeting", { caller_id: alice.accountId, message: "Howdy" }));
+  await t.throwsAsync(alice.call(implementation, "add_proxy", { account_id: alice.accountId }));
+
+  // other sub-accounts of the factory are not proxies
+  const cost: any = await factory.view("get_deployment_cost", {});
+  await alice.call(factory, "create_factory_subaccount_and_deploy", { name: "full" }, { gas: "80000000000000", attachedDeposit: cost.total });
+  t.false(await implementation.view("is_proxy", { account_id: factory.getSubAccount("full").accountId }));
+});
+
+test("proxies can only point to registered implementations", async (t) => {
+  const { factory, alice } = t.context.accounts;
+
+  await t.throwsAsync(
+    alice.call(
+      factory,
+      "create_proxy_subaccount",
+      { name: "sub", implementation: alice.accountId },
+      { gas: "100000000000000", attachedDeposit: NEAR.parse("0.1 N").toString() }
+    )
+  );
+});
+
+test("benchmark: full copies and proxies", async (t) => {
+  const { factory, alice, implementation } = t.context.accounts;
+
+  const fullCost: any = await factory.view("get_deployment_cost", {});
+  await alice.call(
+    factory,
+    "create_factory_subaccount_and_deploy",
+    { name: "full" },
+    { gas: "80000000000000", attachedDeposit: fullCost.total }
+  );
+
+  const proxyCost: any = await factory.view("get_proxy_deployment_cost", { implementation: implementation.accountId });
+  await alice.call(
+    factory,
+    "create_proxy_subaccount",
+    { name: "proxy", implementation: implementation.accountId },
+    { gas: "100000000000000", attachedDeposit: proxyCost.total }
+  );
+
+  const full = await factory.getSubAccount("full").accountView();
+  const proxy = await factory.getSubAccount("proxy").accountView();
+  const shared = await implementation.accountView();
+
+  const storageCost = (bytes: number) => NEAR.from(NEAR.parse("0.00001 N").muln(bytes).toString()).toHuman();
+  t.log(`full copy: ${full.storage_usage} bytes, ${storageCost(full.storage_usage)}`);
+  t.log(`proxy:     ${proxy.storage_usage} bytes, ${storageCost(proxy.storage_usage)}`);
+  t.log(`shared implementation, paid once: ${shared.storage_usage} bytes, ${storageCost(shared.storage_usage)}`);
+
+  // the deposits asked for cover the real usage
+  t.true(full.storage_usage <= Number(fullCost.account_storage));
+  t.true(proxy.storage_usage <= Number(proxyCost.account_storage));
+
+  // the forwarder is a couple of kilobytes against ~90KB for hello-near
+  t.true(proxy.storage_usage * 20 < full.storage_usage);
+});
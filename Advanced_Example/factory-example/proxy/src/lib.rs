@@ -0,0 +1,279 @@
+//! A forwarder deployed by the factory instead of a full copy of a contract.
+//!
+//! It only stores the account of its implementation, and forwards every call to it:
+//! - `init` takes the implementation's account id as raw input, and can only run once.
+//! - `forward` takes `<method> <json args>` as raw input, and calls `method` on the implementation
+//!   with the same deposit and the remaining gas. `caller_id` is added to the arguments, since the
+//!   implementation only sees the forwarder as predecessor.
+//! - `on_forward` is the callback of a forwarded call with a deposit. The deposit comes back to the
+//!   forwarder when the call fails, so it is returned to the caller.
+//! - `get_implementation` returns the implementation's account id.
+#![cfg_attr(not(test), no_std)]
+
+extern "C" {
+    fn input(register_id: u64);
+    fn read_register(register_id: u64, ptr: u64);
+    fn register_len(register_id: u64) -> u64;
+    fn current_account_id(register_id: u64);
+    fn predecessor_account_id(register_id: u64);
+    fn attached_deposit(balance_ptr: u64);
+    fn prepaid_gas() -> u64;
+    fn used_gas() -> u64;
+    fn storage_read(key_len: u64, key_ptr: u64, register_id: u64) -> u64;
+    fn storage_write(
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+        register_id: u64,
+    ) -> u64;
+    fn promise_create(
+        account_id_len: u64,
+        account_id_ptr: u64,
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        amount_ptr: u64,
+        gas: u64,
+    ) -> u64;
+    fn promise_then(
+        promise_index: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        amount_ptr: u64,
+        gas: u64,
+    ) -> u64;
+    fn promise_result(result_idx: u64, register_id: u64) -> u64;
+    fn promise_batch_create(account_id_len: u64, account_id_ptr: u64) -> u64;
+    fn promise_batch_action_transfer(promise_index: u64, amount_ptr: u64);
+    fn promise_return(promise_idx: u64);
+    fn value_return(value_len: u64, value_ptr: u64);
+    fn panic_utf8(len: u64, ptr: u64) -> !;
+}
+
+const IMPLEMENTATION_KEY: &[u8] = b"i";
+const CALLER_FIELD: &[u8] = b"{\"caller_id\":\"";
+const GAS_FOR_FORWARD: u64 = 5_000_000_000_000; // 5 TGAS
+const GAS_FOR_ON_FORWARD: u64 = 5_000_000_000_000; // 5 TGAS
+const ON_FORWARD: &[u8] = b"on_forward";
+const PROMISE_FAILED: u64 = 2;
+
+const MAX_ACCOUNT_LEN: usize = 64;
+const MAX_INPUT_LEN: usize = 8 * 1024;
+// The input, plus the `caller_id` field
+const MAX_ARGS_LEN: usize = MAX_INPUT_LEN + CALLER_FIELD.len() + MAX_ACCOUNT_LEN + 2;
+// The deposit to return, then the caller
+const MAX_REFUND_LEN: usize = 16 + MAX_ACCOUNT_LEN;
+
+const REGISTER: u64 = 0;
+
+fn fail(msg: &str) -> ! {
+    unsafe { panic_utf8(msg.len() as u64, msg.as_ptr() as u64) }
+}
+
+/// Copies the register into `buf`, failing with `msg` if it does not fit
+fn read_into<'a>(buf: &'a mut [u8], msg: &str) -> &'a [u8] {
+    let len = unsafe { register_len(REGISTER) } as usize;
+    if len > buf.len() {
+        fail(msg);
+    }
+    unsafe { read_register(REGISTER, buf.as_mut_ptr() as u64) };
+    &buf[..len]
+}
+
+fn read_implementation(buf: &mut [u8; MAX_ACCOUNT_LEN]) -> &[u8] {
+    let key = IMPLEMENTATION_KEY;
+    if unsafe { storage_read(key.len() as u64, key.as_ptr() as u64, REGISTER) } == 0 {
+        fail("Not initialized");
+    }
+    read_into(buf, "Invalid implementation")
+}
+
+#[no_mangle]
+pub extern "C" fn init() {
+    let key = IMPLEMENTATION_KEY;
+    if unsafe { storage_read(key.len() as u64, key.as_ptr() as u64, REGISTER) } == 1 {
+        fail("Already initialized");
+    }
+
+    let mut buf = [0u8; MAX_ACCOUNT_LEN];
+    unsafe { input(REGISTER) };
+    let implementation = read_into(&mut buf, "Invalid implementation");
+    if implementation.len() < 2 {
+        fail("Invalid implementation");
+    }
+
+    unsafe {
+        storage_write(
+            key.len() as u64,
+            key.as_ptr() as u64,
+            implementation.len() as u64,
+            implementation.as_ptr() as u64,
+            REGISTER,
+        )
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn forward() {
+    let mut input_buf = [0u8; MAX_INPUT_LEN];
+    unsafe { input(REGISTER) };
+    let input = read_into(&mut input_buf, "Input too long");
+
+    // `<method> <json args>`, the arguments are optional
+    let (method, args) = match input.iter().position(|&b| b == b' ') {
+        Some(i) => (&input[..i], trim(&input[i + 1..])),
+        None => (input, &input[input.len()..]),
+    };
+    if method.is_empty() {
+        fail("Missing method");
+    }
+
+    let mut caller_buf = [0u8; MAX_ACCOUNT_LEN];
+    unsafe { predecessor_account_id(REGISTER) };
+    let caller = read_into(&mut caller_buf, "Invalid caller");
+
+    // {"caller_id":"<caller>", ...args}
+    let mut args_buf = [0u8; MAX_ARGS_LEN];
+    let mut len = 0;
+    for part in [CALLER_FIELD, caller, b"\""] {
+        args_buf[len..len + part.len()].copy_from_slice(part);
+        len += part.len();
+    }
+    let rest = match args {
+        [] => &b"}"[..],
+        [b'{', rest @ ..] if trim(rest).starts_with(b"}") => trim(rest),
+        [b'{', rest @ ..] => {
+            args_buf[len] = b',';
+            len += 1;
+            rest
+        }
+        _ => fail("Arguments must be a JSON object"),
+    };
+    args_buf[len..len + rest.len()].copy_from_slice(rest);
+    len += rest.len();
+
+    let mut implementation_buf = [0u8; MAX_ACCOUNT_LEN];
+    let implementation = read_implementation(&mut implementation_buf);
+
+    let mut deposit = [0u8; 16];
+    unsafe { attached_deposit(deposit.as_mut_ptr() as u64) };
+    let refunds = u128::from_le_bytes(deposit) > 0;
+
+    // a call with a deposit keeps the gas of its callback
+    let reserved = if refunds {
+        GAS_FOR_FORWARD + GAS_FOR_ON_FORWARD
+    } else {
+        GAS_FOR_FORWARD
+    };
+    let gas = unsafe { prepaid_gas() - used_gas() }
+        .checked_sub(reserved)
+        .unwrap_or_else(|| fail("Not enough gas"));
+
+    unsafe {
+        let promise = promise_create(
+            implementation.len() as u64,
+            implementation.as_ptr() as u64,
+            method.len() as u64,
+            method.as_ptr() as u64,
+            len as u64,
+            args_buf.as_ptr() as u64,
+            deposit.as_ptr() as u64,
+            gas,
+        );
+
+        if refunds {
+            // on_forward takes the deposit and the caller as raw input
+            let mut refund_buf = [0u8; MAX_REFUND_LEN];
+            refund_buf[..16].copy_from_slice(&deposit);
+            refund_buf[16..16 + caller.len()].copy_from_slice(caller);
+
+            let mut current_buf = [0u8; MAX_ACCOUNT_LEN];
+            current_account_id(REGISTER);
+            let current = read_into(&mut current_buf, "Invalid account");
+
+            let no_deposit = [0u8; 16];
+            promise_then(
+                promise,
+                current.len() as u64,
+                current.as_ptr() as u64,
+                ON_FORWARD.len() as u64,
+                ON_FORWARD.as_ptr() as u64,
+                (16 + caller.len()) as u64,
+                refund_buf.as_ptr() as u64,
+                no_deposit.as_ptr() as u64,
+                GAS_FOR_ON_FORWARD,
+            );
+        }
+
+        // the caller gets the result of the implementation, the callback runs alongside
+        promise_return(promise);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn on_forward() {
+    let mut current_buf = [0u8; MAX_ACCOUNT_LEN];
+    unsafe { current_account_id(REGISTER) };
+    let current = read_into(&mut current_buf, "Invalid account");
+    let mut predecessor_buf = [0u8; MAX_ACCOUNT_LEN];
+    unsafe { predecessor_account_id(REGISTER) };
+    if read_into(&mut predecessor_buf, "Invalid caller") != current {
+        fail("Method on_forward is private");
+    }
+
+    // the deposit is only lost if the implementation failed
+    if unsafe { promise_result(0, REGISTER) } != PROMISE_FAILED {
+        return;
+    }
+
+    let mut refund_buf = [0u8; MAX_REFUND_LEN];
+    unsafe { input(REGISTER) };
+    let refund = read_into(&mut refund_buf, "Invalid refund");
+    if refund.len() <= 16 {
+        fail("Invalid refund");
+    }
+    let (deposit, caller) = refund.split_at(16);
+
+    unsafe {
+        let promise = promise_batch_create(caller.len() as u64, caller.as_ptr() as u64);
+        promise_batch_action_transfer(promise, deposit.as_ptr() as u64);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_implementation() {
+    let mut buf = [0u8; MAX_ACCOUNT_LEN + 2];
+    let mut implementation_buf = [0u8; MAX_ACCOUNT_LEN];
+    let implementation = read_implementation(&mut implementation_buf);
+
+    // Returned as a JSON string
+    let len = implementation.len() + 2;
+    buf[0] = b'"';
+    buf[1..len - 1].copy_from_slice(implementation);
+    buf[len - 1] = b'"';
+    unsafe { value_return(len as u64, buf.as_ptr() as u64) };
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+#[cfg(not(test))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    fail("Forwarder panicked")
+}
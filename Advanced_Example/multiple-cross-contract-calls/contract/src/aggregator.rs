@@ -0,0 +1,98 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, near_bindgen, require, AccountId, Promise};
+use xcc::{Call, CallResult, GasBudget};
+
+use crate::{Contract, ContractExt, GAS_FOR_OVERHEAD, XCC_GAS};
+
+// Each call costs XCC_GAS and its fees, this keeps the total under the 300 TGas a transaction can attach
+const MAX_CALLS: usize = 18;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ViewCall {
+    pub contract: AccountId,
+    pub method: String,
+    pub args: Option<Value>,
+}
+
+/// The outcome of one call, with either its `value` or the `error`
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AggregatedResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<CallResult<Value>> for AggregatedResult {
+    fn from(result: CallResult<Value>) -> Self {
+        match result {
+            Ok(value) => Self {
+                ok: true,
+                value: Some(value),
+                error: None,
+            },
+            Err(err) => Self {
+                ok: false,
+                value: None,
+                error: Some(err.to_string()),
+            },
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Calls every contract in parallel, and returns their results in the same order.
+    /// A failed call does not prevent the others from being returned.
+    pub fn aggregate(&mut self, calls: Vec<ViewCall>) -> Promise {
+        require!(!calls.is_empty(), "Nothing to call");
+        require!(
+            calls.len() <= MAX_CALLS,
+            format!("At most {MAX_CALLS} calls can be aggregated")
+        );
+        Self::aggregate_gas(calls.len()).require_prepaid("aggregate");
+
+        let promises = calls.into_iter().map(|call| {
+            Call::new(call.contract, &call.method)
+                .args_json(&call.args.unwrap_or_else(|| json!({})))
+                .with_gas(XCC_GAS)
+                .promise()
+        });
+
+        // Join all promises and chain a callback to collect their results
+        promises
+            .reduce(Promise::and)
+            .unwrap()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(XCC_GAS)
+                    .aggregate_callback(),
+            )
+    }
+
+    #[private]
+    pub fn aggregate_callback(&self) -> Vec<AggregatedResult> {
+        (0..env::promise_results_count())
+            .map(|index| xcc::promise_result::<Value>(index).into())
+            .collect()
+    }
+
+    pub fn aggregate_required_gas(&self, calls: u64) -> U64 {
+        U64(Self::aggregate_gas(calls as usize).total().0)
+    }
+}
+
+impl Contract {
+    fn aggregate_gas(calls: usize) -> GasBudget {
+        // The calls, and the callback
+        GasBudget::new()
+            .calls(XCC_GAS, calls as u64)
+            .calls(XCC_GAS, 1)
+            .add(GAS_FOR_OVERHEAD)
+    }
+}
@@ -1,21 +1,34 @@
-use near_sdk::{env, log, near_bindgen, serde_json::json, Promise, PromiseError};
+use near_sdk::serde_json::json;
+use near_sdk::json_types::U64;
+use near_sdk::{env, log, near_bindgen, Promise, PromiseError};
+use xcc::{Call, GasBudget, LogError};
 
-use crate::{Contract, ContractExt, NO_ARGS, NO_DEPOSIT, XCC_GAS};
+use crate::{Contract, ContractExt, GAS_FOR_OVERHEAD, XCC_GAS};
 
 #[near_bindgen]
 impl Contract {
     pub fn batch_actions(&mut self) -> Promise {
-        let hi = json!({ "greeting": "hi" }).to_string().into_bytes();
-        let bye = json!({ "greeting": "bye" }).to_string().into_bytes();
+        Self::batch_actions_gas().require_prepaid("batch_actions");
+
+        let set_greeting = |greeting: &str| {
+            Call::new(self.hello_account.clone(), "set_greeting")
+                .args_json(&json!({ "greeting": greeting }))
+                .with_gas(XCC_GAS)
+        };
+        let get_greeting = Call::new(self.hello_account.clone(), "get_greeting").with_gas(XCC_GAS);
 
         // You can create one transaction calling multiple methods
         // on a same contract
-        Promise::new(self.hello_account.clone())
-            .function_call("set_greeting".to_owned(), hi, NO_DEPOSIT, XCC_GAS)
-            .function_call("get_greeting".to_owned(), NO_ARGS, NO_DEPOSIT, XCC_GAS)
-            .function_call("set_greeting".to_owned(), bye, NO_DEPOSIT, XCC_GAS)
-            .function_call("get_greeting".to_owned(), NO_ARGS, NO_DEPOSIT, XCC_GAS)
-            .then(Self::ext(env::current_account_id()).batch_actions_callback())
+        let batch = set_greeting("hi").promise();
+        let batch = get_greeting.add_to(batch);
+        let batch = set_greeting("bye").add_to(batch);
+        get_greeting
+            .add_to(batch)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(XCC_GAS)
+                    .batch_actions_callback(),
+            )
     }
 
     #[private]
@@ -23,13 +36,26 @@ impl Contract {
         &self,
         #[callback_result] last_result: Result<String, PromiseError>,
     ) -> String {
-        // The callback only has access to the last action's result
-        if let Ok(result) = last_result {
-            log!(format!("The last result is {result}"));
-            result
-        } else {
-            log!("The batch call failed and all calls got reverted");
-            "".to_string()
-        }
+        // The callback only has access to the last action's result,
+        // if any call failed all of them got reverted
+        let result = xcc::from_callback(last_result)
+            .log_error("the batch")
+            .unwrap_or_default();
+        log!(format!("The last result is {result}"));
+        result
+    }
+
+    pub fn batch_actions_required_gas(&self) -> U64 {
+        U64(Self::batch_actions_gas().total().0)
+    }
+}
+
+impl Contract {
+    fn batch_actions_gas() -> GasBudget {
+        // 4 calls in the batch, and the callback
+        GasBudget::new()
+            .calls(XCC_GAS, 4)
+            .calls(XCC_GAS, 1)
+            .add(GAS_FOR_OVERHEAD)
     }
 }
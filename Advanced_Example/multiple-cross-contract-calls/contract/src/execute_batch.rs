@@ -0,0 +1,195 @@
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::Value;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PublicKey};
+use xcc::{CallError, GasBudget, LogError, GAS_FOR_CALL_FEES};
+
+use crate::{Contract, ContractExt, GAS_FOR_OVERHEAD, XCC_GAS};
+
+/// Restricts an access key to calling `method_names` (any method if empty) on `receiver_id`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FunctionCallPermission {
+    // yoctoNEAR the key can spend on gas fees, unlimited if not given
+    pub allowance: Option<U128>,
+    pub receiver_id: AccountId,
+    pub method_names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "type")]
+pub enum Action {
+    FunctionCall {
+        method_name: String,
+        args: Base64VecU8,
+        deposit: U128,
+        gas: U64,
+    },
+    Transfer {
+        deposit: U128,
+    },
+    // A full access key, unless `permission` is given
+    AddKey {
+        public_key: PublicKey,
+        permission: Option<FunctionCallPermission>,
+    },
+    DeleteKey {
+        public_key: PublicKey,
+    },
+    Deploy {
+        code: Base64VecU8,
+    },
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::FunctionCall { .. } => "FunctionCall",
+            Action::Transfer { .. } => "Transfer",
+            Action::AddKey { .. } => "AddKey",
+            Action::DeleteKey { .. } => "DeleteKey",
+            Action::Deploy { .. } => "Deploy",
+        }
+    }
+
+    // The protocol only lets an account manage its own keys and code
+    fn is_self_only(&self) -> bool {
+        matches!(
+            self,
+            Action::AddKey { .. } | Action::DeleteKey { .. } | Action::Deploy { .. }
+        )
+    }
+
+    // A transfer to an existing account can't fail
+    fn can_fail(&self) -> bool {
+        !matches!(self, Action::Transfer { .. })
+    }
+
+    fn gas(&self) -> Gas {
+        match self {
+            Action::FunctionCall { gas, .. } => Gas(gas.0),
+            _ => Gas(0),
+        }
+    }
+
+    fn add_to(self, batch: Promise) -> Promise {
+        match self {
+            Action::FunctionCall {
+                method_name,
+                args,
+                deposit,
+                gas,
+            } => batch.function_call(method_name, args.0, deposit.0, Gas(gas.0)),
+            Action::Transfer { deposit } => batch.transfer(deposit.0),
+            Action::AddKey {
+                public_key,
+                permission: None,
+            } => batch.add_full_access_key(public_key),
+            Action::AddKey {
+                public_key,
+                permission: Some(permission),
+            } => batch.add_access_key(
+                public_key,
+                // 0 means unlimited
+                permission.allowance.map(|a| a.0).unwrap_or(0),
+                permission.receiver_id,
+                permission.method_names.join(","),
+            ),
+            Action::DeleteKey { public_key } => batch.delete_key(public_key),
+            Action::Deploy { code } => batch.deploy_contract(code.0),
+        }
+    }
+}
+
+/// Why a batch failed. All of its actions were reverted.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BatchError {
+    pub error: CallError,
+    // The failing action, when only one action of the batch could fail
+    pub index: Option<u32>,
+    // The actions that could fail. Callbacks are not told which action of a batch failed, the
+    // receipt's outcome is: the first action fails if `receiver_id` doesn't exist, and all
+    // actions but transfers can fail on their own
+    pub candidates: Vec<u32>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Executes `actions` on `receiver_id` in a single batch: they run in order, and if one
+    /// fails all of them are reverted. Returns the result of the last action, or a `BatchError`.
+    /// The deposits of the actions are paid by this contract, so only allowed accounts can call
+    /// it. Batches on this contract itself run as the contract, so only the owner, the contract's
+    /// own account, can execute them.
+    pub fn execute_batch(&mut self, receiver_id: AccountId, actions: Vec<Action>) -> Promise {
+        let caller = env::predecessor_account_id();
+        let is_owner = caller == env::current_account_id();
+        require!(
+            is_owner || self.allowlist.contains(&caller),
+            "Not allowed to execute batches"
+        );
+        require!(!actions.is_empty(), "No actions to execute");
+
+        let is_self = receiver_id == env::current_account_id();
+        require!(
+            is_owner || !is_self,
+            "Only the owner can execute batches on this contract"
+        );
+        let mut budget = GasBudget::new().calls(XCC_GAS, 1).add(GAS_FOR_OVERHEAD);
+        let mut candidates = vec![];
+        for (index, action) in actions.iter().enumerate() {
+            require!(
+                is_self || !action.is_self_only(),
+                format!(
+                    "Action {index}: {} can only be executed on this contract",
+                    action.name()
+                )
+            );
+            if index == 0 || action.can_fail() {
+                candidates.push(index as u32);
+            }
+            budget = budget.add(action.gas() + GAS_FOR_CALL_FEES);
+        }
+        budget.require_prepaid("execute_batch");
+
+        actions
+            .into_iter()
+            .fold(Promise::new(receiver_id), |batch, action| {
+                action.add_to(batch)
+            })
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(XCC_GAS)
+                    .execute_batch_callback(candidates),
+            )
+    }
+
+    #[private]
+    pub fn execute_batch_callback(&self, candidates: Vec<u32>) -> Result<Value, BatchError> {
+        // Only the last action's result is available
+        xcc::promise_result::<Value>(0)
+            .log_error("the batch")
+            .map_err(|error| BatchError {
+                error,
+                index: match candidates[..] {
+                    [index] => Some(index),
+                    _ => None,
+                },
+                candidates,
+            })
+    }
+
+    #[private]
+    pub fn allow(&mut self, account_id: AccountId) {
+        self.allowlist.insert(&account_id);
+    }
+
+    #[private]
+    pub fn disallow(&mut self, account_id: AccountId) {
+        self.allowlist.remove(&account_id);
+    }
+
+    pub fn get_allowlist(&self) -> Vec<AccountId> {
+        self.allowlist.to_vec()
+    }
+}
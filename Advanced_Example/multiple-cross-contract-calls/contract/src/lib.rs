@@ -1,13 +1,16 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{near_bindgen, AccountId, Balance, Gas};
+use near_sdk::collections::UnorderedSet;
+use near_sdk::{near_bindgen, AccountId, Gas};
 
+mod aggregator;
 mod batch_actions;
+mod execute_batch;
 mod multiple_contracts;
 mod similar_contracts;
 
-const XCC_GAS: Gas = Gas(10u64.pow(13));
-const NO_DEPOSIT: Balance = 0;
-const NO_ARGS: Vec<u8> = vec![];
+const XCC_GAS: Gas = xcc::tgas(10);
+// Gas used by the methods themselves, besides the fees of the calls they schedule
+const GAS_FOR_OVERHEAD: Gas = xcc::tgas(5);
 const HELLO_CONTRACT: &str = "hello.near-examples.testnet";
 const COUNTER_CONTRACT: &str = "counter.near-examples.testnet";
 const GUESTBOOK_CONTRACT: &str = "guestbook.near-examples.testnet";
@@ -19,6 +22,8 @@ pub struct Contract {
     pub hello_account: AccountId,
     pub counter_account: AccountId,
     pub guestbook_account: AccountId,
+    // Accounts allowed to call `execute_batch`
+    pub allowlist: UnorderedSet<AccountId>,
 }
 
 impl Default for Contract {
@@ -27,6 +32,7 @@ impl Default for Contract {
             hello_account: HELLO_CONTRACT.parse().unwrap(),
             counter_account: COUNTER_CONTRACT.parse().unwrap(),
             guestbook_account: GUESTBOOK_CONTRACT.parse().unwrap(),
+            allowlist: UnorderedSet::new(b"a"),
         }
     }
 }
@@ -44,6 +50,9 @@ impl Contract {
             hello_account,
             counter_account,
             guestbook_account,
+            allowlist: UnorderedSet::new(b"a"),
         }
     }
 }
+
+nep_330::impl_contract_source_metadata!(Contract);
@@ -1,8 +1,10 @@
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json::json;
+use near_sdk::json_types::U64;
 use near_sdk::{env, log, near_bindgen, AccountId, Promise, PromiseError};
+use xcc::{Call, GasBudget, LogError};
 
-use crate::{Contract, ContractExt, NO_ARGS, XCC_GAS};
+use crate::{Contract, ContractExt, GAS_FOR_OVERHEAD, XCC_GAS};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -16,33 +18,23 @@ pub struct PostedMessage {
 impl Contract {
     /// A method which calls different contracts via cross contract function calls.
     pub fn multiple_contracts(&mut self) -> Promise {
+        Self::multiple_contracts_gas().require_prepaid("multiple_contracts");
+
         // We create a promise that calls the `get_greeting` function on the HELLO_CONTRACT
-        let hello_promise = Promise::new(self.hello_account.clone()).function_call(
-            "get_greeting".to_owned(),
-            NO_ARGS,
-            0,
-            XCC_GAS,
-        );
+        let hello_promise = Call::new(self.hello_account.clone(), "get_greeting")
+            .with_gas(XCC_GAS)
+            .promise();
 
         // We create a promise that calls the `get_num` function on the COUNTER_CONTRACT
-        let counter_promise = Promise::new(self.counter_account.clone()).function_call(
-            "get_num".to_owned(),
-            NO_ARGS,
-            0,
-            XCC_GAS,
-        );
+        let counter_promise = Call::new(self.counter_account.clone(), "get_num")
+            .with_gas(XCC_GAS)
+            .promise();
 
         // We create a promise that calls the `get_messages` function on the GUESTBOOK_CONTRACT
-        let args = json!({ "from_index": "0", "limit": 2 })
-            .to_string()
-            .into_bytes();
-
-        let guestbook_promise = Promise::new(self.guestbook_account.clone()).function_call(
-            "get_messages".to_owned(),
-            args,
-            0,
-            XCC_GAS,
-        );
+        let guestbook_promise = Call::new(self.guestbook_account.clone(), "get_messages")
+            .args_json(&json!({ "from_index": "0", "limit": 2 }))
+            .with_gas(XCC_GAS)
+            .promise();
 
         // We join all promises and chain a callback to collect their results.
         hello_promise
@@ -62,31 +54,33 @@ impl Contract {
         #[callback_result] counter_result: Result<i8, PromiseError>,
         #[callback_result] guestbook_result: Result<Vec<PostedMessage>, PromiseError>,
     ) -> (String, i8, Vec<PostedMessage>) {
-        // The callback has access to the result of the 3 calls
-        let greeting = if let Ok(result) = hello_result {
-            log!(format!("HelloNear says {result}"));
-            result
-        } else {
-            log!("The call to HelloNear failed");
-            "".to_string()
-        };
+        // The callback has access to the result of the 3 calls,
+        // the failed ones are logged and replaced by a default value
+        let greeting = xcc::from_callback(hello_result).log_error("HelloNear");
+        let counter = xcc::from_callback(counter_result).log_error("Counter");
+        let messages = xcc::from_callback(guestbook_result).log_error("GuestBook");
 
-        let counter = if let Ok(result) = counter_result {
-            log!(format!("Counter is {result}"));
-            result
-        } else {
-            log!("The call to Counter failed");
-            0
-        };
+        log!(format!(
+            "HelloNear says {greeting:?}, Counter is {counter:?}, the messages are {messages:?}"
+        ));
+        (
+            greeting.unwrap_or_default(),
+            counter.unwrap_or_default(),
+            messages.unwrap_or_default(),
+        )
+    }
 
-        let messages = if let Ok(result) = guestbook_result {
-            log!(format!("The messages are {result:?}"));
-            result
-        } else {
-            log!("The call to GuestBook failed");
-            vec![]
-        };
+    pub fn multiple_contracts_required_gas(&self) -> U64 {
+        U64(Self::multiple_contracts_gas().total().0)
+    }
+}
 
-        (greeting, counter, messages)
+impl Contract {
+    fn multiple_contracts_gas() -> GasBudget {
+        // 3 calls, and the callback
+        GasBudget::new()
+            .calls(XCC_GAS, 3)
+            .calls(XCC_GAS, 1)
+            .add(GAS_FOR_OVERHEAD)
     }
 }
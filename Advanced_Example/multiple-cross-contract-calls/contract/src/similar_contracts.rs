@@ -1,21 +1,26 @@
 use near_sdk::serde_json::json;
-use near_sdk::{env, log, near_bindgen, Promise, PromiseResult};
+use near_sdk::json_types::U64;
+use near_sdk::{env, log, near_bindgen, Promise};
+use xcc::{Call, GasBudget, LogError};
 
-use crate::{Contract, ContractExt, NO_ARGS, XCC_GAS};
+use crate::{Contract, ContractExt, GAS_FOR_OVERHEAD, XCC_GAS};
 
 #[near_bindgen]
 impl Contract {
     fn promise_set_get(&self, message: &str) -> Promise {
         // Aux method to create a batch transaction calling
         // set_message and get_message in the HELLO CONTRACT
-        let args = json!({ "greeting": message }).to_string().into_bytes();
+        let set_greeting = Call::new(self.hello_account.clone(), "set_greeting")
+            .args_json(&json!({ "greeting": message }))
+            .with_gas(XCC_GAS);
+        let get_greeting = Call::new(self.hello_account.clone(), "get_greeting").with_gas(XCC_GAS);
 
-        Promise::new(self.hello_account.clone())
-            .function_call("set_greeting".to_owned(), args, 0, XCC_GAS)
-            .function_call("get_greeting".to_owned(), NO_ARGS, 0, XCC_GAS)
+        get_greeting.add_to(set_greeting.promise())
     }
 
     pub fn similar_contracts(&mut self) -> Promise {
+        Self::similar_contracts_gas().require_prepaid("similar_contracts");
+
         // Create promises to call 3 contracts that return the same type
         // For simplicity here we call the same contract
         let hello_one = self.promise_set_get("hi");
@@ -34,28 +39,27 @@ impl Contract {
     pub fn similar_contracts_callback(&self) -> Vec<String> {
         (0..3)
             .filter_map(|index| {
-                // env::promise_result(i) has the result of the i-th call
-                let result = env::promise_result(index);
-                match result {
-                    PromiseResult::Failed => {
-                        log!(format!("Promise number {index} failed."));
-                        None
-                    }
-                    PromiseResult::NotReady => {
-                        log!(format!("Promise number {index} is not ready yet."));
-                        None
-                    }
-                    PromiseResult::Successful(value) => {
-                        if let Ok(message) = near_sdk::serde_json::from_slice::<String>(&value) {
-                            log!(format!("Call {index} returned: {message}"));
-                            Some(message)
-                        } else {
-                            log!(format!("Error deserializing call {index} result."));
-                            None
-                        }
-                    }
-                }
+                // xcc::promise_result(i) has the result of the i-th call
+                let message = xcc::promise_result::<String>(index)
+                    .log_error(&format!("promise number {index}"))
+                    .ok()?;
+                log!(format!("Call {index} returned: {message}"));
+                Some(message)
             })
             .collect()
     }
+
+    pub fn similar_contracts_required_gas(&self) -> U64 {
+        U64(Self::similar_contracts_gas().total().0)
+    }
+}
+
+impl Contract {
+    fn similar_contracts_gas() -> GasBudget {
+        // 3 batches of 2 calls, and the callback
+        GasBudget::new()
+            .calls(XCC_GAS, 6)
+            .calls(XCC_GAS, 1)
+            .add(GAS_FOR_OVERHEAD)
+    }
 }
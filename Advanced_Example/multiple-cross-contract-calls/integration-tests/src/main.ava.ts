@@ -1,4 +1,4 @@
-import { Worker, NearAccount } from "near-workspaces";
+import { Worker, NearAccount, NEAR, KeyPair } from "near-workspaces";
 import anyTest, { TestFn } from "ava";
 
 const test = anyTest as TestFn<{
@@ -8,6 +8,8 @@ const test = anyTest as TestFn<{
 
 type PremiumMessage = { premium: boolean; sender: string; text: string };
 
+const TGAS = 10 ** 12;
+
 test.beforeEach(async (t) => {
   // Init the worker and start a Sandbox server
   const worker = await Worker.init();
@@ -112,3 +114,169 @@ test("batch_actions", async (t) => {
 
   t.deepEqual(result, "bye");
 });
+
+test("fails early without enough gas", async (t) => {
+  const { xcc, alice } = t.context.accounts;
+
+  for (const method of ["batch_actions", "multiple_contracts", "similar_contracts"]) {
+    // a few hundred TGas fit in a number
+    const required = Number(await xcc.view(`${method}_required_gas`));
+    const tooLittle = (required - TGAS).toString();
+
+    const error = await t.throwsAsync(alice.call(xcc, method, {}, { gas: tooLittle }));
+    t.regex(error!.message, new RegExp(`${method} needs at least ${required / TGAS} TGas`));
+  }
+});
+
+test("the required gas is enough", async (t) => {
+  const { xcc, alice } = t.context.accounts;
+
+  const required: string = await xcc.view("similar_contracts_required_gas");
+  const results = await alice.call(xcc, "similar_contracts", {}, { gas: required });
+
+  t.deepEqual(results, ["hi", "howdy", "bye"]);
+});
+
+test("aggregates any number of calls", async (t) => {
+  const { xcc, alice, helloNear, counter, guestBook } = t.context.accounts;
+
+  await alice.call(helloNear, "set_greeting", { greeting: "Howdy" });
+  await alice.call(counter, "increment", {});
+
+  const results = await alice.call(
+    xcc,
+    "aggregate",
+    {
+      calls: [
+        { contract: helloNear.accountId, method: "get_greeting" },
+        { contract: counter.accountId, method: "get_num" },
+        { contract: counter.accountId, method: "does_not_exist" },
+        { contract: guestBook.accountId, method: "get_messages", args: { from_index: "0", limit: 2 } },
+      ],
+    },
+    { gas: "300000000000000" }
+  );
+
+  // the failed call does not hide the others
+  t.deepEqual(results, [
+    { ok: true, value: "Howdy" },
+    { ok: true, value: 1 },
+    { ok: false, error: "the call failed" },
+    { ok: true, value: [] },
+  ]);
+});
+
+const base64Json = (args: object) => Buffer.from(JSON.stringify(args)).toString("base64");
+
+test("only allowed accounts can execute batches", async (t) => {
+  const { xcc, alice, helloNear } = t.context.accounts;
+
+  const actions = [{ type: "Transfer", deposit: NEAR.parse("1 N").toString() }];
+  await t.throwsAsync(alice.call(xcc, "execute_batch", { receiver_id: helloNear.accountId, actions }, { gas: "300000000000000" }));
+
+  await xcc.call(xcc, "allow", { account_id: alice.accountId });
+  t.deepEqual(await xcc.view("get_allowlist"), [alice.accountId]);
+});
+
+test("executes a batch and returns the last result", async (t) => {
+  const { xcc, alice, helloNear } = t.context.accounts;
+  await xcc.call(xcc, "allow", { account_id: alice.accountId });
+
+  const before = await helloNear.availableBalance();
+  const result = await alice.call(
+    xcc,
+    "execute_batch",
+    {
+      receiver_id: helloNear.accountId,
+      actions: [
+        { type: "FunctionCall", method_name: "set_greeting", args: base64Json({ greeting: "hi" }), deposit: "0", gas: "10000000000000" },
+        { type: "Transfer", deposit: NEAR.parse("1 N").toString() },
+        { type: "FunctionCall", method_name: "get_greeting", args: "", deposit: "0", gas: "10000000000000" },
+      ],
+    },
+    { gas: "300000000000000" }
+  );
+  const after = await helloNear.availableBalance();
+
+  t.deepEqual(result, { Ok: "hi" });
+  t.true(after.sub(before).gte(NEAR.parse("1 N")));
+});
+
+test("a failed action reverts the whole batch", async (t) => {
+  const { xcc, alice, helloNear } = t.context.accounts;
+  await xcc.call(xcc, "allow", { account_id: alice.accountId });
+
+  const greeting = await helloNear.view("get_greeting");
+  const result = await alice.call(
+    xcc,
+    "execute_batch",
+    {
+      receiver_id: helloNear.accountId,
+      actions: [
+        { type: "FunctionCall", method_name: "set_greeting", args: base64Json({ greeting: "bye" }), deposit: "0", gas: "10000000000000" },
+        { type: "FunctionCall", method_name: "does_not_exist", args: "", deposit: "0", gas: "10000000000000" },
+      ],
+    },
+    { gas: "300000000000000" }
+  );
+
+  // either call could have failed
+  t.deepEqual(result, { Err: { error: "failed", index: null, candidates: [0, 1] } });
+  t.is(await helloNear.view("get_greeting"), greeting);
+});
+
+test("reports the failing action when only one can fail", async (t) => {
+  const { xcc, alice, helloNear } = t.context.accounts;
+  await xcc.call(xcc, "allow", { account_id: alice.accountId });
+
+  const result = await alice.call(
+    xcc,
+    "execute_batch",
+    {
+      receiver_id: helloNear.accountId,
+      actions: [{ type: "FunctionCall", method_name: "does_not_exist", args: "", deposit: "0", gas: "10000000000000" }],
+    },
+    { gas: "300000000000000" }
+  );
+
+  t.deepEqual(result, { Err: { error: "failed", index: 0, candidates: [0] } });
+});
+
+test("manages the contract's own keys", async (t) => {
+  const { xcc, alice, helloNear } = t.context.accounts;
+  await xcc.call(xcc, "allow", { account_id: alice.accountId });
+
+  const publicKey = KeyPair.fromRandom("ed25519").getPublicKey().toString();
+  const hasKey = async () => (await xcc.viewAccessKeys()).keys.some((key: any) => key.public_key === publicKey);
+  const addKey = {
+    receiver_id: xcc.accountId,
+    actions: [{ type: "AddKey", public_key: publicKey, permission: { receiver_id: helloNear.accountId, method_names: ["set_greeting"] } }],
+  };
+
+  // allowed accounts can't take over the contract
+  const denied = await t.throwsAsync(alice.call(xcc, "execute_batch", addKey, { gas: "300000000000000" }));
+  t.regex(denied!.message, /Only the owner can execute batches on this contract/);
+  t.false(await hasKey());
+
+  await xcc.call(xcc, "execute_batch", addKey, { gas: "300000000000000" });
+  t.true(await hasKey());
+
+  await xcc.call(
+    xcc,
+    "execute_batch",
+    { receiver_id: xcc.accountId, actions: [{ type: "DeleteKey", public_key: publicKey }] },
+    { gas: "300000000000000" }
+  );
+  t.false(await hasKey());
+
+  // keys and code of other accounts can not be managed, the failing action is reported
+  const error = await t.throwsAsync(
+    alice.call(
+      xcc,
+      "execute_batch",
+      { receiver_id: helloNear.accountId, actions: [{ type: "Transfer", deposit: "1" }, { type: "AddKey", public_key: publicKey }] },
+      { gas: "300000000000000" }
+    )
+  );
+  t.regex(error!.message, /Action 1: AddKey can only be executed on this contract/);
+});
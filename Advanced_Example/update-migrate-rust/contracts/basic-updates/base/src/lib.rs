@@ -1,3 +1,4 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Vector;
 use near_sdk::json_types::U128;
@@ -20,6 +21,7 @@ pub struct PostedMessage {
 pub struct GuestBook {
     messages: Vector<PostedMessage>,
     payments: Vector<Balance>,
+    guard: MessageGuard,
 }
 
 impl Default for GuestBook {
@@ -27,18 +29,23 @@ impl Default for GuestBook {
         Self {
             messages: Vector::new(b"m"),
             payments: Vector::new(b"p"),
+            guard: MessageGuard::new(b"g"),
         }
     }
 }
 
 #[near_bindgen]
 impl GuestBook {
+    /// Premium messages keep the attached deposit as their payment, the others only pay
+    /// for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
-        let premium = payment >= POINT_ONE;
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
 
+        let payment = if premium { env::attached_deposit() } else { 0 };
         let message = PostedMessage {
             premium,
             sender,
@@ -46,6 +53,8 @@ impl GuestBook {
         };
         self.messages.push(&message);
         self.payments.push(&payment);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PostedMessage> {
@@ -69,3 +78,5 @@ impl GuestBook {
             .collect()
     }
 }
+
+nep_330::impl_contract_source_metadata!(GuestBook);
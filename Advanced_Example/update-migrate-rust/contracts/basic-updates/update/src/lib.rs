@@ -1,12 +1,19 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Vector;
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::Serialize;
-use near_sdk::{env, near_bindgen, AccountId, Balance};
+use near_sdk::{env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault};
+use versioned::VersionedState;
 
+use migrate::{Migration, MigrationStatus, VersionedGuestBook};
 mod migrate;
 
 const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
+const DEFAULT_MIGRATION_LIMIT: u64 = 100;
+// The base contract keeps its messages under `m`, new messages get their own prefix
+const MESSAGES_PREFIX: &[u8] = b"n";
+const GUARD_PREFIX: &[u8] = b"g";
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
@@ -19,26 +26,41 @@ pub struct PostedMessage {
 }
 
 #[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct GuestBook {
     messages: Vector<PostedMessage>,
+    migration: Option<Migration>,
+    guard: MessageGuard,
 }
 
-impl Default for GuestBook {
-    fn default() -> Self {
+#[near_bindgen]
+impl GuestBook {
+    /// Initializes a new guest book, use `migrate` to update the base contract instead
+    #[init]
+    pub fn new() -> Self {
+        versioned::write_state_version(VersionedGuestBook::VERSION);
         Self {
-            messages: Vector::new(b"m"),
+            messages: Vector::new(MESSAGES_PREFIX),
+            migration: None,
+            guard: MessageGuard::new(GUARD_PREFIX),
         }
     }
-}
 
-#[near_bindgen]
-impl GuestBook {
+    /// Premium messages keep the attached deposit as their payment, the others only pay
+    /// for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
+        require!(
+            self.migration.is_none(),
+            "The messages are being migrated, try again later"
+        );
+
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
-        let premium = payment >= POINT_ONE;
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
+
+        let payment = if premium { env::attached_deposit() } else { 0 };
         let message = PostedMessage {
             payment,
             sender,
@@ -46,15 +68,67 @@ impl GuestBook {
             text,
         };
         self.messages.push(&message);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PostedMessage> {
-        let from = u128::from(from_index.unwrap_or(U128(0)));
+        let from = u128::from(from_index.unwrap_or(U128(0))) as u64;
 
-        self.messages
-            .iter()
-            .skip(from as usize)
+        (from..self.total_messages())
             .take(limit.unwrap_or(10) as usize)
+            .filter_map(|idx| self.internal_get_message(idx))
             .collect()
     }
+
+    pub fn total_messages(&self) -> u64 {
+        match &self.migration {
+            Some(migration) => self.messages.len() + migration.remaining(),
+            None => self.messages.len(),
+        }
+    }
+
+    /// Migrates at most `limit` messages of the previous version and removes them from the
+    /// old storage, returns whether all of them were migrated. Anyone can call it until it
+    /// returns `true`.
+    pub fn migrate_messages(&mut self, limit: Option<u64>) -> bool {
+        let migration = self
+            .migration
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("No migration in progress"));
+
+        let messages = &mut self.messages;
+        let done = migration.advance(limit.unwrap_or(DEFAULT_MIGRATION_LIMIT), |message| {
+            messages.push(&message);
+        });
+
+        if done {
+            self.migration = None;
+            log!("Migrated {} messages", self.messages.len());
+        }
+        done
+    }
+
+    pub fn migration_status(&self) -> MigrationStatus {
+        MigrationStatus {
+            in_progress: self.migration.is_some(),
+            migrated: U64(self.messages.len()),
+            total: U64(self.total_messages()),
+        }
+    }
 }
+
+impl GuestBook {
+    // While migrating, the first messages are already in `messages` and the rest
+    // are still in the previous format
+    fn internal_get_message(&self, idx: u64) -> Option<PostedMessage> {
+        if idx < self.messages.len() {
+            return self.messages.get(idx);
+        }
+        self.migration
+            .as_ref()
+            .and_then(|migration| migration.get(idx))
+    }
+}
+
+nep_330::impl_contract_source_metadata!(GuestBook);
@@ -1,4 +1,9 @@
 use crate::*;
+use versioned::{MigrationCursor, VersionedState};
+
+// Prefixes of the vectors of the base contract
+const OLD_MESSAGES_PREFIX: &[u8] = b"m";
+const OLD_PAYMENTS_PREFIX: &[u8] = b"p";
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
@@ -9,37 +14,122 @@ pub struct OldPostedMessage {
     pub text: String,
 }
 
-#[derive(BorshDeserialize, BorshSerialize)]
+/// The state of the base contract. The guard was added to it after it was published, so the
+/// state of a published guest book ends after its payments and gets an empty guard.
 pub struct OldState {
     messages: Vector<OldPostedMessage>,
     payments: Vector<Balance>,
+    guard: MessageGuard,
 }
 
-#[near_bindgen]
-impl GuestBook {
-    #[private]
-    #[init(ignore_state)]
-    pub fn migrate() -> Self {
-        // retrieve the current state from the contract
-        let old_state: OldState = env::state_read().expect("failed");
-
-        // iterate through the state migrating it to the new version
-        let mut new_messages: Vector<PostedMessage> = Vector::new(b"p");
-
-        for (idx, posted) in old_state.messages.iter().enumerate() {
-            let payment = old_state.payments.get(idx as u64).unwrap_or(0);
-
-            new_messages.push(&PostedMessage {
-                payment,
-                premium: posted.premium,
-                sender: posted.sender,
-                text: posted.text,
-            })
-        }
+impl BorshDeserialize for OldState {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let messages = BorshDeserialize::deserialize(buf)?;
+        let payments = BorshDeserialize::deserialize(buf)?;
+        let guard = if buf.is_empty() {
+            MessageGuard::new(GUARD_PREFIX)
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        Ok(Self {
+            messages,
+            payments,
+            guard,
+        })
+    }
+}
+
+// The state of the base contract has no version stored, so it is read as `V0`
+#[derive(BorshDeserialize)]
+pub enum VersionedGuestBook {
+    V0(OldState),
+    V1(GuestBook),
+}
+
+impl VersionedState for VersionedGuestBook {
+    type Latest = GuestBook;
+    const VERSION: u8 = 1;
+}
 
-        // return the new state
-        Self {
-            messages: new_messages,
+impl From<VersionedGuestBook> for GuestBook {
+    fn from(state: VersionedGuestBook) -> Self {
+        match state {
+            VersionedGuestBook::V1(guest_book) => guest_book,
+            // Migrating every message at once can exceed the gas of a single transaction,
+            // so they are moved little by little through `migrate_messages`
+            VersionedGuestBook::V0(old_state) => Self {
+                messages: Vector::new(MESSAGES_PREFIX),
+                migration: Some(Migration {
+                    old_messages: old_state.messages,
+                    old_payments: old_state.payments,
+                    cursor: MigrationCursor::new(),
+                }),
+                guard: old_state.guard,
+            },
         }
     }
 }
+
+versioned::impl_migrate!(GuestBook, VersionedGuestBook);
+
+/// Messages of the base contract that were not moved to `GuestBook::messages` yet.
+///
+/// Each message is removed from the old vectors once migrated, so they are empty when
+/// the migration is done.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Migration {
+    old_messages: Vector<OldPostedMessage>,
+    old_payments: Vector<Balance>,
+    cursor: MigrationCursor,
+}
+
+impl Migration {
+    /// Number of messages that were not migrated yet.
+    pub fn remaining(&self) -> u64 {
+        self.old_messages.len() - self.cursor.next_index()
+    }
+
+    /// Reads the message `idx` in the new format.
+    pub fn get(&self, idx: u64) -> Option<PostedMessage> {
+        let posted = self.old_messages.get(idx)?;
+        // get the payment using the message index
+        let payment = self.old_payments.get(idx).unwrap_or(0);
+
+        Some(PostedMessage {
+            payment,
+            premium: posted.premium,
+            sender: posted.sender,
+            text: posted.text,
+        })
+    }
+
+    /// Hands at most `limit` messages to `migrate`, in order, returns whether it was the last batch.
+    pub fn advance<F>(&mut self, limit: u64, mut migrate: F) -> bool
+    where
+        F: FnMut(PostedMessage),
+    {
+        let len = self.old_messages.len();
+        let mut cursor = self.cursor;
+        let done = cursor.advance(len, limit, |idx| {
+            migrate(self.get(idx).unwrap());
+            // `Vector` only removes its last element, so the migrated one is removed by its key
+            env::storage_remove(&vector_key(OLD_MESSAGES_PREFIX, idx));
+            env::storage_remove(&vector_key(OLD_PAYMENTS_PREFIX, idx));
+        });
+        self.cursor = cursor;
+        done
+    }
+}
+
+// Key of the element `idx` of a `Vector` stored under `prefix`
+fn vector_key(prefix: &[u8], idx: u64) -> Vec<u8> {
+    [prefix, &idx.to_le_bytes()].concat()
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrationStatus {
+    pub in_progress: bool,
+    pub migrated: U64,
+    pub total: U64,
+}
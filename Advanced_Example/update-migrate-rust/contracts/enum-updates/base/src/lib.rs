@@ -1,3 +1,4 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Vector;
 use near_sdk::json_types::U128;
@@ -13,29 +14,36 @@ const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct GuestBook {
     messages: Vector<VersionedPostedMessage>,
+    guard: MessageGuard,
 }
 
 impl Default for GuestBook {
     fn default() -> Self {
         Self {
             messages: Vector::new(b"m"),
+            guard: MessageGuard::new(b"g"),
         }
     }
 }
 
 #[near_bindgen]
 impl GuestBook {
+    /// Premium messages keep the attached deposit, the others only pay for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
-        let premium = payment >= POINT_ONE;
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
+
         let message = VersionedPostedMessage::V1(PostedMessageV1 {
             sender,
             premium,
             text,
         });
         self.messages.push(&message);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(
@@ -52,4 +60,6 @@ impl GuestBook {
             .map(|message| message.into())
             .collect()
     }
-}
\ No newline at end of file
+}
+
+nep_330::impl_contract_source_metadata!(GuestBook);
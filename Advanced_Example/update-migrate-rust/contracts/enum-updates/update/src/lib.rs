@@ -1,42 +1,92 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::Vector;
 use near_sdk::json_types::U128;
 use near_sdk::serde::Serialize;
-use near_sdk::{env, near_bindgen, AccountId, Balance};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault};
+use versioned::{MigrationCursor, VersionedState, VersionedVector};
 
 use versioned_msg::{PostedMessageV2, VersionedPostedMessage};
 mod versioned_msg;
 
 const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
+const DEFAULT_UPGRADE_LIMIT: u64 = 100;
 
 #[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct GuestBook {
-    messages: Vector<VersionedPostedMessage>,
+    messages: VersionedVector<VersionedPostedMessage>,
+    guard: MessageGuard,
+    // Position of `upgrade_messages`, until every message is stored as `V2`
+    upgrade: Option<MigrationCursor>,
 }
 
-impl Default for GuestBook {
-    fn default() -> Self {
-        Self {
-            messages: Vector::new(b"m"),
+// The state of the base contract, which stored no version. Its `Vector` has the same
+// layout as a `VersionedVector`
+#[derive(BorshDeserialize)]
+pub struct GuestBookV0 {
+    messages: VersionedVector<VersionedPostedMessage>,
+    guard: MessageGuard,
+}
+
+#[derive(BorshDeserialize)]
+pub enum VersionedGuestBook {
+    V0(GuestBookV0),
+    V1(GuestBook),
+}
+
+impl VersionedState for VersionedGuestBook {
+    type Latest = GuestBook;
+    const VERSION: u8 = 1;
+}
+
+impl From<VersionedGuestBook> for GuestBook {
+    fn from(state: VersionedGuestBook) -> Self {
+        match state {
+            VersionedGuestBook::V1(guest_book) => guest_book,
+            // The messages are read as `V2` right away, `upgrade_messages` rewrites them
+            VersionedGuestBook::V0(old_state) => Self {
+                messages: old_state.messages,
+                guard: old_state.guard,
+                upgrade: Some(MigrationCursor::new()),
+            },
         }
     }
 }
 
+versioned::impl_migrate!(GuestBook, VersionedGuestBook);
+
 #[near_bindgen]
 impl GuestBook {
+    /// Initializes a new guest book, use `migrate` to update the base contract instead
+    #[init]
+    pub fn new() -> Self {
+        versioned::write_state_version(VersionedGuestBook::VERSION);
+        Self {
+            messages: VersionedVector::new(b"m"),
+            guard: MessageGuard::new(b"g"),
+            upgrade: None,
+        }
+    }
+
+    /// Premium messages keep the attached deposit as their payment, the others only pay
+    /// for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
-        let premium = payment >= POINT_ONE;
-        let message = VersionedPostedMessage::V2(PostedMessageV2 {
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
+
+        let payment = if premium { env::attached_deposit() } else { 0 };
+        let message = PostedMessageV2 {
             payment,
             sender,
             premium,
             text,
-        });
-        self.messages.push(&message);
+        };
+        self.messages.push(message);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(
@@ -50,7 +100,27 @@ impl GuestBook {
             .iter()
             .skip(from as usize)
             .take(limit.unwrap_or(10) as usize)
-            .map(|message| message.into())
             .collect()
     }
-}
\ No newline at end of file
+
+    /// Rewrites at most `limit` messages of the base contract as `V2`, returns whether all of
+    /// them were upgraded. Anyone can call it until it returns `true`.
+    pub fn upgrade_messages(&mut self, limit: Option<u64>) -> bool {
+        let mut cursor = self
+            .upgrade
+            .unwrap_or_else(|| env::panic_str("No upgrade in progress"));
+
+        let done = self
+            .messages
+            .migrate(&mut cursor, limit.unwrap_or(DEFAULT_UPGRADE_LIMIT));
+        if done {
+            self.upgrade = None;
+            log!("Upgraded {} messages", self.messages.len());
+        } else {
+            self.upgrade = Some(cursor);
+        }
+        done
+    }
+}
+
+nep_330::impl_contract_source_metadata!(GuestBook);
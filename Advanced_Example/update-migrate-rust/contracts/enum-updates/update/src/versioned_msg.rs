@@ -1,4 +1,5 @@
 use crate::*;
+use versioned::Versioned;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
@@ -38,3 +39,17 @@ impl From<VersionedPostedMessage> for PostedMessageV2 {
         }
     }
 }
+
+impl From<PostedMessageV2> for VersionedPostedMessage {
+    fn from(message: PostedMessageV2) -> Self {
+        VersionedPostedMessage::V2(message)
+    }
+}
+
+impl Versioned for VersionedPostedMessage {
+    type Latest = PostedMessageV2;
+
+    fn is_latest(&self) -> bool {
+        matches!(self, VersionedPostedMessage::V2(_))
+    }
+}
@@ -0,0 +1,75 @@
+//! Keeps the guest books from having their storage filled by anyone.
+//!
+//! - Messages are at most [`MAX_MESSAGE_LENGTH`] bytes long.
+//! - An account waits [`MIN_BLOCKS_BETWEEN_MESSAGES`] between two messages.
+//! - The attached deposit pays for the storage of the message, see [`charge_storage`].
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{
+    env, require, AccountId, Balance, BlockHeight, IntoStorageKey, Promise, StorageUsage,
+};
+
+/// In bytes.
+pub const MAX_MESSAGE_LENGTH: usize = 280;
+/// Roughly 10 seconds.
+pub const MIN_BLOCKS_BETWEEN_MESSAGES: BlockHeight = 10;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MessageGuard {
+    last_posted: LookupMap<AccountId, BlockHeight>,
+}
+
+impl MessageGuard {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self {
+            last_posted: LookupMap::new(prefix),
+        }
+    }
+
+    /// Checks the length of `text` and that `sender` waited since its last message, which is
+    /// then recorded. Should be called after reading `env::storage_usage()`, so the record is
+    /// charged to `sender` too.
+    pub fn check_message(&mut self, sender: &AccountId, text: &str) {
+        check_text(text);
+
+        let block_height = env::block_height();
+        if let Some(last_posted) = self.last_posted.get(sender) {
+            let next_allowed = last_posted + MIN_BLOCKS_BETWEEN_MESSAGES;
+            require!(
+                block_height >= next_allowed,
+                format!("Wait until block {next_allowed} to post again")
+            );
+        }
+        self.last_posted.insert(sender, &block_height);
+    }
+}
+
+/// Checks that `text` is not empty and at most [`MAX_MESSAGE_LENGTH`] bytes long, e.g. when
+/// editing a message.
+pub fn check_text(text: &str) {
+    require!(!text.is_empty(), "The message is empty");
+    require!(
+        text.len() <= MAX_MESSAGE_LENGTH,
+        format!("Messages can not be longer than {MAX_MESSAGE_LENGTH} bytes")
+    );
+}
+
+/// Pays for the storage written since `initial_storage` with the attached deposit. Unless
+/// `keep_deposit`, the surplus is returned to the predecessor.
+pub fn charge_storage(initial_storage: StorageUsage, keep_deposit: bool) {
+    let used = env::storage_usage().saturating_sub(initial_storage);
+    let cost = env::storage_byte_cost() * used as Balance;
+    let attached = env::attached_deposit();
+
+    require!(
+        attached >= cost,
+        format!("Attach at least {cost} yⓃ to pay for the storage")
+    );
+    if !keep_deposit && attached > cost {
+        Promise::new(env::predecessor_account_id()).transfer(attached - cost);
+    }
+}
@@ -1,10 +1,10 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Vector;
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::Serialize;
 use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault};
-
-mod update;
+use upgrade::{Upgrade, DEFAULT_UPGRADE_DELAY};
 
 const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
 
@@ -22,27 +22,34 @@ pub struct PostedMessage {
 pub struct GuestBook {
     messages: Vector<PostedMessage>,
     payments: Vector<Balance>,
-    manager: AccountId
+    upgrade: Upgrade,
+    guard: MessageGuard,
 }
 
 #[near_bindgen]
 impl GuestBook {
 
     #[init]
-    pub fn init(manager: AccountId) -> Self {
-        Self{
+    pub fn init(manager: AccountId, upgrade_delay: Option<U64>) -> Self {
+        let delay = upgrade_delay.map(u64::from).unwrap_or(DEFAULT_UPGRADE_DELAY);
+        Self {
             messages: Vector::new(b"m"),
             payments: Vector::new(b"p"),
-            manager
+            upgrade: Upgrade::new(b"u", manager, delay),
+            guard: MessageGuard::new(b"g"),
         }
     }
 
+    /// Premium messages keep the attached deposit as their payment, the others only pay
+    /// for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
-        let premium = payment >= POINT_ONE;
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
 
+        let payment = if premium { env::attached_deposit() } else { 0 };
         let message = PostedMessage {
             premium,
             sender,
@@ -50,6 +57,8 @@ impl GuestBook {
         };
         self.messages.push(&message);
         self.payments.push(&payment);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PostedMessage> {
@@ -73,3 +82,7 @@ impl GuestBook {
             .collect()
     }
 }
+
+upgrade::impl_upgradable!(GuestBook, upgrade);
+
+nep_330::impl_contract_source_metadata!(GuestBook);
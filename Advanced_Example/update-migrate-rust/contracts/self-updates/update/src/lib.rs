@@ -1,11 +1,12 @@
+use guard::{charge_storage, MessageGuard};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Vector;
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::Serialize;
 use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault};
+use upgrade::{Upgrade, DEFAULT_UPGRADE_DELAY};
 
 mod migrate;
-mod update;
 
 const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
 
@@ -23,25 +24,33 @@ pub struct PostedMessage {
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct GuestBook {
     messages: Vector<PostedMessage>,
-    manager: AccountId
+    upgrade: Upgrade,
+    guard: MessageGuard,
 }
 
 #[near_bindgen]
 impl GuestBook {
 
     #[init]
-    pub fn init(manager: AccountId) -> Self {
+    pub fn init(manager: AccountId, upgrade_delay: Option<U64>) -> Self {
+        let delay = upgrade_delay.map(u64::from).unwrap_or(DEFAULT_UPGRADE_DELAY);
         Self {
             messages: Vector::new(b"m"),
-            manager
+            upgrade: Upgrade::new(b"u", manager, delay),
+            guard: MessageGuard::new(b"g"),
         }
     }
 
+    /// Premium messages keep the attached deposit as their payment, the others only pay
+    /// for their storage
     #[payable]
     pub fn add_message(&mut self, text: String) {
-        let payment = env::attached_deposit();
+        let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
-        let premium = payment >= POINT_ONE;
+        let initial_storage = env::storage_usage();
+        self.guard.check_message(&sender, &text);
+
+        let payment = if premium { env::attached_deposit() } else { 0 };
         let message = PostedMessage {
             payment,
             sender,
@@ -49,6 +58,8 @@ impl GuestBook {
             text,
         };
         self.messages.push(&message);
+
+        charge_storage(initial_storage, premium);
     }
 
     pub fn get_messages(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PostedMessage> {
@@ -61,3 +72,7 @@ impl GuestBook {
             .collect()
     }
 }
+
+upgrade::impl_upgradable!(GuestBook, upgrade);
+
+nep_330::impl_contract_source_metadata!(GuestBook);
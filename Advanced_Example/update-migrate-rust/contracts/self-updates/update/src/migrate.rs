@@ -13,7 +13,8 @@ pub struct OldPostedMessage {
 pub struct OldState {
     messages: Vector<OldPostedMessage>,
     payments: Vector<Balance>,
-    manager: AccountId,
+    upgrade: Upgrade,
+    guard: MessageGuard,
 }
 
 #[near_bindgen]
@@ -41,7 +42,8 @@ impl GuestBook {
         // return the new state
         Self {
             messages: new_messages,
-            manager: old_state.manager,
+            upgrade: old_state.upgrade,
+            guard: old_state.guard,
         }
     }
 }
@@ -0,0 +1,103 @@
+//! Events emitted by the upgrade flow, following the events format (nep-297):
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+//!
+//! ```json
+//! EVENT_JSON:{"standard":"upgrade","version":"1.0.0","event":"upgrade_staged","data":[...]}
+//! ```
+
+use near_sdk::env;
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::serde::Serialize;
+
+/// The manager staged new code. It can be deployed from `available_at` on.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeStaged<'a> {
+    pub code_hash: &'a Base58CryptoHash,
+    pub available_at: &'a U64,
+}
+
+impl UpgradeStaged<'_> {
+    pub fn emit(self) {
+        UpgradeEventKind::UpgradeStaged(&[self]).emit()
+    }
+}
+
+/// The manager dropped the staged code before deploying it.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeCancelled<'a> {
+    pub code_hash: &'a Base58CryptoHash,
+}
+
+impl UpgradeCancelled<'_> {
+    pub fn emit(self) {
+        UpgradeEventKind::UpgradeCancelled(&[self]).emit()
+    }
+}
+
+/// The staged code was deployed and `migrate` succeeded.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeDeployed<'a> {
+    pub code_hash: &'a Base58CryptoHash,
+}
+
+impl UpgradeDeployed<'_> {
+    pub fn emit(self) {
+        UpgradeEventKind::UpgradeDeployed(&[self]).emit()
+    }
+}
+
+/// The deploy or `migrate` failed, the account still runs the previous code.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeFailed<'a> {
+    pub code_hash: &'a Base58CryptoHash,
+}
+
+impl UpgradeFailed<'_> {
+    pub fn emit(self) {
+        UpgradeEventKind::UpgradeFailed(&[self]).emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct UpgradeEvent<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: UpgradeEventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+enum UpgradeEventKind<'a> {
+    UpgradeStaged(&'a [UpgradeStaged<'a>]),
+    UpgradeCancelled(&'a [UpgradeCancelled<'a>]),
+    UpgradeDeployed(&'a [UpgradeDeployed<'a>]),
+    UpgradeFailed(&'a [UpgradeFailed<'a>]),
+}
+
+impl UpgradeEventKind<'_> {
+    fn emit(self) {
+        let event = UpgradeEvent {
+            standard: "upgrade",
+            version: "1.0.0",
+            event_kind: self,
+        };
+        // Events cannot fail to serialize so fine to panic on error
+        let json = near_sdk::serde_json::to_string(&event)
+            .ok()
+            .unwrap_or_else(|| env::abort());
+        env::log_str(&format!("EVENT_JSON:{json}"));
+    }
+}
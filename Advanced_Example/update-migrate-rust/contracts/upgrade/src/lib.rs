@@ -0,0 +1,146 @@
+//! Staged, time-locked self upgrades.
+//!
+//! Instead of deploying a new wasm as soon as the manager sends it, the code is first
+//! *staged* in the contract's storage. Anyone can then inspect its sha256 through
+//! `code_hash`, and the code can only be deployed once `delay` nanoseconds have passed.
+//!
+//! The deploy and the `migrate` call are sent in the same batch, so if `migrate` fails the
+//! deploy is reverted too and the account keeps running the old code and state.
+
+pub mod events;
+pub mod macros;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{
+    env, require, AccountId, CryptoHash, Duration, Gas, IntoStorageKey, Promise, Timestamp,
+};
+
+use events::{UpgradeCancelled, UpgradeDeployed, UpgradeFailed, UpgradeStaged};
+
+/// One day, in nanoseconds.
+pub const DEFAULT_UPGRADE_DELAY: Duration = 24 * 60 * 60 * 1_000_000_000;
+
+pub const GAS_FOR_MIGRATE: Gas = Gas(200_000_000_000_000); // 200 TGAS
+pub const GAS_FOR_ON_DEPLOY: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Upgrade {
+    pub manager: AccountId,
+    pub delay: Duration,
+    staged_code: LazyOption<Vec<u8>>,
+    staged_hash: Option<CryptoHash>,
+    staged_at: Option<Timestamp>,
+}
+
+impl Upgrade {
+    pub fn new<S>(prefix: S, manager: AccountId, delay: Duration) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self {
+            manager,
+            delay,
+            staged_code: LazyOption::new(prefix, None),
+            staged_hash: None,
+            staged_at: None,
+        }
+    }
+
+    pub fn assert_manager(&self) {
+        require!(
+            env::predecessor_account_id() == self.manager,
+            "Only the manager can update the code"
+        );
+    }
+
+    /// sha256 of the staged code, if any.
+    pub fn code_hash(&self) -> Option<Base58CryptoHash> {
+        self.staged_hash.map(Base58CryptoHash::from)
+    }
+
+    /// Earliest timestamp at which the staged code can be deployed.
+    pub fn available_at(&self) -> Option<Timestamp> {
+        self.staged_at.map(|staged_at| staged_at + self.delay)
+    }
+
+    /// Stores `code` and (re)starts the timelock. Staging again replaces the previous code.
+    pub fn internal_stage_code(&mut self, code: Vec<u8>) {
+        self.assert_manager();
+        require!(!code.is_empty(), "Error: No input");
+
+        let code_hash = env::sha256_array(&code);
+        self.staged_code.set(&code);
+        self.staged_hash = Some(code_hash);
+        self.staged_at = Some(env::block_timestamp());
+
+        UpgradeStaged {
+            code_hash: &code_hash.into(),
+            available_at: &self.available_at().unwrap().into(),
+        }
+        .emit();
+    }
+
+    pub fn internal_cancel_staged_code(&mut self) {
+        self.assert_manager();
+        let code_hash = self
+            .code_hash()
+            .unwrap_or_else(|| env::panic_str("No code staged"));
+
+        self.clear();
+        UpgradeCancelled {
+            code_hash: &code_hash,
+        }
+        .emit();
+    }
+
+    /// Deploys the staged code on the current account and calls `migrate` with `migrate_args`
+    /// in the same batch. The caller is expected to chain `on_deploy_staged_code` to the result.
+    pub fn internal_deploy_staged_code(&mut self, migrate_args: Vec<u8>) -> Promise {
+        self.assert_manager();
+        let available_at = self
+            .available_at()
+            .unwrap_or_else(|| env::panic_str("No code staged"));
+        require!(
+            env::block_timestamp() >= available_at,
+            format!("The staged code can't be deployed before {available_at}")
+        );
+
+        let code = self.staged_code.get().unwrap();
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call("migrate".to_string(), migrate_args, 0, GAS_FOR_MIGRATE)
+    }
+
+    /// Resolves a deploy. On success the staged code is dropped, on failure it is kept so the
+    /// manager can inspect it and either cancel it or stage a fixed version.
+    pub fn internal_on_deploy_staged_code(
+        &mut self,
+        code_hash: Base58CryptoHash,
+        deployed: bool,
+    ) -> bool {
+        if deployed {
+            // The staged code could have been replaced while the deploy was in flight
+            if self.code_hash() == Some(code_hash) {
+                self.clear();
+            }
+            UpgradeDeployed {
+                code_hash: &code_hash,
+            }
+            .emit();
+        } else {
+            UpgradeFailed {
+                code_hash: &code_hash,
+            }
+            .emit();
+        }
+        deployed
+    }
+
+    fn clear(&mut self) {
+        self.staged_code.remove();
+        self.staged_hash = None;
+        self.staged_at = None;
+    }
+}
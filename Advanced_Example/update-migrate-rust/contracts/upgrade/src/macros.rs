@@ -0,0 +1,57 @@
+/// Exposes the staged upgrade flow on `$contract`, where `$upgrade` is its
+/// [`Upgrade`](crate::Upgrade) field.
+///
+/// Both the current and the next version of the contract must implement it: on success
+/// `on_deploy_staged_code` runs on the freshly deployed code, and on failure on the old one.
+#[macro_export]
+macro_rules! impl_upgradable {
+    ($contract: ident, $upgrade: ident) => {
+        #[near_bindgen]
+        impl $contract {
+            pub fn stage_code(&mut self) {
+                // Receive the code directly from the input to avoid the
+                // GAS overhead of deserializing parameters
+                let code = near_sdk::env::input().expect("Error: No input").to_vec();
+                self.$upgrade.internal_stage_code(code)
+            }
+
+            pub fn cancel_staged_code(&mut self) {
+                self.$upgrade.internal_cancel_staged_code()
+            }
+
+            pub fn code_hash(&self) -> Option<near_sdk::json_types::Base58CryptoHash> {
+                self.$upgrade.code_hash()
+            }
+
+            pub fn staged_code_available_at(&self) -> Option<near_sdk::json_types::U64> {
+                self.$upgrade.available_at().map(near_sdk::json_types::U64)
+            }
+
+            pub fn deploy_staged_code(
+                &mut self,
+                migrate_args: Option<near_sdk::json_types::Base64VecU8>,
+            ) -> near_sdk::Promise {
+                let code_hash = self.$upgrade.code_hash().unwrap_or_else(|| {
+                    near_sdk::env::panic_str("No code staged")
+                });
+                self.$upgrade
+                    .internal_deploy_staged_code(migrate_args.map(|a| a.0).unwrap_or_default())
+                    .then(
+                        Self::ext(near_sdk::env::current_account_id())
+                            .with_static_gas($crate::GAS_FOR_ON_DEPLOY)
+                            .on_deploy_staged_code(code_hash),
+                    )
+            }
+
+            #[private]
+            pub fn on_deploy_staged_code(
+                &mut self,
+                code_hash: near_sdk::json_types::Base58CryptoHash,
+            ) -> bool {
+                let deployed = near_sdk::is_promise_success();
+                self.$upgrade
+                    .internal_on_deploy_staged_code(code_hash, deployed)
+            }
+        }
+    };
+}
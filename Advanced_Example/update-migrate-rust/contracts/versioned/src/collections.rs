@@ -0,0 +1,278 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{UnorderedMap, Vector};
+use near_sdk::IntoStorageKey;
+
+use crate::{MigrationCursor, Versioned};
+
+/// A [`Vector`] of versioned values. Entries are returned in their latest version,
+/// and are always written in their latest version. Reading an entry doesn't rewrite it,
+/// use [`VersionedVector::get_and_upgrade`] on write paths to persist the upgrade.
+///
+/// It has the same storage layout as `Vector<T>`, so it can replace one without a migration.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VersionedVector<T> {
+    inner: Vector<T>,
+}
+
+impl<T: Versioned> VersionedVector<T> {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self {
+            inner: Vector::new(prefix),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn push(&mut self, value: T::Latest) {
+        self.inner.push(&T::from(value));
+    }
+
+    pub fn get(&self, index: u64) -> Option<T::Latest> {
+        self.inner.get(index).map(Into::into)
+    }
+
+    /// Returns the entry at `index` in its latest version, rewriting it if it was outdated.
+    pub fn get_and_upgrade(&mut self, index: u64) -> Option<T::Latest> {
+        let value = self.inner.get(index)?;
+        if value.is_latest() {
+            return Some(value.into());
+        }
+        let upgraded = T::from(value.into());
+        self.inner.replace(index, &upgraded);
+        Some(upgraded.into())
+    }
+
+    pub fn replace(&mut self, index: u64, value: T::Latest) -> T::Latest {
+        self.inner.replace(index, &T::from(value)).into()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T::Latest> + '_ {
+        self.inner.iter().map(Into::into)
+    }
+
+    /// Rewrites the entry at `index` in its latest version, returns whether it was outdated.
+    pub fn upgrade(&mut self, index: u64) -> bool {
+        match self.inner.get(index) {
+            Some(value) if !value.is_latest() => {
+                self.inner.replace(index, &T::from(value.into()));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Upgrades at most `limit` entries from the `cursor` on, returns whether all the
+    /// entries are in their latest version.
+    pub fn migrate(&mut self, cursor: &mut MigrationCursor, limit: u64) -> bool {
+        cursor.advance(self.len(), limit, |index| {
+            self.upgrade(index);
+        })
+    }
+
+    pub fn as_vector(&self) -> &Vector<T> {
+        &self.inner
+    }
+}
+
+/// An [`UnorderedMap`] of versioned values. Values are returned in their latest version,
+/// and are always written in their latest version. Reading a value doesn't rewrite it,
+/// use [`VersionedMap::get_and_upgrade`] on write paths to persist the upgrade.
+///
+/// It has the same storage layout as `UnorderedMap<K, T>`, so it can replace one without a migration.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VersionedMap<K, T> {
+    inner: UnorderedMap<K, T>,
+}
+
+impl<K, T> VersionedMap<K, T>
+where
+    K: BorshSerialize + BorshDeserialize,
+    T: Versioned,
+{
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self {
+            inner: UnorderedMap::new(prefix),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<T::Latest> {
+        self.inner.get(key).map(Into::into)
+    }
+
+    /// Returns the value of `key` in its latest version, rewriting it if it was outdated.
+    pub fn get_and_upgrade(&mut self, key: &K) -> Option<T::Latest> {
+        let value = self.inner.get(key)?;
+        if value.is_latest() {
+            return Some(value.into());
+        }
+        let upgraded = T::from(value.into());
+        self.inner.insert(key, &upgraded);
+        Some(upgraded.into())
+    }
+
+    pub fn insert(&mut self, key: &K, value: T::Latest) -> Option<T::Latest> {
+        self.inner.insert(key, &T::from(value)).map(Into::into)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<T::Latest> {
+        self.inner.remove(key).map(Into::into)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, T::Latest)> + '_ {
+        self.inner.iter().map(|(key, value)| (key, value.into()))
+    }
+
+    /// Rewrites the value of `key` in its latest version, returns whether it was outdated.
+    pub fn upgrade(&mut self, key: &K) -> bool {
+        match self.inner.get(key) {
+            Some(value) if !value.is_latest() => {
+                self.inner.insert(key, &T::from(value.into()));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Upgrades at most `limit` values from the `cursor` on, returns whether all the
+    /// values are in their latest version.
+    ///
+    /// Removing keys while a migration is in progress moves the last entry of the map,
+    /// so it may be skipped. This is harmless since values are upgraded when read anyway.
+    pub fn migrate(&mut self, cursor: &mut MigrationCursor, limit: u64) -> bool {
+        cursor.advance(self.len(), limit, |index| {
+            let key = self.inner.keys_as_vector().get(index).unwrap();
+            self.upgrade(&key);
+        })
+    }
+
+    pub fn as_map(&self) -> &UnorderedMap<K, T> {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(BorshDeserialize, BorshSerialize)]
+    enum VersionedNumber {
+        V1(u8),
+        V2(u64),
+    }
+
+    impl From<VersionedNumber> for u64 {
+        fn from(number: VersionedNumber) -> Self {
+            match number {
+                VersionedNumber::V1(n) => n as u64,
+                VersionedNumber::V2(n) => n,
+            }
+        }
+    }
+
+    impl From<u64> for VersionedNumber {
+        fn from(n: u64) -> Self {
+            VersionedNumber::V2(n)
+        }
+    }
+
+    impl Versioned for VersionedNumber {
+        type Latest = u64;
+
+        fn is_latest(&self) -> bool {
+            matches!(self, VersionedNumber::V2(_))
+        }
+    }
+
+    #[test]
+    fn reads_old_entries_in_latest_version() {
+        let mut old: Vector<VersionedNumber> = Vector::new(b"v");
+        old.push(&VersionedNumber::V1(1));
+        old.push(&VersionedNumber::V2(2));
+
+        // Same storage layout, an old vector can be read as a versioned one
+        let vector =
+            VersionedVector::<VersionedNumber>::try_from_slice(&old.try_to_vec().unwrap()).unwrap();
+        assert_eq!(vector.iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(!vector.as_vector().get(0).unwrap().is_latest());
+    }
+
+    #[test]
+    fn get_and_upgrade_persists_the_latest_version() {
+        let mut old: Vector<VersionedNumber> = Vector::new(b"v");
+        old.push(&VersionedNumber::V1(1));
+        let mut vector =
+            VersionedVector::<VersionedNumber>::try_from_slice(&old.try_to_vec().unwrap()).unwrap();
+
+        assert_eq!(vector.get(0), Some(1));
+        assert!(!vector.as_vector().get(0).unwrap().is_latest());
+        assert_eq!(vector.get_and_upgrade(0), Some(1));
+        assert!(vector.as_vector().get(0).unwrap().is_latest());
+
+        let mut old: UnorderedMap<String, VersionedNumber> = UnorderedMap::new(b"m");
+        old.insert(&"1".to_string(), &VersionedNumber::V1(1));
+        let mut map =
+            VersionedMap::<String, VersionedNumber>::try_from_slice(&old.try_to_vec().unwrap())
+                .unwrap();
+
+        assert_eq!(map.get_and_upgrade(&"1".to_string()), Some(1));
+        assert!(map.as_map().get(&"1".to_string()).unwrap().is_latest());
+        assert_eq!(map.get_and_upgrade(&"2".to_string()), None);
+    }
+
+    #[test]
+    fn migrates_vector_in_chunks() {
+        let mut old: Vector<VersionedNumber> = Vector::new(b"v");
+        for n in 0..5 {
+            old.push(&VersionedNumber::V1(n));
+        }
+
+        let mut vector =
+            VersionedVector::<VersionedNumber>::try_from_slice(&old.try_to_vec().unwrap()).unwrap();
+        let mut cursor = MigrationCursor::new();
+        assert!(!vector.migrate(&mut cursor, 2));
+        assert_eq!(cursor.next_index(), 2);
+        assert!(!vector.migrate(&mut cursor, 2));
+        assert!(vector.migrate(&mut cursor, 2));
+
+        assert!(vector.as_vector().iter().all(|n| n.is_latest()));
+        assert_eq!(vector.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn migrates_map_in_chunks() {
+        let mut old: UnorderedMap<String, VersionedNumber> = UnorderedMap::new(b"m");
+        for n in 0..3 {
+            old.insert(&n.to_string(), &VersionedNumber::V1(n));
+        }
+
+        let mut map =
+            VersionedMap::<String, VersionedNumber>::try_from_slice(&old.try_to_vec().unwrap())
+                .unwrap();
+        let mut cursor = MigrationCursor::new();
+        assert!(!map.migrate(&mut cursor, 2));
+        assert!(map.migrate(&mut cursor, 2));
+
+        assert!(map.as_map().values().all(|n| n.is_latest()));
+        assert_eq!(map.get(&"2".to_string()), Some(2));
+    }
+}
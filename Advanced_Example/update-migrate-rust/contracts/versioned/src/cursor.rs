@@ -0,0 +1,39 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+
+/// Position of a migration that is split across several transactions.
+///
+/// Keep it in the contract's state and call [`MigrationCursor::advance`] (or the `migrate`
+/// method of a versioned collection) until it returns `true`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrationCursor {
+    next_index: u64,
+}
+
+impl MigrationCursor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the next entry to migrate.
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn is_done(&self, len: u64) -> bool {
+        self.next_index >= len
+    }
+
+    /// Calls `f` on at most `limit` of the remaining indexes of a collection of `len` entries,
+    /// and returns whether the whole collection was migrated.
+    pub fn advance<F>(&mut self, len: u64, limit: u64, mut f: F) -> bool
+    where
+        F: FnMut(u64),
+    {
+        let end = len.min(self.next_index.saturating_add(limit));
+        for index in self.next_index..end {
+            f(index);
+        }
+        self.next_index = self.next_index.max(end);
+        self.is_done(len)
+    }
+}
@@ -0,0 +1,47 @@
+//! Helpers to version a contract's state, generalizing the [enum-updates](../enum-updates/) example.
+//!
+//! - [`Versioned`]: an enum holding every version of a value, stored in collections.
+//!   [`VersionedVector`] and [`VersionedMap`] upgrade the entries lazily when they are read, and
+//!   rewrite them in their latest version with `get_and_upgrade` or `upgrade`.
+//! - [`VersionedState`]: an enum holding every version of the contract's state, used by the
+//!   `migrate` method generated with [`impl_migrate!`].
+//! - [`MigrationCursor`]: eagerly upgrades a large collection in chunks, across several transactions.
+
+pub mod collections;
+pub mod cursor;
+pub mod macros;
+pub mod state;
+
+pub use collections::{VersionedMap, VersionedVector};
+pub use cursor::MigrationCursor;
+pub use state::{
+    migrate_state, read_versioned_state, state_version, write_state_version, VersionedState,
+};
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+
+/// A value stored as an enum of all its versions.
+///
+/// ```ignore
+/// #[derive(BorshSerialize, BorshDeserialize)]
+/// pub enum VersionedPostedMessage {
+///     V1(PostedMessageV1),
+///     V2(PostedMessageV2),
+/// }
+///
+/// impl Versioned for VersionedPostedMessage {
+///     type Latest = PostedMessageV2;
+///
+///     fn is_latest(&self) -> bool {
+///         matches!(self, VersionedPostedMessage::V2(_))
+///     }
+/// }
+/// ```
+pub trait Versioned:
+    BorshSerialize + BorshDeserialize + From<Self::Latest> + Into<Self::Latest>
+{
+    type Latest;
+
+    /// Whether the value is already stored in its latest version.
+    fn is_latest(&self) -> bool;
+}
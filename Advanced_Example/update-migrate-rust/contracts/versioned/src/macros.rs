@@ -0,0 +1,15 @@
+/// Generates a `migrate` method that upgrades the stored state, whatever its version,
+/// to `$contract`, which must be the `Latest` of `$versioned_state`.
+#[macro_export]
+macro_rules! impl_migrate {
+    ($contract: ident, $versioned_state: ty) => {
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            #[init(ignore_state)]
+            pub fn migrate() -> Self {
+                $crate::migrate_state::<$versioned_state>()
+            }
+        }
+    };
+}
@@ -0,0 +1,71 @@
+use near_sdk::borsh::BorshDeserialize;
+use near_sdk::{env, require};
+
+/// Key under which `#[near_bindgen]` stores the contract's state.
+pub const STATE_KEY: &[u8] = b"STATE";
+/// Key under which the version of the stored state is kept.
+pub const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// The contract's state, as an enum of all its versions.
+///
+/// The variants must be declared from the oldest to the newest: the index of a variant
+/// is the version of the state it holds. Contracts deployed before using this crate
+/// have no version stored, so their state is read as the first variant.
+///
+/// ```ignore
+/// #[derive(BorshDeserialize)]
+/// pub enum VersionedGuestBook {
+///     V0(GuestBookV0),
+///     V1(GuestBook),
+/// }
+///
+/// impl VersionedState for VersionedGuestBook {
+///     type Latest = GuestBook;
+///     const VERSION: u8 = 1;
+/// }
+/// ```
+pub trait VersionedState: BorshDeserialize + Into<Self::Latest> {
+    type Latest;
+
+    /// Version of `Latest`.
+    const VERSION: u8;
+}
+
+/// Version of the stored state, `0` if none was written.
+pub fn state_version() -> u8 {
+    env::storage_read(STATE_VERSION_KEY)
+        .and_then(|version| version.first().copied())
+        .unwrap_or(0)
+}
+
+/// Should be called by the `#[init]` method of a contract whose state is versioned.
+pub fn write_state_version(version: u8) {
+    env::storage_write(STATE_VERSION_KEY, &[version]);
+}
+
+/// Reads the stored state as the variant matching its version.
+pub fn read_versioned_state<S: VersionedState>() -> S {
+    let version = state_version();
+    require!(
+        version <= S::VERSION,
+        "The state was written by a newer version of the contract"
+    );
+
+    let state = env::storage_read(STATE_KEY)
+        .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+
+    // The borsh representation of an enum is the index of the variant followed by its content
+    let mut tagged = Vec::with_capacity(state.len() + 1);
+    tagged.push(version);
+    tagged.extend(state);
+
+    S::try_from_slice(&tagged)
+        .unwrap_or_else(|_| env::panic_str("Cannot deserialize the contract state"))
+}
+
+/// Reads the stored state, whatever its version, and upgrades it to `S::Latest`.
+pub fn migrate_state<S: VersionedState>() -> S::Latest {
+    let state = read_versioned_state::<S>();
+    write_state_version(S::VERSION);
+    state.into()
+}
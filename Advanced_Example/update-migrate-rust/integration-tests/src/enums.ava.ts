@@ -37,9 +37,10 @@ test.afterEach(async (t) => {
 });
 
 test("version update", async (t) => {
-  const { guestBook, alice } = t.context.accounts;
+  const { worker, accounts: { guestBook, alice } } = t.context;
 
   await guestBook.deploy("./contracts/target/wasm32-unknown-unknown/release/enums_update.wasm");
+  await guestBook.call(guestBook, "migrate", {});
 
   // gets the version one messages
   const msgs = await guestBook.view("get_messages");
@@ -51,7 +52,8 @@ test("version update", async (t) => {
 
   t.deepEqual(msgs, expected)
 
-  // gets version one and two messages
+  // gets version one and two messages, once alice can post again
+  await worker.provider.fastForward(10);
   await alice.call(guestBook, "add_message", { text: "howdy" }, { attachedDeposit: NEAR.parse('1') });
 
   const new_msgs = await guestBook.view("get_messages");
@@ -64,4 +66,9 @@ test("version update", async (t) => {
 
   t.deepEqual(new_msgs, new_expected)
 
+  // the version one messages are rewritten as version two, and read the same
+  t.is(await guestBook.call(guestBook, "upgrade_messages", { limit: 1 }), false);
+  t.is(await guestBook.call(guestBook, "upgrade_messages", { limit: 2 }), true);
+  await t.throwsAsync(guestBook.call(guestBook, "upgrade_messages", {}));
+  t.deepEqual(await guestBook.view("get_messages"), new_expected);
 });
@@ -47,12 +47,27 @@ test("by default it return the two messages and their payments", async (t) => {
     { premium: true, sender: alice.accountId, text: "bye" },
   ];
 
-  const expected_payments = [NEAR.parse("0.09").toString(), NEAR.parse("0.1").toString()];
+  // only premium messages keep their deposit
+  const expected_payments = ["0", NEAR.parse("0.1").toString()];
 
   t.deepEqual(msgs, expected_msgs);
   t.deepEqual(payments, expected_payments);
 });
 
+test("the source metadata tells which version is deployed", async (t) => {
+  const { guestBook } = t.context.accounts;
+
+  const base: any = await guestBook.view("contract_source_metadata");
+  t.is(base.version, "1.0.0");
+  t.deepEqual(base.standards, [{ standard: "nep330", version: "1.1.0" }]);
+
+  await guestBook.deploy("./contracts/target/wasm32-unknown-unknown/release/update.wasm");
+  await guestBook.call(guestBook, "migrate", {});
+
+  const update: any = await guestBook.view("contract_source_metadata");
+  t.is(update.version, "1.1.0");
+});
+
 test("the migration removes payments and updates PostedMessages", async (t) => {
   const { guestBook, alice } = t.context.accounts;
 
@@ -61,12 +76,138 @@ test("the migration removes payments and updates PostedMessages", async (t) => {
 
   await t.throwsAsync(guestBook.view("get_payments"));
 
+  // the messages are read in the new format while they are being migrated
+  t.deepEqual(await guestBook.view("migration_status"), { in_progress: true, migrated: "0", total: "2" });
+  await t.throwsAsync(alice.call(guestBook, "add_message", { text: "too soon" }));
+
+  await guestBook.call(guestBook, "migrate_messages", { limit: 1 });
+  t.deepEqual(await guestBook.view("migration_status"), { in_progress: true, migrated: "1", total: "2" });
+
+  const done = await guestBook.call(guestBook, "migrate_messages", { limit: 1 });
+  t.is(done, true);
+  t.deepEqual(await guestBook.view("migration_status"), { in_progress: false, migrated: "2", total: "2" });
+
+  // the migrated messages and payments are removed from the old vectors
+  t.is((await guestBook.viewStateRaw("m")).length, 0);
+  t.is((await guestBook.viewStateRaw("p")).length, 0);
+
   const msgs = await guestBook.view("get_messages");
 
   const expected = [
-    { payment: 9e+22, premium: false, sender: guestBook.accountId, text: "hello" },
+    { payment: 0, premium: false, sender: guestBook.accountId, text: "hello" },
     { payment: 1e+23, premium: true, sender: alice.accountId, text: "bye" },
   ];
 
   t.deepEqual(msgs, expected)
-});
\ No newline at end of file
+});
+
+test("the guest book published before the guard is migrated too", async (t) => {
+  const { root, alice } = t.context.accounts;
+
+  // the published base has no guard in its state
+  const published = await root.createSubAccount("published");
+  await published.deploy("./contracts/target/wasm32-unknown-unknown/release/published_base.wasm");
+  await alice.call(published, "add_message", { text: "hello" }, { attachedDeposit: NEAR.parse("0.1") });
+
+  await published.deploy("./contracts/target/wasm32-unknown-unknown/release/update.wasm");
+  await published.call(published, "migrate", {});
+  t.is(await published.call(published, "migrate_messages", { limit: 10 }), true);
+
+  t.deepEqual(await published.view("get_messages"), [
+    { payment: 1e+23, premium: true, sender: alice.accountId, text: "hello" },
+  ]);
+
+  // the guard starts empty
+  await alice.call(published, "add_message", { text: "hi" }, { attachedDeposit: NEAR.parse("0.01") });
+  t.is(await published.view("total_messages"), 2);
+});
+
+test("the migration can be resumed across transactions", async (t) => {
+  const { worker, accounts: { guestBook, alice } } = t.context;
+  const total = 10_000;
+
+  // Writing 10k messages through `add_message` would take too long, patch the base state instead
+  const records = [
+    stateRecord(guestBook.accountId, Buffer.from("STATE"), Buffer.concat([borshVector("m", total), borshVector("p", total), borshString("g")])),
+  ];
+  for (let idx = 0; idx < total; idx++) {
+    const premium = idx % 2 == 1;
+    const message = Buffer.concat([Buffer.from([premium ? 1 : 0]), borshString(alice.accountId), borshString(`message ${idx}`)]);
+    records.push(stateRecord(guestBook.accountId, vectorKey("m", idx), message));
+    records.push(stateRecord(guestBook.accountId, vectorKey("p", idx), premium ? POINT_ONE : ZERO));
+  }
+  for (let i = 0; i < records.length; i += 1000) {
+    await worker.provider.patchStateRecords({ records: records.slice(i, i + 1000) });
+  }
+
+  await guestBook.deploy("./contracts/target/wasm32-unknown-unknown/release/update.wasm");
+  await guestBook.call(guestBook, "migrate", {});
+
+  let done = false;
+  let calls = 0;
+  while (!done) {
+    done = await guestBook.call(guestBook, "migrate_messages", { limit: 250 }, { gas: "300000000000000" });
+    calls++;
+  }
+
+  t.is(calls, total / 250);
+  t.deepEqual(await guestBook.view("migration_status"), { in_progress: false, migrated: `${total}`, total: `${total}` });
+
+  const msgs: any = await guestBook.view("get_messages", { from_index: "9998", limit: 2 });
+  t.deepEqual(msgs, [
+    { payment: 0, premium: false, sender: alice.accountId, text: "message 9998" },
+    { payment: 1e+23, premium: true, sender: alice.accountId, text: "message 9999" },
+  ]);
+
+  // writes are allowed again
+  await alice.call(guestBook, "add_message", { text: "hi" }, { attachedDeposit: NEAR.parse("0.01") });
+  t.is(await guestBook.view("total_messages"), total + 1);
+});
+
+test("messages are limited in length and frequency", async (t) => {
+  const { worker, accounts: { guestBook, alice } } = t.context;
+
+  await t.throwsAsync(alice.call(guestBook, "add_message", { text: "a".repeat(281) }, { attachedDeposit: NEAR.parse("0.01") }));
+
+  // alice posted in `beforeEach`
+  const error = await t.throwsAsync(alice.call(guestBook, "add_message", { text: "hi" }, { attachedDeposit: NEAR.parse("0.01") }));
+  t.regex(error!.message, /to post again/);
+
+  await worker.provider.fastForward(10);
+  await alice.call(guestBook, "add_message", { text: "hi" }, { attachedDeposit: NEAR.parse("0.01") });
+
+  // the deposit must pay for the storage
+  await worker.provider.fastForward(10);
+  await t.throwsAsync(alice.call(guestBook, "add_message", { text: "hi again" }));
+});
+
+// 0.1 NEAR and 0 as borsh u128
+const POINT_ONE = Buffer.from("000080f64ae1c7022d15000000000000", "hex");
+const ZERO = Buffer.alloc(16);
+
+function stateRecord(accountId: string, key: Buffer, value: Buffer) {
+  return { Data: { account_id: accountId, data_key: key.toString("base64"), value: value.toString("base64") } };
+}
+
+function borshU64(value: number): Buffer {
+  // the values used here fit in 32 bits
+  const buf = Buffer.alloc(8);
+  buf.writeUInt32LE(value);
+  return buf;
+}
+
+function borshString(value: string): Buffer {
+  const bytes = Buffer.from(value);
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(bytes.length);
+  return Buffer.concat([len, bytes]);
+}
+
+// A `Vector` is stored as its length and its prefix, and each entry under `prefix + index`
+function borshVector(prefix: string, len: number): Buffer {
+  return Buffer.concat([borshU64(len), borshString(prefix)]);
+}
+
+function vectorKey(prefix: string, idx: number): Buffer {
+  return Buffer.concat([Buffer.from(prefix), borshU64(idx)]);
+}
@@ -2,6 +2,10 @@ import { Worker, NEAR, NearAccount } from 'near-workspaces';
 import anyTest, { TestFn } from 'ava';
 import * as fs from 'fs';
 
+// one minute, in nanoseconds
+const UPGRADE_DELAY = "60000000000";
+const SELF_UPDATE_WASM = "../contracts/target/wasm32-unknown-unknown/release/self_update.wasm";
+
 const test = anyTest as TestFn<{
   worker: Worker;
   accounts: Record<string, NearAccount>;
@@ -21,7 +25,7 @@ test.beforeEach(async (t) => {
 
   // Deploy and init the contract
   await guestBook.deploy("./contracts/target/wasm32-unknown-unknown/release/self_base.wasm");
-  await guestBook.call(guestBook, "init", { manager: alice.accountId });
+  await guestBook.call(guestBook, "init", { manager: alice.accountId, upgrade_delay: UPGRADE_DELAY });
   // TODO: remove keys once workspaces-js allows it
 
   // add messages
@@ -51,28 +55,67 @@ test("by default it return the two messages and their payments", async (t) => {
     { premium: true, sender: alice.accountId, text: "bye" },
   ];
 
-  const expected_payments = [NEAR.parse("0.09").toString(), NEAR.parse("0.1").toString()];
+  // only premium messages keep their deposit
+  const expected_payments = ["0", NEAR.parse("0.1").toString()];
 
   t.deepEqual(msgs, expected_msgs);
   t.deepEqual(payments, expected_payments);
 });
 
+test("only the manager can stage code", async (t) => {
+  const { guestBook, bob } = t.context.accounts;
+  const code = fs.readFileSync(SELF_UPDATE_WASM);
+
+  await t.throwsAsync(bob.call(guestBook, "stage_code", code, { gas: "300000000000000" }));
+  t.is(await guestBook.view("code_hash"), null);
+});
+
+test("staged code can't be deployed before the delay", async (t) => {
+  const { guestBook, alice } = t.context.accounts;
+  const code = fs.readFileSync(SELF_UPDATE_WASM);
+
+  await alice.call(guestBook, "stage_code", code, { gas: "300000000000000" });
+  t.not(await guestBook.view("code_hash"), null);
+
+  await t.throwsAsync(alice.call(guestBook, "deploy_staged_code", {}, { gas: "300000000000000" }));
+
+  // The contract was not updated
+  const payments = await guestBook.view("get_payments");
+  t.is(payments.length, 2);
+});
+
+test("the manager can cancel staged code", async (t) => {
+  const { guestBook, alice } = t.context.accounts;
+  const code = fs.readFileSync(SELF_UPDATE_WASM);
+
+  await alice.call(guestBook, "stage_code", code, { gas: "300000000000000" });
+  await alice.call(guestBook, "cancel_staged_code", {});
+
+  t.is(await guestBook.view("code_hash"), null);
+  t.is(await guestBook.view("staged_code_available_at"), null);
+});
+
 test("the contract can update itself", async (t) => {
-  const { guestBook, alice, bob } = t.context.accounts;
-  const code = fs.readFileSync("../contracts/target/wasm32-unknown-unknown/release/self_update.wasm");
+  const { worker, accounts: { guestBook, alice, bob } } = t.context;
+  const code = fs.readFileSync(SELF_UPDATE_WASM);
 
-  // Alice is the manager, asks the contract to update itself
-  await alice.call(guestBook, "update_contract", code, {gas: "300000000000000"});
+  // Alice is the manager, stages the new code and waits for the timelock
+  await alice.call(guestBook, "stage_code", code, { gas: "300000000000000" });
+  await worker.provider.fastForward(100);
+
+  const deployed = await alice.call(guestBook, "deploy_staged_code", {}, { gas: "300000000000000" });
+  t.is(deployed, true);
 
   // The contract is updated
   await t.throwsAsync(guestBook.view("get_payments"));
+  t.is(await guestBook.view("code_hash"), null);
 
   const msgs = await guestBook.view("get_messages");
 
   const expected = [
-    { payment: 9e+22, premium: false, sender: bob.accountId, text: "hello" },
+    { payment: 0, premium: false, sender: bob.accountId, text: "hello" },
     { payment: 1e+23, premium: true, sender: alice.accountId, text: "bye" },
   ];
 
   t.deepEqual(msgs, expected)
-});
\ No newline at end of file
+});
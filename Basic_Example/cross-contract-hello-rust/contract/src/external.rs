@@ -1,8 +1,8 @@
-use near_sdk::ext_contract;
+use near_sdk::serde::Serialize;
+use near_sdk::{ext_contract, Gas};
+use xcc::CallArgs;
 
-pub const TGAS: u64 = 1_000_000_000_000;
-pub const NO_DEPOSIT: u128 = 0;
-pub const XCC_SUCCESS: u64 = 1;
+pub const XCC_GAS: Gas = xcc::tgas(5);
 
 // Validator interface, for cross-contract calls
 #[ext_contract(hello_near)]
@@ -10,3 +10,14 @@ trait HelloNear {
     fn get_greeting(&self) -> String;
     fn set_greeting(&self, message: String);
 }
+
+// Arguments of HelloNear's `set_greeting`, for the calls made through `xcc::Call`
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SetGreetingArgs {
+    pub message: String,
+}
+
+impl CallArgs for SetGreetingArgs {
+    const METHOD: &'static str = "set_greeting";
+}
@@ -1,5 +1,7 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, log, near_bindgen, AccountId, Gas, PanicOnDefault, Promise, PromiseError};
+use near_sdk::json_types::U64;
+use near_sdk::{env, near_bindgen, AccountId, Gas, PanicOnDefault, Promise};
+use xcc::{Call, LogError, Retry};
 
 pub mod external;
 pub use crate::external::*;
@@ -23,63 +25,75 @@ impl Contract {
     pub fn query_greeting(&self) -> Promise {
         // Create a promise to call HelloNEAR.get_greeting()
         let promise = hello_near::ext(self.hello_account.clone())
-            .with_static_gas(Gas(5 * TGAS))
+            .with_static_gas(XCC_GAS)
             .get_greeting();
 
         return promise.then(
             // Create a promise to callback query_greeting_callback
             Self::ext(env::current_account_id())
-                .with_static_gas(Gas(5 * TGAS))
+                .with_static_gas(XCC_GAS)
                 .query_greeting_callback(),
         );
     }
 
     #[private] // Public - but only callable by env::current_account_id()
-    pub fn query_greeting_callback(
-        &self,
-        #[callback_result] call_result: Result<String, PromiseError>,
-    ) -> String {
-        // Check if the promise succeeded by calling the method outlined in external.rs
-        if call_result.is_err() {
-            log!("There was an error contacting Hello NEAR");
-            return "".to_string();
-        }
-
-        // Return the greeting
-        let greeting: String = call_result.unwrap();
-        greeting
+    pub fn query_greeting_callback(&self) -> String {
+        // Return the greeting, or an empty one if Hello NEAR could not be contacted
+        xcc::promise_result::<String>(0)
+            .log_error("Hello NEAR")
+            .unwrap_or_default()
     }
 
     // Public - change external greeting
     pub fn change_greeting(&mut self, new_greeting: String) -> Promise {
         // Create a promise to call HelloNEAR.set_greeting(message:string)
         hello_near::ext(self.hello_account.clone())
-            .with_static_gas(Gas(5 * TGAS))
+            .with_static_gas(XCC_GAS)
             .set_greeting(new_greeting)
             .then(
                 // Create a callback change_greeting_callback
                 Self::ext(env::current_account_id())
-                    .with_static_gas(Gas(5 * TGAS))
+                    .with_static_gas(XCC_GAS)
                     .change_greeting_callback(),
             )
     }
 
     #[private]
-    pub fn change_greeting_callback(
-        &mut self,
-        #[callback_result] call_result: Result<(), PromiseError>,
-    ) -> bool {
-        // Return whether or not the promise succeeded using the method outlined in external.rs
-        if call_result.is_err() {
-            env::log_str("set_greeting failed...");
-            return false;
-        } else {
+    pub fn change_greeting_callback(&mut self) -> bool {
+        // Return whether or not the promise succeeded
+        let result = xcc::promise_result::<()>(0).log_error("set_greeting");
+        if result.is_ok() {
             env::log_str("set_greeting was successful!");
-            return true;
         }
+        result.is_ok()
+    }
+
+    // Public - change external greeting starting with `gas`, and calling again with twice
+    // the gas up to `attempts` times. The deposit is forwarded, and returned if the call fails
+    #[payable]
+    pub fn change_greeting_with_retry(
+        &mut self,
+        new_greeting: String,
+        gas: U64,
+        attempts: u8,
+    ) -> Promise {
+        Call::with_args(
+            self.hello_account.clone(),
+            &SetGreetingArgs {
+                message: new_greeting,
+            },
+        )
+        .deposit(env::attached_deposit())
+        .with_gas(Gas(gas.0))
+        .retry(Retry::new(attempts))
+        .resolve()
     }
 }
 
+xcc::impl_xcc_resolve!(Contract);
+
+nep_330::impl_contract_source_metadata!(Contract);
+
 #[cfg(test)]
 mod tests {
     use super::*;
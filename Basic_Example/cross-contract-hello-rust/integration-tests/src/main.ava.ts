@@ -1,4 +1,4 @@
-import { Worker, NearAccount } from 'near-workspaces';
+import { Worker, NearAccount, NEAR } from 'near-workspaces';
 import anyTest, { TestFn } from 'ava';
 
 const test = anyTest as TestFn<{
@@ -56,4 +56,37 @@ test("change the greeting", async (t) => {
 
   const howdy = await alice.call(xcc, "query_greeting", {}, { gas: "200000000000000" });
   t.is(howdy, 'Howdy');
-});
\ No newline at end of file
+});
+
+test("retries the call with more gas", async (t) => {
+  const { xcc, alice } = t.context.accounts;
+
+  // 1 TGas is not enough for set_greeting, the call is made again with 2, 4 and 8 TGas
+  const result = await alice.call(
+    xcc,
+    "change_greeting_with_retry",
+    { new_greeting: "Howdy", gas: "1000000000000", attempts: 3 },
+    { gas: "300000000000000" }
+  );
+  t.deepEqual(result, { Ok: null });
+
+  const howdy = await alice.call(xcc, "query_greeting", {}, { gas: "200000000000000" });
+  t.is(howdy, 'Howdy');
+});
+
+test("returns the deposit when the call fails", async (t) => {
+  const { xcc, alice } = t.context.accounts;
+
+  // set_greeting is not payable, so the call fails
+  const before = await alice.availableBalance();
+  const result = await alice.call(
+    xcc,
+    "change_greeting_with_retry",
+    { new_greeting: "Howdy", gas: "5000000000000", attempts: 0 },
+    { gas: "300000000000000", attachedDeposit: NEAR.parse("1 N").toString() }
+  );
+  const after = await alice.availableBalance();
+
+  t.deepEqual(result, { Err: "failed" });
+  t.true(before.sub(after).lt(NEAR.parse("0.1 N")));
+});
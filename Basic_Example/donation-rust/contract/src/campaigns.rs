@@ -0,0 +1,448 @@
+use crate::donation::Donation;
+use crate::{Contract, ContractExt, StorageKey};
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, is_promise_success, log, near_bindgen, require, AccountId, Balance, Gas, Promise,
+    PromiseOrValue, StorageUsage, Timestamp,
+};
+use nep_141::core::ext_ft_core;
+use nep_141::receiver::FungibleTokenReceiver;
+
+pub const TOTAL_BPS: u16 = 10_000;
+pub const MAX_BENEFICIARIES: usize = 10;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGAS
+const GAS_FOR_RESOLVE: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+/// Part of the raised funds going to `account_id`, in basis points (1/100 of a percent)
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Share {
+    pub account_id: AccountId,
+    pub bps: u16,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Campaign {
+    pub creator: AccountId,
+    pub title: String,
+    // `None` for campaigns in NEAR
+    pub token_id: Option<AccountId>,
+    pub goal: Balance,
+    pub deadline: Timestamp,
+    pub shares: Vec<Share>,
+    // Whether each beneficiary claimed its share
+    pub claimed: Vec<bool>,
+    pub raised: Balance,
+    pub donations: UnorderedMap<AccountId, Balance>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum CampaignStatus {
+    // Accepting donations
+    Active,
+    // The goal was met, the beneficiaries can claim their shares
+    Succeeded,
+    // The goal was not met, the donors can ask for a refund
+    Failed,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignView {
+    pub id: U64,
+    pub creator: AccountId,
+    pub title: String,
+    pub token_id: Option<AccountId>,
+    pub goal: U128,
+    pub deadline: U64,
+    pub shares: Vec<Share>,
+    pub raised: U128,
+    pub number_of_donors: u64,
+    pub status: CampaignStatus,
+}
+
+/// `msg` of the `ft_transfer_call` donating to a campaign
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct DonateMsg {
+    campaign_id: U64,
+}
+
+impl Campaign {
+    pub fn status(&self) -> CampaignStatus {
+        if env::block_timestamp() < self.deadline {
+            CampaignStatus::Active
+        } else if self.raised >= self.goal {
+            CampaignStatus::Succeeded
+        } else {
+            CampaignStatus::Failed
+        }
+    }
+
+    /// Amount raised for the beneficiary `index`, the first one gets the rounding leftovers
+    pub fn share_amount(&self, index: usize) -> Balance {
+        let amount_of = |share: &Share| self.raised * share.bps as Balance / TOTAL_BPS as Balance;
+        if index == 0 {
+            self.raised - self.shares[1..].iter().map(amount_of).sum::<Balance>()
+        } else {
+            amount_of(&self.shares[index])
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a campaign raising `goal` in NEAR, or in the NEP-141 `token_id`, until `deadline`
+    /// (in nanoseconds). The attached deposit pays for its storage, the rest is returned.
+    #[payable]
+    pub fn create_campaign(
+        &mut self,
+        title: String,
+        goal: U128,
+        deadline: U64,
+        shares: Vec<Share>,
+        token_id: Option<AccountId>,
+    ) -> U64 {
+        require!(goal.0 > 0, "The goal must be positive");
+        require!(
+            deadline.0 > env::block_timestamp(),
+            "The deadline must be in the future"
+        );
+        assert_valid_shares(&shares);
+
+        let initial_storage = env::storage_usage();
+        let id = self.campaigns.len();
+        let claimed = vec![false; shares.len()];
+        self.campaigns.push(&Campaign {
+            creator: env::predecessor_account_id(),
+            title,
+            token_id,
+            goal: goal.0,
+            deadline: deadline.0,
+            shares,
+            claimed,
+            raised: 0,
+            donations: UnorderedMap::new(StorageKey::CampaignDonations { campaign_id: id }),
+        });
+
+        let cost = storage_cost(initial_storage);
+        let attached = env::attached_deposit();
+        require!(
+            attached >= cost,
+            format!("Attach at least {cost} yoctoNEAR")
+        );
+        if attached > cost {
+            Promise::new(env::predecessor_account_id()).transfer(attached - cost);
+        }
+
+        log!("Created campaign {}", id);
+        U64(id)
+    }
+
+    /// Donates the attached deposit to a NEAR campaign. The first donation pays for the storage
+    /// of the donor's record, unless they registered with `register_donor`.
+    #[payable]
+    pub fn donate_to_campaign(&mut self, campaign_id: U64) -> U128 {
+        let donor = env::predecessor_account_id();
+        let mut campaign = self.internal_get_active_campaign(campaign_id.0);
+        require!(
+            campaign.token_id.is_none(),
+            "Donate to this campaign through ft_transfer_call"
+        );
+
+        let initial_storage = env::storage_usage();
+        let mut donated_so_far = campaign.donations.get(&donor).unwrap_or(0);
+        campaign.donations.insert(&donor, &donated_so_far);
+
+        let cost = storage_cost(initial_storage);
+        let attached = env::attached_deposit();
+        require!(attached > cost, format!("Attach at least {cost} yoctoNEAR"));
+
+        let donation_amount = attached - cost;
+        donated_so_far += donation_amount;
+        campaign.donations.insert(&donor, &donated_so_far);
+        campaign.raised += donation_amount;
+        self.campaigns.replace(campaign_id.0, &campaign);
+
+        log!(
+            "Thank you {} for donating {} to campaign {}!",
+            donor,
+            donation_amount,
+            campaign_id.0
+        );
+        U128(donated_so_far)
+    }
+
+    /// Pays for the storage of the caller's record in a campaign, which donations of tokens
+    /// can not pay for
+    #[payable]
+    pub fn register_donor(&mut self, campaign_id: U64) {
+        let donor = env::predecessor_account_id();
+        let mut campaign = self.internal_get_active_campaign(campaign_id.0);
+
+        let initial_storage = env::storage_usage();
+        if campaign.donations.get(&donor).is_none() {
+            campaign.donations.insert(&donor, &0);
+            self.campaigns.replace(campaign_id.0, &campaign);
+        }
+
+        let cost = storage_cost(initial_storage);
+        let attached = env::attached_deposit();
+        require!(
+            attached >= cost,
+            format!("Attach at least {cost} yoctoNEAR")
+        );
+        if attached > cost {
+            Promise::new(donor).transfer(attached - cost);
+        }
+    }
+
+    /// Returns the donation of the caller to a campaign that missed its goal, along with the
+    /// storage of their record
+    pub fn refund(&mut self, campaign_id: U64) -> Promise {
+        let donor = env::predecessor_account_id();
+        let mut campaign = self.internal_get_campaign(campaign_id.0);
+        require!(
+            campaign.status() == CampaignStatus::Failed,
+            "Only the donations to failed campaigns are refunded"
+        );
+
+        let initial_storage = env::storage_usage();
+        let amount = campaign
+            .donations
+            .remove(&donor)
+            .unwrap_or_else(|| env::panic_str("Nothing to refund"));
+        self.campaigns.replace(campaign_id.0, &campaign);
+        let storage = storage_refund(initial_storage);
+
+        // The storage is returned once the donation is
+        send(&campaign.token_id, &donor, amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE)
+                .resolve_refund(campaign_id, donor, U128(amount), U128(storage)),
+        )
+    }
+
+    /// Sends its share of a successful campaign to the calling beneficiary
+    pub fn claim_share(&mut self, campaign_id: U64) -> Promise {
+        let beneficiary = env::predecessor_account_id();
+        let mut campaign = self.internal_get_campaign(campaign_id.0);
+        require!(
+            campaign.status() == CampaignStatus::Succeeded,
+            "Only the shares of successful campaigns can be claimed"
+        );
+
+        let index = campaign
+            .shares
+            .iter()
+            .position(|share| share.account_id == beneficiary)
+            .unwrap_or_else(|| env::panic_str("Not a beneficiary of the campaign"));
+        require!(!campaign.claimed[index], "The share was already claimed");
+
+        campaign.claimed[index] = true;
+        self.campaigns.replace(campaign_id.0, &campaign);
+
+        let amount = campaign.share_amount(index);
+        send(&campaign.token_id, &beneficiary, amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE)
+                .resolve_claim(campaign_id, index as u32),
+        )
+    }
+
+    #[private]
+    pub fn resolve_refund(
+        &mut self,
+        campaign_id: U64,
+        donor: AccountId,
+        amount: U128,
+        storage: U128,
+    ) -> bool {
+        if is_promise_success() {
+            Promise::new(donor).transfer(storage.0);
+            return true;
+        }
+
+        // The funds are still here, the donor can try again, e.g. once registered in the token
+        log!("Failed to refund {} to {}", amount.0, donor);
+        let mut campaign = self.internal_get_campaign(campaign_id.0);
+        campaign.donations.insert(&donor, &amount.0);
+        self.campaigns.replace(campaign_id.0, &campaign);
+        false
+    }
+
+    #[private]
+    pub fn resolve_claim(&mut self, campaign_id: U64, index: u32) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+
+        let mut campaign = self.internal_get_campaign(campaign_id.0);
+        log!(
+            "Failed to send its share to {}",
+            campaign.shares[index as usize].account_id
+        );
+        campaign.claimed[index as usize] = false;
+        self.campaigns.replace(campaign_id.0, &campaign);
+        false
+    }
+
+    pub fn get_campaign(&self, campaign_id: U64) -> Option<CampaignView> {
+        self.campaigns
+            .get(campaign_id.0)
+            .map(|campaign| campaign_view(campaign_id.0, campaign))
+    }
+
+    pub fn number_of_campaigns(&self) -> u64 {
+        self.campaigns.len()
+    }
+
+    pub fn get_campaigns(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<CampaignView> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.campaigns
+            .iter()
+            .enumerate()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(id, campaign)| campaign_view(id as u64, campaign))
+            .collect()
+    }
+
+    pub fn get_campaign_donors(
+        &self,
+        campaign_id: U64,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Donation> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        let campaign = self.internal_get_campaign(campaign_id.0);
+
+        campaign
+            .donations
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(account_id, total_amount)| Donation {
+                account_id,
+                total_amount: U128(total_amount),
+            })
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Donates the tokens to the campaign in `msg`, as `{"campaign_id": "0"}`. Panicking makes
+    /// the token return them to the sender.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let DonateMsg { campaign_id } = near_sdk::serde_json::from_str(&msg)
+            .unwrap_or_else(|_| env::panic_str("The msg must be {\"campaign_id\": \"<id>\"}"));
+        let mut campaign = self.internal_get_active_campaign(campaign_id.0);
+        require!(
+            campaign.token_id.as_ref() == Some(&env::predecessor_account_id()),
+            "The campaign does not raise this token"
+        );
+
+        let donated_so_far = campaign
+            .donations
+            .get(&sender_id)
+            .unwrap_or_else(|| env::panic_str("Call register_donor before donating tokens"));
+        campaign
+            .donations
+            .insert(&sender_id, &(donated_so_far + amount.0));
+        campaign.raised += amount.0;
+        self.campaigns.replace(campaign_id.0, &campaign);
+
+        log!(
+            "Thank you {} for donating {} to campaign {}!",
+            sender_id,
+            amount.0,
+            campaign_id.0
+        );
+        PromiseOrValue::Value(U128(0))
+    }
+}
+
+impl Contract {
+    fn internal_get_campaign(&self, campaign_id: u64) -> Campaign {
+        self.campaigns
+            .get(campaign_id)
+            .unwrap_or_else(|| env::panic_str("Campaign not found"))
+    }
+
+    fn internal_get_active_campaign(&self, campaign_id: u64) -> Campaign {
+        let campaign = self.internal_get_campaign(campaign_id);
+        require!(
+            campaign.status() == CampaignStatus::Active,
+            "The campaign is over"
+        );
+        campaign
+    }
+}
+
+fn assert_valid_shares(shares: &[Share]) {
+    require!(
+        !shares.is_empty() && shares.len() <= MAX_BENEFICIARIES,
+        format!("A campaign has between 1 and {MAX_BENEFICIARIES} beneficiaries")
+    );
+    for (index, share) in shares.iter().enumerate() {
+        require!(
+            shares[..index]
+                .iter()
+                .all(|other| other.account_id != share.account_id),
+            format!("{} is listed twice", share.account_id)
+        );
+    }
+    let total: u32 = shares.iter().map(|share| share.bps as u32).sum();
+    require!(
+        total == TOTAL_BPS as u32,
+        format!("The shares must add up to {TOTAL_BPS} basis points")
+    );
+}
+
+/// Sends `amount` of the campaign's token, or NEAR, to `receiver_id`
+fn send(token_id: &Option<AccountId>, receiver_id: &AccountId, amount: Balance) -> Promise {
+    match token_id {
+        None => Promise::new(receiver_id.clone()).transfer(amount),
+        Some(token_id) => ext_ft_core::ext(token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(receiver_id.clone(), U128(amount), None),
+    }
+}
+
+fn campaign_view(id: u64, campaign: Campaign) -> CampaignView {
+    CampaignView {
+        id: U64(id),
+        status: campaign.status(),
+        number_of_donors: campaign.donations.len(),
+        creator: campaign.creator,
+        title: campaign.title,
+        token_id: campaign.token_id,
+        goal: U128(campaign.goal),
+        deadline: U64(campaign.deadline),
+        shares: campaign.shares,
+        raised: U128(campaign.raised),
+    }
+}
+
+fn storage_cost(initial_storage: StorageUsage) -> Balance {
+    env::storage_byte_cost() * (env::storage_usage() - initial_storage) as Balance
+}
+
+fn storage_refund(initial_storage: StorageUsage) -> Balance {
+    env::storage_byte_cost() * (initial_storage - env::storage_usage()) as Balance
+}
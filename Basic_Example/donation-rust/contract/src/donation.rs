@@ -4,7 +4,7 @@ use crate::ContractExt;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U128;
 use near_sdk::serde::Serialize;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, Promise};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance};
 
 pub const STORAGE_COST: u128 = 1_000_000_000_000_000_000_000;
 
@@ -23,36 +23,36 @@ impl Contract {
         let donor: AccountId = env::predecessor_account_id();
         let donation_amount: Balance = env::attached_deposit();
 
-        let mut donated_so_far = self.donations.get(&donor).unwrap_or(0);
+        let donated_so_far = self.donations.get(&donor);
 
-        let to_transfer: Balance = if donated_so_far == 0 {
+        let storage_fee: Balance = if donated_so_far.is_none() {
             // This is the user's first donation, lets register it, which increases storage
             assert!(
                 donation_amount > STORAGE_COST,
                 "Attach at least {} yoctoNEAR",
                 STORAGE_COST
             );
-
-            // Subtract the storage cost to the amount to transfer
-            donation_amount - STORAGE_COST
+            STORAGE_COST
         } else {
-            donation_amount
+            0
         };
 
-        // Persist in storage the amount donated so far
-        donated_so_far += donation_amount;
+        // Persist in storage the amount donated so far, without the storage fee
+        let donated = donation_amount - storage_fee;
+        let donated_so_far = donated_so_far.unwrap_or(0) + donated;
         self.donations.insert(&donor, &donated_so_far);
 
+        // The beneficiary withdraws the donations when they want
+        self.ledger.donated += donated;
+        self.ledger.storage_fees += storage_fee;
+
         log!(
             "Thank you {} for donating {}! You donated a total of {}",
             donor.clone(),
-            donation_amount,
+            donated,
             donated_so_far
         );
 
-        // Send the money to the beneficiary
-        Promise::new(self.beneficiary.clone()).transfer(to_transfer);
-
         // Return the total amount donated so far
         U128(donated_so_far)
     }
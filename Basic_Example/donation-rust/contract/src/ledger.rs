@@ -0,0 +1,90 @@
+use crate::Contract;
+use crate::ContractExt;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, is_promise_success, log, near_bindgen, require, Balance, Gas, Promise};
+
+const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(5_000_000_000_000); // 5 TGAS
+
+/// What the contract holds for the beneficiary. The storage fees paid by the donors are kept
+/// apart, since they pay for the storage of their records.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Ledger {
+    // Donated, without the storage fees
+    pub donated: Balance,
+    pub storage_fees: Balance,
+    pub withdrawn: Balance,
+    // Sent to the beneficiary, waiting for the transfer to succeed
+    pub pending: Balance,
+}
+
+impl Ledger {
+    pub fn available(&self) -> Balance {
+        self.donated - self.withdrawn - self.pending
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LedgerView {
+    pub donated: U128,
+    pub storage_fees: U128,
+    pub withdrawn: U128,
+    pub pending: U128,
+    pub available: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sends `amount` of the donations to the beneficiary, all of them by default. If the
+    /// transfer fails, the amount becomes available again.
+    pub fn withdraw(&mut self, amount: Option<U128>) -> Promise {
+        require!(
+            env::predecessor_account_id() == self.beneficiary,
+            "Only the beneficiary can withdraw"
+        );
+
+        let available = self.ledger.available();
+        let amount = amount.map(u128::from).unwrap_or(available);
+        require!(amount > 0, "Nothing to withdraw");
+        require!(
+            amount <= available,
+            format!("Only {available} yoctoNEAR can be withdrawn")
+        );
+
+        self.ledger.pending += amount;
+        Promise::new(self.beneficiary.clone())
+            .transfer(amount)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_WITHDRAW)
+                    .resolve_withdraw(U128(amount)),
+            )
+    }
+
+    #[private]
+    pub fn resolve_withdraw(&mut self, amount: U128) -> bool {
+        self.ledger.pending -= amount.0;
+
+        let transferred = is_promise_success();
+        if transferred {
+            self.ledger.withdrawn += amount.0;
+        } else {
+            log!("Failed to transfer {} to the beneficiary", amount.0);
+        }
+        transferred
+    }
+
+    // Public - what was donated, withdrawn and is left to withdraw
+    pub fn get_ledger(&self) -> LedgerView {
+        LedgerView {
+            donated: U128(self.ledger.donated),
+            storage_fees: U128(self.ledger.storage_fees),
+            withdrawn: U128(self.ledger.withdrawn),
+            pending: U128(self.ledger.pending),
+            available: U128(self.ledger.available()),
+        }
+    }
+}
@@ -1,14 +1,34 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::{near_bindgen, AccountId};
+use near_sdk::collections::{UnorderedMap, Vector};
+use near_sdk::{env, near_bindgen, AccountId, BorshStorageKey};
 
+use campaigns::Campaign;
+use ledger::Ledger;
+
+mod campaigns;
 mod donation;
+mod ledger;
+
+#[derive(BorshSerialize, BorshStorageKey)]
+pub(crate) enum StorageKey {
+    Campaigns,
+    CampaignDonations { campaign_id: u64 },
+}
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Contract {
     pub beneficiary: AccountId,
     pub donations: UnorderedMap<AccountId, u128>,
+    pub campaigns: Vector<Campaign>,
+    pub ledger: Ledger,
+}
+
+// The contract before campaigns and the ledger were added
+#[derive(BorshDeserialize, BorshSerialize)]
+struct OldContract {
+    beneficiary: AccountId,
+    donations: UnorderedMap<AccountId, u128>,
 }
 
 impl Default for Contract {
@@ -16,6 +36,8 @@ impl Default for Contract {
         Self {
             beneficiary: "v1.faucet.nonofficial.testnet".parse().unwrap(),
             donations: UnorderedMap::new(b"d"),
+            campaigns: Vector::new(StorageKey::Campaigns),
+            ledger: Ledger::default(),
         }
     }
 }
@@ -28,6 +50,24 @@ impl Contract {
         Self {
             beneficiary,
             donations: UnorderedMap::new(b"d"),
+            campaigns: Vector::new(StorageKey::Campaigns),
+            ledger: Ledger::default(),
+        }
+    }
+
+    // Public - but only callable by env::current_account_id(). Reads the state of the contract
+    // deployed before campaigns and the ledger. The old donations were sent to the beneficiary
+    // right away, so the ledger starts empty
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let old: OldContract =
+            env::state_read().unwrap_or_else(|| env::panic_str("No state to migrate"));
+        Self {
+            beneficiary: old.beneficiary,
+            donations: old.donations,
+            campaigns: Vector::new(StorageKey::Campaigns),
+            ledger: Ledger::default(),
         }
     }
 
@@ -43,15 +83,21 @@ impl Contract {
     }
 }
 
+nep_330::impl_contract_source_metadata!(Contract);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::campaigns::{CampaignStatus, Share};
+    use crate::donation::STORAGE_COST;
+    use near_sdk::json_types::{U128, U64};
     use near_sdk::test_utils::VMContextBuilder;
-    use near_sdk::testing_env;
-    use near_sdk::Balance;
+    use near_sdk::{testing_env, PromiseResult, RuntimeFeesConfig, VMConfig};
+    use near_sdk::{Balance, Timestamp};
 
     const BENEFICIARY: &str = "beneficiary";
     const NEAR: u128 = 1000000000000000000000000;
+    const DEADLINE: Timestamp = 1_000_000_000;
 
     #[test]
     fn initializes() {
@@ -68,8 +114,8 @@ mod tests {
         contract.donate();
         let first_donation = contract.get_donation_for_account("donor_a".parse().unwrap());
 
-        // Check the donation was recorded correctly
-        assert_eq!(first_donation.total_amount.0, 1 * NEAR);
+        // Check the donation was recorded correctly, without the storage fee
+        assert_eq!(first_donation.total_amount.0, 1 * NEAR - STORAGE_COST);
 
         // Make another donation
         set_context("donor_b", 2 * NEAR);
@@ -77,7 +123,7 @@ mod tests {
         let second_donation = contract.get_donation_for_account("donor_b".parse().unwrap());
 
         // Check the donation was recorded correctly
-        assert_eq!(second_donation.total_amount.0, 2 * NEAR);
+        assert_eq!(second_donation.total_amount.0, 2 * NEAR - STORAGE_COST);
 
         // User A makes another donation on top of their original
         set_context("donor_a", 1 * NEAR);
@@ -85,16 +131,251 @@ mod tests {
         let first_donation = contract.get_donation_for_account("donor_a".parse().unwrap());
 
         // Check the donation was recorded correctly
-        assert_eq!(first_donation.total_amount.0, 1 * NEAR * 2);
+        assert_eq!(first_donation.total_amount.0, 1 * NEAR * 2 - STORAGE_COST);
 
         assert_eq!(contract.number_of_donors(), 2);
+
+        let ledger = contract.get_ledger();
+        assert_eq!(ledger.donated.0, 4 * NEAR - 2 * STORAGE_COST);
+        assert_eq!(ledger.storage_fees.0, 2 * STORAGE_COST);
+        assert_eq!(ledger.available.0, ledger.donated.0);
+    }
+
+    #[test]
+    fn beneficiary_withdraws() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        set_context("donor_a", 1 * NEAR);
+        contract.donate();
+
+        set_context(BENEFICIARY, 0);
+        contract.withdraw(Some(U128(NEAR / 2)));
+        assert_eq!(contract.get_ledger().pending.0, NEAR / 2);
+
+        set_callback_context(PromiseResult::Successful(vec![]));
+        contract.resolve_withdraw(U128(NEAR / 2));
+
+        let ledger = contract.get_ledger();
+        assert_eq!(ledger.pending.0, 0);
+        assert_eq!(ledger.withdrawn.0, NEAR / 2);
+        assert_eq!(ledger.available.0, NEAR / 2 - STORAGE_COST);
+    }
+
+    #[test]
+    fn failed_withdrawals_are_available_again() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        set_context("donor_a", 1 * NEAR);
+        contract.donate();
+
+        set_context(BENEFICIARY, 0);
+        contract.withdraw(None);
+        assert_eq!(contract.get_ledger().available.0, 0);
+
+        set_callback_context(PromiseResult::Failed);
+        contract.resolve_withdraw(U128(NEAR - STORAGE_COST));
+
+        let ledger = contract.get_ledger();
+        assert_eq!(ledger.withdrawn.0, 0);
+        assert_eq!(ledger.available.0, NEAR - STORAGE_COST);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the beneficiary can withdraw")]
+    fn only_beneficiary_withdraws() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        set_context("donor_a", 1 * NEAR);
+        contract.donate();
+        contract.withdraw(None);
+    }
+
+    fn shares() -> Vec<Share> {
+        vec![
+            Share {
+                account_id: "beneficiary_a".parse().unwrap(),
+                bps: 7_000,
+            },
+            Share {
+                account_id: "beneficiary_b".parse().unwrap(),
+                bps: 3_000,
+            },
+        ]
+    }
+
+    fn create_campaign(contract: &mut Contract, goal: Balance) -> U64 {
+        set_context("creator", NEAR);
+        contract.create_campaign(
+            "Campaign".to_string(),
+            U128(goal),
+            U64(DEADLINE),
+            shares(),
+            None,
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "The shares must add up to 10000 basis points")]
+    fn campaign_shares_add_up() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let mut shares = shares();
+        shares[1].bps = 2_000;
+
+        set_context("creator", NEAR);
+        contract.create_campaign(
+            "Campaign".to_string(),
+            U128(NEAR),
+            U64(DEADLINE),
+            shares,
+            None,
+        );
+    }
+
+    #[test]
+    fn splits_successful_campaigns() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, 3 * NEAR);
+
+        set_context("donor_a", 2 * NEAR);
+        contract.donate_to_campaign(id);
+        set_context("donor_b", 2 * NEAR);
+        contract.donate_to_campaign(id);
+        assert_eq!(contract.get_campaign_donors(id, None, None).len(), 2);
+
+        set_context_at("beneficiary_b", 0, DEADLINE);
+        let view = contract.get_campaign(id).unwrap();
+        assert_eq!(view.status, CampaignStatus::Succeeded);
+
+        // The shares add up to what was raised, without the storage of the donors
+        let campaign = contract.campaigns.get(id.0).unwrap();
+        assert_eq!(
+            campaign.share_amount(0) + campaign.share_amount(1),
+            view.raised.0
+        );
+        assert_eq!(campaign.share_amount(1), view.raised.0 * 3 / 10);
+
+        contract.claim_share(id);
+        assert!(contract.campaigns.get(id.0).unwrap().claimed[1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The share was already claimed")]
+    fn shares_are_claimed_once() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, NEAR);
+
+        set_context("donor_a", 2 * NEAR);
+        contract.donate_to_campaign(id);
+
+        set_context_at("beneficiary_a", 0, DEADLINE);
+        contract.claim_share(id);
+        contract.claim_share(id);
+    }
+
+    #[test]
+    fn refunds_failed_campaigns() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, 10 * NEAR);
+
+        set_context("donor_a", NEAR);
+        contract.donate_to_campaign(id);
+
+        set_context_at("donor_a", 0, DEADLINE);
+        assert_eq!(
+            contract.get_campaign(id).unwrap().status,
+            CampaignStatus::Failed
+        );
+        contract.refund(id);
+        assert!(contract.get_campaign_donors(id, None, None).is_empty());
+    }
+
+    #[test]
+    fn failed_refunds_are_restored() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, 10 * NEAR);
+
+        set_context("donor_a", NEAR);
+        let donated = contract.donate_to_campaign(id);
+
+        set_context_at("donor_a", 0, DEADLINE);
+        contract.refund(id);
+
+        set_callback_context(PromiseResult::Failed);
+        assert!(!contract.resolve_refund(id, "donor_a".parse().unwrap(), donated, U128(0)));
+        let donors = contract.get_campaign_donors(id, None, None);
+        assert_eq!(donors[0].total_amount, donated);
+    }
+
+    #[test]
+    fn failed_claims_can_be_claimed_again() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, NEAR);
+
+        set_context("donor_a", 2 * NEAR);
+        contract.donate_to_campaign(id);
+
+        set_context_at("beneficiary_a", 0, DEADLINE);
+        contract.claim_share(id);
+
+        set_callback_context(PromiseResult::Failed);
+        assert!(!contract.resolve_claim(id, 0));
+        assert!(!contract.campaigns.get(id.0).unwrap().claimed[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The campaign is over")]
+    fn rejects_late_donations() {
+        let mut contract = Contract::init(BENEFICIARY.parse().unwrap());
+        let id = create_campaign(&mut contract, NEAR);
+
+        set_context_at("donor_a", NEAR, DEADLINE);
+        contract.donate_to_campaign(id);
+    }
+
+    #[test]
+    fn migrates_the_old_contract() {
+        set_context("donor_a", 0);
+        let mut donations = UnorderedMap::new(b"d");
+        donations.insert(&"donor_a".parse().unwrap(), &NEAR);
+        env::state_write(&OldContract {
+            beneficiary: BENEFICIARY.parse().unwrap(),
+            donations,
+        });
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.beneficiary, BENEFICIARY.parse().unwrap());
+        assert_eq!(contract.number_of_donors(), 1);
+        assert_eq!(
+            contract
+                .get_donation_for_account("donor_a".parse().unwrap())
+                .total_amount
+                .0,
+            NEAR
+        );
+        assert_eq!(contract.get_ledger().donated.0, 0);
+        assert_eq!(contract.campaigns.len(), 0);
     }
 
     // Auxiliar fn: create a mock context
     fn set_context(predecessor: &str, amount: Balance) {
+        set_context_at(predecessor, amount, 0);
+    }
+
+    fn set_callback_context(result: PromiseResult) {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(builder.context.current_account_id.clone());
+
+        testing_env!(
+            builder.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    fn set_context_at(predecessor: &str, amount: Balance, timestamp: Timestamp) {
         let mut builder = VMContextBuilder::new();
         builder.predecessor_account_id(predecessor.parse().unwrap());
         builder.attached_deposit(amount);
+        builder.block_timestamp(timestamp);
 
         testing_env!(builder.build());
     }
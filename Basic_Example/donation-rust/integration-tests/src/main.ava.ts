@@ -1,78 +1,203 @@
-import { Worker, NEAR, NearAccount } from "near-workspaces";
-import anyTest, { TestFn } from "ava";
-
-const test = anyTest as TestFn<{
-  worker: Worker;
-  accounts: Record<string, NearAccount>;
-}>;
-
-test.beforeEach(async (t) => {
-  // Init the worker and start a Sandbox server
-  const worker = await Worker.init();
-
-  const root = worker.rootAccount;
-
-  // define users
-  const beneficiary = await root.createSubAccount("beneficiary", {
-    initialBalance: NEAR.parse("30 N").toJSON(),
-  });
-
-  const alice = await root.createSubAccount("alice", {
-    initialBalance: NEAR.parse("30 N").toJSON(),
-  });
-
-  const bob = await root.createSubAccount("bob", {
-    initialBalance: NEAR.parse("30 N").toJSON(),
-  });
-
-  const contract = await root.createSubAccount("contract", {
-    initialBalance: NEAR.parse("30 N").toJSON(),
-  });
-  
-  // Deploy the contract.
-  await contract.deploy(process.argv[2]);
-
-  // Initialize beneficiary
-  await contract.call(contract, "init", {beneficiary: beneficiary.accountId})
-
-  // Save state for test runs, it is unique for each test
-  t.context.worker = worker;
-  t.context.accounts = { root, contract, beneficiary, alice, bob };
-});
-
-test.afterEach(async (t) => {
-  // Stop Sandbox server
-  await t.context.worker.tearDown().catch((error) => {
-    console.log("Failed to stop the Sandbox:", error);
-  });
-});
-
-test("sends donations to the beneficiary", async (t) => {
-  const { contract, alice, beneficiary } = t.context.accounts;
-
-  const balance = await beneficiary.balance();
-  const available = parseFloat(balance.available.toHuman());
-
-  await alice.call(contract, "donate", {}, { attachedDeposit: NEAR.parse("1 N").toString() });
-
-  const new_balance = await beneficiary.balance();
-  const new_available = parseFloat(new_balance.available.toHuman());
-
-  t.is(new_available, available + 1 - 0.001);
-});
-
-test("records the donation", async (t) => {
-  const { contract, bob } = t.context.accounts;
-
-  await bob.call(contract, "donate", {}, { attachedDeposit: NEAR.parse("2 N").toString() });
-
-  const donation: Donation = await contract.view("get_donation_for_account", { account_id: bob.accountId });
-
-  t.is(donation.account_id, bob.accountId);
-  t.is(donation.total_amount, NEAR.parse("2 N").toString());
-});
-
-class Donation{
-  account_id: string = "";
-  total_amount: string = "";
+import { Worker, NEAR, NearAccount } from "near-workspaces";
+import anyTest, { TestFn } from "ava";
+
+const test = anyTest as TestFn<{
+  worker: Worker;
+  accounts: Record<string, NearAccount>;
+}>;
+
+test.beforeEach(async (t) => {
+  // Init the worker and start a Sandbox server
+  const worker = await Worker.init();
+
+  const root = worker.rootAccount;
+
+  // define users
+  const beneficiary = await root.createSubAccount("beneficiary", {
+    initialBalance: NEAR.parse("30 N").toJSON(),
+  });
+
+  const alice = await root.createSubAccount("alice", {
+    initialBalance: NEAR.parse("30 N").toJSON(),
+  });
+
+  const bob = await root.createSubAccount("bob", {
+    initialBalance: NEAR.parse("30 N").toJSON(),
+  });
+
+  const contract = await root.createSubAccount("contract", {
+    initialBalance: NEAR.parse("30 N").toJSON(),
+  });
+  
+  // Deploy the contract.
+  await contract.deploy(process.argv[2]);
+
+  // Initialize beneficiary
+  await contract.call(contract, "init", {beneficiary: beneficiary.accountId})
+
+  // Save state for test runs, it is unique for each test
+  t.context.worker = worker;
+  t.context.accounts = { root, contract, beneficiary, alice, bob };
+});
+
+test.afterEach(async (t) => {
+  // Stop Sandbox server
+  await t.context.worker.tearDown().catch((error) => {
+    console.log("Failed to stop the Sandbox:", error);
+  });
+});
+
+test("the beneficiary withdraws the donations", async (t) => {
+  const { contract, alice, beneficiary } = t.context.accounts;
+
+  const before = await beneficiary.availableBalance();
+  await alice.call(contract, "donate", {}, { attachedDeposit: NEAR.parse("1 N").toString() });
+
+  // nothing is sent until the beneficiary asks for it
+  t.true((await beneficiary.availableBalance()).eq(before));
+  t.like(await contract.view("get_ledger"), {
+    donated: NEAR.parse("0.999 N").toString(),
+    storage_fees: NEAR.parse("0.001 N").toString(),
+    available: NEAR.parse("0.999 N").toString(),
+  });
+
+  await beneficiary.call(contract, "withdraw", {});
+  const after = await beneficiary.availableBalance();
+
+  // 0.999 N, minus the gas
+  t.true(after.sub(before).gt(NEAR.parse("0.99 N")));
+  t.like(await contract.view("get_ledger"), {
+    withdrawn: NEAR.parse("0.999 N").toString(),
+    pending: "0",
+    available: "0",
+  });
+});
+
+test("only the beneficiary withdraws what is available", async (t) => {
+  const { contract, alice, beneficiary } = t.context.accounts;
+  await alice.call(contract, "donate", {}, { attachedDeposit: NEAR.parse("1 N").toString() });
+
+  await t.throwsAsync(alice.call(contract, "withdraw", {}));
+  await t.throwsAsync(beneficiary.call(contract, "withdraw", { amount: NEAR.parse("1 N").toString() }));
+
+  await beneficiary.call(contract, "withdraw", { amount: NEAR.parse("0.5 N").toString() });
+  t.like(await contract.view("get_ledger"), { available: NEAR.parse("0.499 N").toString() });
+});
+
+test("records the donation", async (t) => {
+  const { contract, bob } = t.context.accounts;
+
+  await bob.call(contract, "donate", {}, { attachedDeposit: NEAR.parse("2 N").toString() });
+
+  const donation: Donation = await contract.view("get_donation_for_account", { account_id: bob.accountId });
+
+  // the storage fee is not part of the donation
+  t.is(donation.account_id, bob.accountId);
+  t.is(donation.total_amount, NEAR.parse("1.999 N").toString());
+});
+
+// A deadline `seconds` from now, in nanoseconds
+function deadlineIn(seconds: number): string {
+  return `${Date.now() + seconds * 1000}000000`;
+}
+
+const SHARES = (beneficiary: NearAccount, bob: NearAccount) => [
+  { account_id: beneficiary.accountId, bps: 7000 },
+  { account_id: bob.accountId, bps: 3000 },
+];
+
+test("campaigns split what they raised between their beneficiaries", async (t) => {
+  const { worker, accounts: { contract, alice, bob, beneficiary } } = t.context;
+
+  const id: string = await alice.call(
+    contract,
+    "create_campaign",
+    { title: "Trees", goal: NEAR.parse("1 N").toString(), deadline: deadlineIn(30), shares: SHARES(beneficiary, bob) },
+    { attachedDeposit: NEAR.parse("0.1 N").toString() }
+  );
+  await alice.call(contract, "donate_to_campaign", { campaign_id: id }, { attachedDeposit: NEAR.parse("2 N").toString() });
+
+  // shares are claimed once the deadline passed
+  await t.throwsAsync(beneficiary.call(contract, "claim_share", { campaign_id: id }));
+  await worker.provider.fastForward(100);
+
+  const campaign: any = await contract.view("get_campaign", { campaign_id: id });
+  t.is(campaign.status, "succeeded");
+  t.deepEqual(await contract.view("get_campaign_donors", { campaign_id: id }), [
+    { account_id: alice.accountId, total_amount: campaign.raised },
+  ]);
+
+  const before = await beneficiary.availableBalance();
+  await beneficiary.call(contract, "claim_share", { campaign_id: id });
+  const after = await beneficiary.availableBalance();
+
+  // 70% of almost 2 N, minus the gas
+  t.true(after.sub(before).gt(NEAR.parse("1.39 N")));
+  await t.throwsAsync(beneficiary.call(contract, "claim_share", { campaign_id: id }));
+});
+
+test("donors get a refund when the goal is not met", async (t) => {
+  const { worker, accounts: { contract, alice, bob, beneficiary } } = t.context;
+
+  const id: string = await bob.call(
+    contract,
+    "create_campaign",
+    { title: "Trees", goal: NEAR.parse("10 N").toString(), deadline: deadlineIn(30), shares: SHARES(beneficiary, bob) },
+    { attachedDeposit: NEAR.parse("0.1 N").toString() }
+  );
+  await alice.call(contract, "donate_to_campaign", { campaign_id: id }, { attachedDeposit: NEAR.parse("1 N").toString() });
+  await t.throwsAsync(alice.call(contract, "refund", { campaign_id: id }));
+
+  await worker.provider.fastForward(100);
+  t.like(await contract.view("get_campaign", { campaign_id: id }), { status: "failed" });
+  await t.throwsAsync(beneficiary.call(contract, "claim_share", { campaign_id: id }));
+
+  const before = await alice.availableBalance();
+  await alice.call(contract, "refund", { campaign_id: id });
+  const after = await alice.availableBalance();
+
+  t.true(after.sub(before).gt(NEAR.parse("0.99 N")));
+  t.deepEqual(await contract.view("get_campaign_donors", { campaign_id: id }), []);
+});
+
+const FT_WASM = "../../../ft/contract/target/wasm32-unknown-unknown/release/ft.wasm";
+
+test("campaigns can raise fungible tokens", async (t) => {
+  const { worker, accounts: { root, contract, alice, bob, beneficiary } } = t.context;
+
+  const token = await root.createSubAccount("token");
+  await token.deploy(FT_WASM);
+  await token.call(token, "new_default_meta", { owner_id: alice.accountId, total_supply: "1000" });
+  for (const account of [contract, beneficiary]) {
+    await account.call(token, "storage_deposit", {}, { attachedDeposit: NEAR.parse("0.01 N").toString() });
+  }
+
+  const id: string = await bob.call(
+    contract,
+    "create_campaign",
+    { title: "Trees", goal: "100", deadline: deadlineIn(30), shares: SHARES(beneficiary, bob), token_id: token.accountId },
+    { attachedDeposit: NEAR.parse("0.1 N").toString() }
+  );
+  const msg = JSON.stringify({ campaign_id: id });
+
+  // tokens can not pay for the storage of the donor, which must register first
+  await alice.call(token, "ft_transfer_call", { receiver_id: contract.accountId, amount: "100", msg }, { attachedDeposit: "1", gas: "100000000000000" });
+  t.is(await token.view("ft_balance_of", { account_id: alice.accountId }), "1000");
+
+  await alice.call(contract, "register_donor", { campaign_id: id }, { attachedDeposit: NEAR.parse("0.01 N").toString() });
+  await alice.call(token, "ft_transfer_call", { receiver_id: contract.accountId, amount: "100", msg }, { attachedDeposit: "1", gas: "100000000000000" });
+  t.is(await token.view("ft_balance_of", { account_id: alice.accountId }), "900");
+  t.like(await contract.view("get_campaign", { campaign_id: id }), { raised: "100" });
+
+  // NEAR is not accepted by token campaigns
+  await t.throwsAsync(alice.call(contract, "donate_to_campaign", { campaign_id: id }, { attachedDeposit: NEAR.parse("1 N").toString() }));
+
+  await worker.provider.fastForward(100);
+  await beneficiary.call(contract, "claim_share", { campaign_id: id }, { gas: "100000000000000" });
+  t.is(await token.view("ft_balance_of", { account_id: beneficiary.accountId }), "70");
+});
+
+class Donation{
+  account_id: string = "";
+  total_amount: string = "";
 }
\ No newline at end of file
@@ -1,47 +1,211 @@
+use guard::MessageGuard;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::Vector;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::Serialize;
-use near_sdk::{env, near_bindgen, AccountId, Balance};
+use near_sdk::{
+    env, near_bindgen, require, AccountId, Balance, BorshStorageKey, CryptoHash, Promise,
+    StorageUsage,
+};
 
 const POINT_ONE: Balance = 100_000_000_000_000_000_000_000;
 
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Messages,
+    Pinned,
+    Senders,
+    Moderators,
+    LastPosted,
+    SenderMessages { sender_hash: CryptoHash },
+}
+
+// The message of the first guest book, stored in a `Vector` with no prefix
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldPostedMessage {
+    pub premium: bool,
+    pub sender: AccountId,
+    pub text: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct OldGuestBook {
+    messages: Vector<OldPostedMessage>,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PostedMessage {
+    pub id: U64,
     pub premium: bool,
     pub sender: AccountId,
     pub text: String,
+    // Hidden messages are not returned by the views
+    #[serde(skip)]
+    pub hidden: bool,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct GuestBook {
-    messages: Vector<PostedMessage>,
+    // Ordered by id, so messages are listed in the order they were posted
+    messages: TreeMap<u64, PostedMessage>,
+    next_id: u64,
+    // Premium messages, ordered by id
+    pinned: TreeMap<u64, ()>,
+    // Ids of the messages of each sender
+    senders: LookupMap<AccountId, UnorderedSet<u64>>,
+    moderators: UnorderedSet<AccountId>,
+    // Limits the length of the messages and how often each account posts
+    guard: MessageGuard,
+    // Messages of the first guest book that `migrate_messages` has not moved yet
+    old_messages: Option<Vector<OldPostedMessage>>,
 }
 
 impl Default for GuestBook {
     fn default() -> Self {
         Self {
-            messages: Vector::new(b"".to_vec()),
+            messages: TreeMap::new(StorageKey::Messages),
+            next_id: 0,
+            pinned: TreeMap::new(StorageKey::Pinned),
+            senders: LookupMap::new(StorageKey::Senders),
+            moderators: UnorderedSet::new(StorageKey::Moderators),
+            guard: MessageGuard::new(StorageKey::LastPosted),
+            old_messages: None,
         }
     }
 }
 
 #[near_bindgen]
 impl GuestBook {
+    /// Adds a message, the attached deposit must pay for its storage. Premium messages keep
+    /// the whole deposit, the rest get back what their storage did not use.
     #[payable]
-    pub fn add_message(&mut self, text: String) {
-        // If the user attaches more than 0.01N the message is premium
+    pub fn add_message(&mut self, text: String) -> U64 {
+        // If the user attaches more than 0.1N the message is premium
         let premium = env::attached_deposit() >= POINT_ONE;
         let sender = env::predecessor_account_id();
+        let initial_storage = env::storage_usage();
+
+        self.guard.check_message(&sender, &text);
 
-        let message = PostedMessage {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.internal_add_message(PostedMessage {
+            id: U64(id),
             premium,
             sender,
             text,
-        };
-        self.messages.push(&message);
+            hidden: false,
+        });
+
+        settle_storage(initial_storage, premium);
+        U64(id)
+    }
+
+    /// Replaces the text of a message, paying for its growth or getting back what it shrank
+    #[payable]
+    pub fn edit_message(&mut self, id: U64, text: String) {
+        let mut message = self.internal_get_own_message(id.0);
+        require!(!message.hidden, "Hidden messages can not be edited");
+        guard::check_text(&text);
+        let initial_storage = env::storage_usage();
+
+        message.text = text;
+        self.messages.insert(&id.0, &message);
+
+        settle_storage(initial_storage, false);
+    }
+
+    /// Deletes a message, and returns its storage deposit to its author
+    pub fn delete_message(&mut self, id: U64) {
+        let message = self.internal_get_own_message(id.0);
+        let initial_storage = env::storage_usage();
+
+        self.messages.remove(&id.0);
+        self.pinned.remove(&id.0);
+
+        let mut sent = self
+            .senders
+            .get(&message.sender)
+            .unwrap_or_else(|| env::panic_str("Sender not found"));
+        sent.remove(&id.0);
+        if sent.is_empty() {
+            self.senders.remove(&message.sender);
+        } else {
+            self.senders.insert(&message.sender, &sent);
+        }
+
+        settle_storage(initial_storage, false);
+    }
+
+    pub fn hide_message(&mut self, id: U64) {
+        self.internal_set_hidden(id.0, true);
+    }
+
+    pub fn unhide_message(&mut self, id: U64) {
+        self.internal_set_hidden(id.0, false);
+    }
+
+    /// Moves the state of the first guest book, whose messages were a `Vector`, to this layout.
+    /// The old messages are then moved in batches with `migrate_messages`
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: OldGuestBook =
+            env::state_read().unwrap_or_else(|| env::panic_str("No state to migrate"));
+
+        // Old messages keep their index as id, new ones are numbered after them
+        Self {
+            next_id: old.messages.len(),
+            old_messages: Some(old.messages),
+            ..Default::default()
+        }
+    }
+
+    /// Moves up to `limit` old messages, removing them from the old `Vector`, and returns how
+    /// many are left to move
+    #[private]
+    pub fn migrate_messages(&mut self, limit: u64) -> u64 {
+        let mut old_messages = self
+            .old_messages
+            .take()
+            .unwrap_or_else(|| env::panic_str("There are no messages to migrate"));
+
+        for _ in 0..limit {
+            let old = match old_messages.pop() {
+                Some(old) => old,
+                None => break,
+            };
+            self.internal_add_message(PostedMessage {
+                id: U64(old_messages.len()),
+                premium: old.premium,
+                sender: old.sender,
+                text: old.text,
+                hidden: false,
+            });
+        }
+
+        let left = old_messages.len();
+        if left > 0 {
+            self.old_messages = Some(old_messages);
+        }
+        left
+    }
+
+    #[private]
+    pub fn add_moderator(&mut self, account_id: AccountId) {
+        self.moderators.insert(&account_id);
+    }
+
+    #[private]
+    pub fn remove_moderator(&mut self, account_id: AccountId) {
+        self.moderators.remove(&account_id);
+    }
+
+    pub fn get_moderators(&self) -> Vec<AccountId> {
+        self.moderators.to_vec()
     }
 
     pub fn get_messages(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PostedMessage> {
@@ -49,6 +213,46 @@ impl GuestBook {
 
         self.messages
             .iter()
+            .map(|(_, message)| message)
+            .filter(|message| !message.hidden)
+            .skip(from as usize)
+            .take(limit.unwrap_or(10) as usize)
+            .collect()
+    }
+
+    pub fn get_messages_by_sender(
+        &self,
+        sender: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<PostedMessage> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        let sent = match self.senders.get(&sender) {
+            Some(sent) => sent,
+            None => return vec![],
+        };
+
+        sent.iter()
+            .filter_map(|id| self.messages.get(&id))
+            .filter(|message| !message.hidden)
+            .skip(from as usize)
+            .take(limit.unwrap_or(10) as usize)
+            .collect()
+    }
+
+    /// Premium messages, in the order they were posted
+    pub fn get_pinned_messages(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<PostedMessage> {
+        let from = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.pinned
+            .iter()
+            .filter_map(|(id, _)| self.messages.get(&id))
+            .filter(|message| !message.hidden)
             .skip(from as usize)
             .take(limit.unwrap_or(10) as usize)
             .collect()
@@ -59,6 +263,63 @@ impl GuestBook {
     }
 }
 
+impl GuestBook {
+    fn internal_add_message(&mut self, message: PostedMessage) {
+        let id = message.id.0;
+        self.messages.insert(&id, &message);
+        if message.premium {
+            self.pinned.insert(&id, &());
+        }
+
+        let mut sent = self.senders.get(&message.sender).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::SenderMessages {
+                sender_hash: env::sha256_array(message.sender.as_bytes()),
+            })
+        });
+        sent.insert(&id);
+        self.senders.insert(&message.sender, &sent);
+    }
+
+    fn internal_get_own_message(&self, id: u64) -> PostedMessage {
+        let message = self
+            .messages
+            .get(&id)
+            .unwrap_or_else(|| env::panic_str("Message not found"));
+        require!(
+            message.sender == env::predecessor_account_id(),
+            "Only the author can change a message"
+        );
+        message
+    }
+
+    fn internal_set_hidden(&mut self, id: u64, hidden: bool) {
+        require!(
+            self.moderators.contains(&env::predecessor_account_id()),
+            "Only moderators can hide messages"
+        );
+        let mut message = self
+            .messages
+            .get(&id)
+            .unwrap_or_else(|| env::panic_str("Message not found"));
+        message.hidden = hidden;
+        self.messages.insert(&id, &message);
+    }
+}
+
+/// Charges the author, who is the caller, for the storage used since `initial_storage` out of
+/// the attached deposit, or returns them the storage freed along with the deposit
+fn settle_storage(initial_storage: StorageUsage, keep_deposit: bool) {
+    let current_storage = env::storage_usage();
+    if current_storage >= initial_storage {
+        guard::charge_storage(initial_storage, keep_deposit);
+    } else {
+        let freed = env::storage_byte_cost() * (initial_storage - current_storage) as Balance;
+        Promise::new(env::predecessor_account_id()).transfer(freed + env::attached_deposit());
+    }
+}
+
+nep_330::impl_contract_source_metadata!(GuestBook);
+
 /*
  * the rest of this file sets up unit tests
  * to run these, the command will be: `cargo test`
@@ -67,9 +328,23 @@ impl GuestBook {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use guard::{MAX_MESSAGE_LENGTH, MIN_BLOCKS_BETWEEN_MESSAGES};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, BlockHeight};
+
+    const STORAGE_DEPOSIT: Balance = 10_000_000_000_000_000_000_000;
+
+    fn set_context(predecessor: AccountId, deposit: Balance, block_height: BlockHeight) {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(predecessor);
+        builder.attached_deposit(deposit);
+        builder.block_index(block_height);
+        testing_env!(builder.build());
+    }
 
     #[test]
     fn add_message() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
         let mut contract = GuestBook::default();
         contract.add_message("A message".to_string());
 
@@ -80,9 +355,16 @@ mod tests {
 
     #[test]
     fn iters_messages() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
         let mut contract = GuestBook::default();
         contract.add_message("1st message".to_string());
+        set_context(accounts(0), STORAGE_DEPOSIT, MIN_BLOCKS_BETWEEN_MESSAGES);
         contract.add_message("2nd message".to_string());
+        set_context(
+            accounts(0),
+            STORAGE_DEPOSIT,
+            2 * MIN_BLOCKS_BETWEEN_MESSAGES,
+        );
         contract.add_message("3rd message".to_string());
 
         let total = &contract.total_messages();
@@ -92,4 +374,138 @@ mod tests {
         assert_eq!(last_message.premium, false);
         assert_eq!(last_message.text, "3rd message".to_string());
     }
+
+    #[test]
+    #[should_panic(expected = "to pay for the storage")]
+    fn requires_storage_deposit() {
+        set_context(accounts(0), 0, 0);
+        let mut contract = GuestBook::default();
+        contract.add_message("A message".to_string());
+    }
+
+    #[test]
+    fn edits_and_deletes_own_messages() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        let first = contract.add_message("1st message".to_string());
+        set_context(accounts(0), STORAGE_DEPOSIT, MIN_BLOCKS_BETWEEN_MESSAGES);
+        let second = contract.add_message("2nd message".to_string());
+
+        contract.edit_message(first, "edited".to_string());
+        contract.delete_message(second);
+
+        let messages = contract.get_messages(None, None);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].text, "edited".to_string());
+        assert!(
+            contract
+                .get_messages_by_sender(accounts(0), None, None)
+                .len()
+                == 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the author can change a message")]
+    fn only_author_deletes() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        let id = contract.add_message("A message".to_string());
+
+        set_context(accounts(1), 0, 0);
+        contract.delete_message(id);
+    }
+
+    #[test]
+    fn moderators_hide_messages() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        let id = contract.add_message("A message".to_string());
+        set_context(accounts(0), STORAGE_DEPOSIT, MIN_BLOCKS_BETWEEN_MESSAGES);
+        contract.add_message("Another message".to_string());
+        contract.moderators.insert(&accounts(1));
+
+        set_context(accounts(1), 0, 0);
+        contract.hide_message(id);
+        assert_eq!(contract.get_messages(None, None).len(), 1);
+
+        contract.unhide_message(id);
+        assert_eq!(contract.get_messages(None, None).len(), 2);
+    }
+
+    #[test]
+    fn pins_premium_messages() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        contract.add_message("A message".to_string());
+
+        set_context(accounts(1), POINT_ONE, 0);
+        contract.add_message("A premium message".to_string());
+
+        let pinned = contract.get_pinned_messages(None, None);
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].sender, accounts(1));
+    }
+
+    #[test]
+    fn migrates_the_first_guest_book() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut messages = Vector::new(b"".to_vec());
+        for (premium, sender, text) in [
+            (false, accounts(0), "1st message"),
+            (true, accounts(1), "2nd message"),
+            (false, accounts(0), "3rd message"),
+        ] {
+            messages.push(&OldPostedMessage {
+                premium,
+                sender,
+                text: text.to_string(),
+            });
+        }
+        env::state_write(&OldGuestBook { messages });
+
+        let mut contract = GuestBook::migrate();
+        assert_eq!(contract.migrate_messages(2), 1);
+        assert_eq!(contract.migrate_messages(2), 0);
+
+        let texts: Vec<String> = contract
+            .get_messages(None, None)
+            .into_iter()
+            .map(|message| message.text)
+            .collect();
+        assert_eq!(texts, ["1st message", "2nd message", "3rd message"]);
+        assert_eq!(contract.get_pinned_messages(None, None)[0].id, U64(1));
+        assert_eq!(
+            contract
+                .get_messages_by_sender(accounts(0), None, None)
+                .len(),
+            2
+        );
+
+        let id = contract.add_message("4th message".to_string());
+        assert_eq!(id, U64(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Messages can not be longer than 280 bytes")]
+    fn rejects_long_messages() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        contract.add_message("a".repeat(MAX_MESSAGE_LENGTH + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Wait until block 10 to post again")]
+    fn rate_limits_senders() {
+        set_context(accounts(0), STORAGE_DEPOSIT, 0);
+        let mut contract = GuestBook::default();
+        contract.add_message("A message".to_string());
+
+        set_context(
+            accounts(0),
+            STORAGE_DEPOSIT,
+            MIN_BLOCKS_BETWEEN_MESSAGES - 1,
+        );
+        contract.add_message("Too soon".to_string());
+    }
 }
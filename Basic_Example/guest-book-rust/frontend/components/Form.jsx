@@ -12,6 +12,7 @@ export default function Form({ onSubmit, currentAccountId }) {
             autoComplete="off"
             autoFocus
             id="message"
+            maxLength={280}
             required
           />
         </p>
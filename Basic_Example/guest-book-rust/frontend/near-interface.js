@@ -19,7 +19,8 @@ export class GuestBook {
   }
 
   async addMessage(message, donation) {
-    const deposit = utils.format.parseNearAmount(donation);
+    // Messages pay for their storage, 0.01 NEAR covers the longest one and the rest is refunded
+    const deposit = utils.format.parseNearAmount((Number(donation) + 0.01).toFixed(5));
     return await this.wallet.callMethod({ contractId: this.contractId, method: "add_message", args: { text: message }, deposit });
   }
 
@@ -36,19 +36,23 @@ test.afterEach(async (t) => {
   });
 });
 
+// Pays for the storage of a short message
+const STORAGE_DEPOSIT = NEAR.parse("0.01 N").toString();
+const MIN_BLOCKS_BETWEEN_MESSAGES = 10;
+
 test("send one message and retrieve it", async (t) => {
   const { root, contract } = t.context.accounts;
-  await root.call(contract, "add_message", { text: "aloha" });
+  await root.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: STORAGE_DEPOSIT });
   const msgs = await contract.view("get_messages");
   const expectedMessagesResult = [
-    { premium: false, sender: root.accountId, text: "aloha" },
+    { id: "0", premium: false, sender: root.accountId, text: "aloha" },
   ];
   t.deepEqual(msgs, expectedMessagesResult);
 });
 
 test("send two messages and expect two total", async (t) => {
   const { root, contract, alice } = t.context.accounts;
-  await root.call(contract, "add_message", { text: "aloha" });
+  await root.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: STORAGE_DEPOSIT });
   await alice.call(contract, "add_message", { text: "hola" }, { attachedDeposit: NEAR.parse('1') });
   
   const total_messages = await contract.view("total_messages");
@@ -56,9 +60,94 @@ test("send two messages and expect two total", async (t) => {
 
   const msgs = await contract.view("get_messages");
   const expected = [
-    { premium: false, sender: root.accountId, text: "aloha" },
-    { premium: true, sender: alice.accountId, text: "hola" },
+    { id: "0", premium: false, sender: root.accountId, text: "aloha" },
+    { id: "1", premium: true, sender: alice.accountId, text: "hola" },
   ];
 
   t.deepEqual(msgs, expected);
-});
\ No newline at end of file
+});
+
+test("messages pay for their storage", async (t) => {
+  const { contract, alice } = t.context.accounts;
+
+  await t.throwsAsync(alice.call(contract, "add_message", { text: "aloha" }));
+  t.is(await contract.view("total_messages"), 0);
+});
+
+test("authors edit and delete their messages", async (t) => {
+  const { root, contract, alice } = t.context.accounts;
+  const id = await alice.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: STORAGE_DEPOSIT });
+
+  await t.throwsAsync(root.call(contract, "edit_message", { id, text: "hijacked" }));
+  await alice.call(contract, "edit_message", { id, text: "aloha everyone" }, { attachedDeposit: STORAGE_DEPOSIT });
+  t.like(await contract.view("get_messages"), [{ text: "aloha everyone" }]);
+
+  // the storage deposit comes back
+  const before = await alice.availableBalance();
+  await alice.call(contract, "delete_message", { id });
+  const after = await alice.availableBalance();
+
+  t.true(after.gt(before));
+  t.deepEqual(await contract.view("get_messages"), []);
+});
+
+test("moderators hide messages", async (t) => {
+  const { root, contract, alice } = t.context.accounts;
+  const id = await alice.call(contract, "add_message", { text: "spam" }, { attachedDeposit: STORAGE_DEPOSIT });
+
+  await t.throwsAsync(root.call(contract, "hide_message", { id }));
+
+  await contract.call(contract, "add_moderator", { account_id: root.accountId });
+  await root.call(contract, "hide_message", { id });
+  t.deepEqual(await contract.view("get_messages"), []);
+  t.deepEqual(await contract.view("get_messages_by_sender", { sender: alice.accountId }), []);
+});
+
+test("premium messages are pinned, and messages are listed per sender", async (t) => {
+  const { worker, accounts: { root, contract, alice } } = t.context;
+  await root.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: STORAGE_DEPOSIT });
+  await alice.call(contract, "add_message", { text: "hola" }, { attachedDeposit: NEAR.parse("1 N").toString() });
+  await worker.provider.fastForward(MIN_BLOCKS_BETWEEN_MESSAGES);
+  await alice.call(contract, "add_message", { text: "ciao" }, { attachedDeposit: STORAGE_DEPOSIT });
+
+  t.deepEqual(await contract.view("get_pinned_messages"), [
+    { id: "1", premium: true, sender: alice.accountId, text: "hola" },
+  ]);
+
+  const aliceMessages: any = await contract.view("get_messages_by_sender", { sender: alice.accountId, from_index: "1" });
+  t.deepEqual(aliceMessages.map((m: any) => m.text), ["ciao"]);
+});
+
+test("rejects long messages", async (t) => {
+  const { contract, alice } = t.context.accounts;
+
+  const error = await t.throwsAsync(
+    alice.call(contract, "add_message", { text: "a".repeat(281) }, { attachedDeposit: STORAGE_DEPOSIT })
+  );
+  t.regex(error!.message, /Messages can not be longer than 280 bytes/);
+});
+
+test("accounts wait between messages", async (t) => {
+  const { worker, accounts: { contract, alice } } = t.context;
+  await alice.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: STORAGE_DEPOSIT });
+
+  const error = await t.throwsAsync(
+    alice.call(contract, "add_message", { text: "aloha again" }, { attachedDeposit: STORAGE_DEPOSIT })
+  );
+  t.regex(error!.message, /to post again/);
+
+  await worker.provider.fastForward(MIN_BLOCKS_BETWEEN_MESSAGES);
+  await alice.call(contract, "add_message", { text: "aloha again" }, { attachedDeposit: STORAGE_DEPOSIT });
+  t.is(await contract.view("total_messages"), 2);
+});
+
+test("only the storage is kept from regular messages", async (t) => {
+  const { contract, alice } = t.context.accounts;
+
+  const before = await alice.availableBalance();
+  await alice.call(contract, "add_message", { text: "aloha" }, { attachedDeposit: NEAR.parse("0.09 N").toString() });
+  const after = await alice.availableBalance();
+
+  // the message uses less than 0.01 N of storage, and the call less than 0.01 N of gas
+  t.true(before.sub(after).lt(NEAR.parse("0.02 N")));
+});
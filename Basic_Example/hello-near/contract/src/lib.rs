@@ -32,3 +32,5 @@ impl Contract {
         self.message = message;
     }
 }
+
+nep_330::impl_contract_source_metadata!(Contract);
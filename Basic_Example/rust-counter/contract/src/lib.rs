@@ -34,6 +34,8 @@ impl Counter {
     }
 }
 
+nep_330::impl_contract_source_metadata!(Counter);
+
 /*
  * the rest of this file sets up unit tests
  * to run these, the command will be: `cargo test`
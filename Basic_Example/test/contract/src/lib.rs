@@ -34,6 +34,8 @@ impl Contract {
     }
 }
 
+nep_330::impl_contract_source_metadata!(Contract);
+
 /*
  * The rest of this file holds the inline tests for the code above
  * Learn more about Rust tests: https://doc.rust-lang.org/book/ch11-01-writing-tests.html
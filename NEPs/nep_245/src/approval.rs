@@ -0,0 +1,71 @@
+use crate::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId, Promise};
+
+/// Lets an owner approve other accounts to transfer an amount of its tokens. Unlike
+/// non-fungible approvals, an approval is kept per owner, since many accounts may hold the
+/// same token, and is decreased by every transfer made with it.
+///
+/// [approval management standard]: https://nomicon.io/Standards/Tokens/MultiToken/ApprovalManagement
+pub trait MultiTokenApproval {
+    /// Approves `account_id` to transfer `amounts` of `token_ids` of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of at least 1 yoctoⓃ for
+    ///   security purposes
+    /// * Contract MAY require caller to attach larger deposit, to cover cost of
+    ///   storing approver data
+    /// * Contract MUST panic if the caller holds less than the approved amounts
+    /// * Contract MUST increment approval ID even if re-approving an account
+    /// * If `msg` is present, contract MUST call `mt_on_approve` on `account_id`
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens for which to add an approval
+    /// * `amounts`: the amount of each token the account can transfer
+    /// * `account_id`: the account to approve
+    /// * `msg`: optional string to be passed to `mt_on_approve`
+    ///
+    /// Returns void, if no `msg` given. Otherwise, returns promise call to
+    /// `mt_on_approve`, which can resolve with whatever it wants.
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise>;
+
+    /// Revokes the approvals of `account_id` for `token_ids` of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * Contract MUST refund the storage deposit of the revoked approvals
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId);
+
+    /// Revokes all approvals for `token_ids` of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * Contract MUST refund the storage deposit of the revoked approvals
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>);
+
+    /// Checks if `approved_account_id` can transfer `amounts` of `token_ids` of the owner,
+    /// optionally checking the approval ids.
+    ///
+    /// Arguments:
+    /// * `owner_id`: the owner of the tokens
+    /// * `token_ids`: the tokens to check
+    /// * `approved_account_id`: the account to check
+    /// * `amounts`: the amount of each token that should be approved
+    /// * `approval_ids`: optional approval ids to check against the current ones
+    fn mt_is_approved(
+        &self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool;
+}
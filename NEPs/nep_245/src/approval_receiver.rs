@@ -0,0 +1,26 @@
+use crate::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, AccountId};
+
+/// Called by the multi token contract when an account approves this contract.
+#[ext_contract(ext_mt_approval_receiver)]
+pub trait MultiTokenApprovalReceiver {
+    /// Notes
+    ///
+    /// * The contract knows the token contract ID from `predecessor_account_id`
+    ///
+    /// Arguments:
+    /// * `token_ids`: the approved tokens
+    /// * `amounts`: the approved amount of each token
+    /// * `owner_id`: the owner of the tokens
+    /// * `approval_ids`: the approval id of each token, stored by the multi token contract
+    /// * `msg`: information necessary for this contract to know how to process the approval
+    fn mt_on_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        owner_id: AccountId,
+        approval_ids: Vec<u64>,
+        msg: String,
+    ) -> near_sdk::PromiseOrValue<String>;
+}
@@ -0,0 +1,95 @@
+use crate::token::{Token, TokenId};
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId, PromiseOrValue};
+
+/// The core methods of a multi token contract, which holds fungible and non-fungible tokens
+/// side by side. Every method taking a single `token_id` has a batch counterpart.
+///
+/// [core multi token standard]: https://nomicon.io/Standards/Tokens/MultiToken/Core
+pub trait MultiTokenCore {
+    /// Simple transfer of `amount` of `token_id` from the current owner to `receiver_id`.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if called by someone other than the owner, or an account
+    ///   approved by the owner for at least `amount`
+    /// * Contract MUST panic if the balance of the owner is lower than `amount`
+    /// * If using Approval Management, the approval is decreased by `amount`
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the token
+    /// * `token_id`: the token to transfer
+    /// * `amount`: the number of tokens to transfer, as a string
+    /// * `approval`: the owner and the approval id, when an approved account transfers on
+    ///   behalf of the owner
+    /// * `memo` (optional): for use cases that may benefit from indexing or providing
+    ///   information for a transfer
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    );
+
+    /// Transfers several tokens at once. `token_ids`, `amounts` and `approvals` are
+    /// matched by index, and MUST have the same length.
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    );
+
+    /// Transfers a token and calls `mt_on_transfer` on the receiver, like
+    /// `nft_transfer_call` and `ft_transfer_call`. The receiver returns the amounts it did not
+    /// use, which `mt_resolve_transfer` gives back to the previous owner.
+    ///
+    /// Requirements:
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * The receiving contract must implement `mt_on_transfer`, otherwise the whole
+    ///   transfer is reverted
+    ///
+    /// Arguments are the ones of `mt_transfer`, and:
+    /// * `msg`: what the receiving contract needs to process the transfer
+    ///
+    /// Returns the amounts that were transferred in the end.
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// The batch version of `mt_transfer_call`, calling `mt_on_transfer` once for all tokens.
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Returns the tokens with the given ids, `null` for the ones that don't exist.
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>>;
+
+    /// Returns the balance of `account_id` for `token_id`, "0" if it has none.
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+    /// Returns the balances of `account_id` for each of `token_ids`.
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+    /// Returns the total supply of `token_id`, `null` if the token doesn't exist.
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128>;
+
+    /// Returns the total supply of each of `token_ids`.
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>>;
+}
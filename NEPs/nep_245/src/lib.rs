@@ -0,0 +1,8 @@
+pub mod approval;
+pub mod approval_receiver;
+pub mod core;
+pub mod enumeration;
+pub mod metadata;
+pub mod receiver;
+pub mod resolver;
+pub mod token;
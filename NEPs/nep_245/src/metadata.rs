@@ -0,0 +1,119 @@
+use crate::token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::require;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// This spec can be treated like a version of the standard.
+pub const MT_METADATA_SPEC: &str = "mt-1.0.0";
+
+/// Metadata for the multi token contract itself.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTContractMetadata {
+    pub spec: String, // required, essentially a version like "mt-1.0.0"
+    pub name: String, // required, ex. "Zoink's Digitial Sword Collection"
+}
+
+/// Metadata shared by a set of tokens, ex. every copy of the same sword.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTBaseTokenMetadata {
+    pub name: String,              // required, ex. "Silver Swords" or "Metaverse 3"
+    pub id: String,                // required, a unique identifier for this metadata
+    pub symbol: Option<String>,    // required for fungible tokens, ex. "MOCHI"
+    pub icon: Option<String>,      // Data URL
+    pub decimals: Option<String>,  // number of decimals for fungible tokens, ex. "24"
+    pub base_uri: Option<String>, // Centralized gateway known to have reliable access to decentralized storage assets referenced by `reference` or `media` URLs
+    pub reference: Option<String>, // URL to a JSON file with more info
+    pub copies: Option<u64>, // number of copies of this set of metadata in existence when token was minted
+    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
+}
+
+/// Metadata of a single token.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, PartialEq, Eq, BorshDeserialize, BorshSerialize, Default,
+)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTTokenMetadata {
+    pub title: Option<String>, // ex. "Arch Nemesis: Mail Carrier" or "Parcel #5055"
+    pub description: Option<String>, // free-form description
+    pub media: Option<String>, // URL to associated media, preferably to decentralized, content-addressed storage
+    pub media_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of content referenced by the `media` field
+    pub issued_at: Option<String>,       // ISO 8601 datetime when token was issued or minted
+    pub expires_at: Option<String>,      // ISO 8601 datetime when token expires
+    pub starts_at: Option<String>,       // ISO 8601 datetime when token starts being valid
+    pub updated_at: Option<String>,      // ISO 8601 datetime when token was last updated
+    pub extra: Option<String>, // anything extra the token wants to store on-chain, stringified JSON
+    pub reference: Option<String>, // URL to an off-chain JSON file with more info
+    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field
+}
+
+/// The base and the token metadata of a token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTTokenMetadataAll {
+    pub base: MTBaseTokenMetadata,
+    pub token: MTTokenMetadata,
+}
+
+/// Offers details on the contract-level, base and token metadata.
+pub trait MultiTokenMetadataProvider {
+    fn mt_metadata_contract(&self) -> MTContractMetadata;
+
+    fn mt_metadata_token_all(&self, token_ids: Vec<TokenId>) -> Vec<Option<MTTokenMetadataAll>>;
+
+    fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadata>>;
+
+    fn mt_metadata_base_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTBaseTokenMetadata>>;
+
+    fn mt_metadata_base_by_metadata_id(
+        &self,
+        base_metadata_ids: Vec<String>,
+    ) -> Vec<Option<MTBaseTokenMetadata>>;
+}
+
+impl MTContractMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.spec == MT_METADATA_SPEC, "Spec is not MT metadata");
+    }
+}
+
+impl MTBaseTokenMetadata {
+    pub fn assert_valid(&self) {
+        require!(
+            self.reference.is_some() == self.reference_hash.is_some(),
+            "Reference and reference hash must be present"
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+        }
+    }
+}
+
+impl MTTokenMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.media.is_some() == self.media_hash.is_some());
+        if let Some(media_hash) = &self.media_hash {
+            require!(media_hash.0.len() == 32, "Media hash has to be 32 bytes");
+        }
+
+        require!(self.reference.is_some() == self.reference_hash.is_some());
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(
+                reference_hash.0.len() == 32,
+                "Reference hash has to be 32 bytes"
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, AccountId, PromiseOrValue};
+
+/// Used when tokens are transferred with `mt_transfer_call` or `mt_batch_transfer_call`.
+/// Implemented by the receiving contract.
+#[ext_contract(ext_mt_receiver)]
+pub trait MultiTokenReceiver {
+    /// Takes some action after receiving multi tokens.
+    ///
+    /// Requirements:
+    /// * Contract MUST restrict calls to this function to a set of allowed contracts
+    /// * Contract MUST return an amount for each of `token_ids`, at most the amount received
+    ///
+    /// Arguments:
+    /// * `sender_id`: the sender of `mt_transfer_call`
+    /// * `previous_owner_ids`: the owners of each token before the transfer. They may differ
+    ///   from `sender_id` when an approved account transferred them
+    /// * `token_ids`: the tokens received
+    /// * `amounts`: the amounts received of each token
+    /// * `msg`: information necessary for this contract to know how to process the request
+    ///
+    /// Returns the amounts of each token that were not used and must go back to the
+    /// previous owners.
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
@@ -0,0 +1,41 @@
+use crate::token::{ClearedApproval, TokenId};
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, AccountId};
+
+/// Used when tokens are transferred with `mt_transfer_call` or `mt_batch_transfer_call`.
+/// Implemented by the multi token contract.
+#[ext_contract(ext_mt_resolver)]
+pub trait MultiTokenResolver {
+    /// Finalizes an `mt_transfer_call` chain of cross-contract calls.
+    ///
+    /// The `mt_transfer_call` process:
+    ///
+    /// 1. Sender calls `mt_transfer_call` on the multi token contract
+    /// 2. The tokens are transferred from the sender to the receiver
+    /// 3. The contract calls `mt_on_transfer` on the receiver
+    /// 4+. The receiver may make other cross-contract calls
+    /// N. The contract resolves the promise chain with `mt_resolve_transfer`
+    ///
+    /// Requirements:
+    /// * Contract MUST forbid calls to this function by any account except self
+    /// * If the promise chain failed, contract MUST revert the whole transfer
+    /// * Otherwise, contract MUST give the unused amounts back to the previous owners, as
+    ///   far as the receiver still holds them, and restore the cleared approvals
+    ///
+    /// Arguments:
+    /// * `previous_owner_ids`: the owners of each token before the transfer
+    /// * `receiver_id`: the account that received the tokens
+    /// * `token_ids`: the tokens transferred
+    /// * `amounts`: the amounts transferred of each token
+    /// * `approvals`: the approvals cleared by the transfer of each token, if any
+    ///
+    /// Returns the amounts of each token that were transferred in the end.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<Vec<ClearedApproval>>>>,
+    ) -> Vec<U128>;
+}
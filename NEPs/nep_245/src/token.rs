@@ -0,0 +1,28 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Balance};
+
+/// The ids of multi tokens are strings, like the ids of non-fungible tokens.
+pub type TokenId = String;
+
+/// An approval removed by a transfer: the approved account, the approval id and the amount it
+/// was approved for. `mt_resolve_transfer` restores them if the transfer is reverted.
+pub type ClearedApproval = (AccountId, u64, U128);
+
+/// A token of a multi token contract. Fungible tokens have no `owner_id`, since many accounts
+/// hold a balance of them; non-fungible tokens have exactly one owner.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    pub owner_id: Option<AccountId>,
+}
+
+/// What an account is allowed to transfer on behalf of an owner.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct Approval {
+    pub amount: Balance,
+    pub approval_id: u64,
+}
@@ -8,6 +8,7 @@ use serde::Serialize;
 pub(crate) enum NearEvent<'a> {
     Nep171(crate::nep_171::Nep171Event<'a>),
     Nep141(crate::nep_141::Nep141Event<'a>),
+    Nep245(crate::nep_245::Nep245Event<'a>),
 }
 
 impl<'a> NearEvent<'a> {
@@ -1,3 +1,4 @@
 pub mod event;
 pub mod nep_141;
 pub mod nep_171;
+pub mod nep_245;
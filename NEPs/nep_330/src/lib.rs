@@ -0,0 +1,68 @@
+//! Standard for nep330 (Contract Source Metadata).
+//!
+//! <https://github.com/near/NEPs/blob/master/neps/nep-0330.md>
+//!
+//! Lets explorers and deployment tooling find out which code a contract was built from and
+//! which standards it implements, by calling `contract_source_metadata`.
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub mod macros;
+
+/// The version of this standard, implemented by every contract using it.
+pub const NEP330_VERSION: &str = "1.1.0";
+
+/// Where the code of the contract can be found, and which version of it was deployed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>, // ex. "1.0.0", a commit hash or any other identifier
+    pub link: Option<String>,    // ex. a link to the repository, or to the code on IPFS
+    pub standards: Vec<Standard>,
+}
+
+/// A standard implemented by the contract, ex. `nep141` version `1.0.0`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+    pub standard: String,
+    pub version: String,
+}
+
+/// Offers details on the source code of the contract.
+pub trait ContractSourceMetadataTrait {
+    fn contract_source_metadata(&self) -> ContractSourceMetadata;
+}
+
+impl Standard {
+    pub fn new(standard: &str, version: &str) -> Self {
+        Self {
+            standard: standard.to_string(),
+            version: version.to_string(),
+        }
+    }
+}
+
+impl ContractSourceMetadata {
+    /// The metadata of a contract implementing `standards`, besides this one. The version and
+    /// the link are the ones given at build time through the `NEP330_VERSION` and
+    /// `NEP330_LINK` environment variables, and otherwise the `version` and `repository` of
+    /// the contract's Cargo.toml, which `impl_contract_source_metadata` passes in.
+    pub fn new(version: &str, link: &str, standards: &[(&str, &str)]) -> Self {
+        let standards = std::iter::once(Standard::new("nep330", NEP330_VERSION))
+            .chain(
+                standards
+                    .iter()
+                    .map(|(standard, version)| Standard::new(standard, version)),
+            )
+            .collect();
+
+        Self {
+            version: Some(version.to_string()).filter(|version| !version.is_empty()),
+            link: Some(link.to_string()).filter(|link| !link.is_empty()),
+            standards,
+        }
+    }
+}
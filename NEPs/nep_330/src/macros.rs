@@ -0,0 +1,26 @@
+/// Implements `contract_source_metadata` for a contract, listing the standards it implements
+/// besides NEP-330. The version and the link default to the `version` and `repository` of the
+/// contract's Cargo.toml, and can be set at build time with the `NEP330_VERSION` and
+/// `NEP330_LINK` environment variables, ex. to the deployed commit.
+///
+/// ```ignore
+/// impl_contract_source_metadata!(Contract, [("nep141", "1.0.0"), ("nep145", "1.0.0")]);
+/// ```
+#[macro_export]
+macro_rules! impl_contract_source_metadata {
+    ($contract: ident) => {
+        $crate::impl_contract_source_metadata!($contract, []);
+    };
+    ($contract: ident, [$(($standard: expr, $version: expr)),* $(,)?]) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::ContractSourceMetadataTrait for $contract {
+            fn contract_source_metadata(&self) -> $crate::ContractSourceMetadata {
+                $crate::ContractSourceMetadata::new(
+                    option_env!("NEP330_VERSION").unwrap_or(env!("CARGO_PKG_VERSION")),
+                    option_env!("NEP330_LINK").unwrap_or(env!("CARGO_PKG_REPOSITORY")),
+                    &[$(($standard, $version)),*],
+                )
+            }
+        }
+    };
+}
@@ -196,3 +196,5 @@ impl EIP712 {
         }
     }
 }
+
+nep_330::impl_contract_source_metadata!(EIP712);
@@ -30,3 +30,5 @@ impl VerifyContract {
         }
     }
 }
+
+nep_330::impl_contract_source_metadata!(VerifyContract);
@@ -72,3 +72,5 @@ impl FungibleTokenReceiver for Hello {
         }
     }
 }
+
+nep_330::impl_contract_source_metadata!(Hello);
@@ -65,3 +65,5 @@ impl FungibleTokenMetadataProvider for Contract {
         self.metadata.get().unwrap()
     }
 }
+
+nep_330::impl_contract_source_metadata!(Contract);
@@ -0,0 +1,611 @@
+/*!
+A constant product pool of two fungible tokens, like two deployments of the `ft` contract.
+NOTES:
+  - Tokens are sent with `ft_transfer_call` on them, with `msg` holding the action:
+    - `{"action": "add_liquidity"}` adds them to the sender's deposit. Once both tokens are
+      deposited, as much as the ratio of the reserves allows moves to the reserves and the sender
+      gets LP shares for it, the rest stays deposited. The first liquidity sets the ratio, unless
+      the last provider left some reserves over, and mints as many shares as the amount of
+      `token_a`.
+    - `{"action": "swap", "min_out": "<amount>"}` swaps them for the other token, which is sent to
+      the sender. If the swap returns less than `min_out`, all of the tokens are refunded.
+  - Swaps pay a fee in basis points of the amount in, which stays in the reserves and so goes to
+    the liquidity providers.
+  - LP shares are a NEP-141 token of this contract, built on the `FungibleToken` of the `ft`
+    contract. Accounts must register with `storage_deposit` before adding liquidity.
+  - `remove_liquidity` burns shares for their part of the reserves, and so does
+    `storage_unregister` with `force` for all of the shares of the account. Tokens the pool sends go
+    through `ft_transfer`: if they can't be sent, they are kept in the account's deposit, to
+    `withdraw` later.
+  - The pool must be registered on both tokens. It pays for the storage of the deposits.
+*/
+mod math;
+
+use ft::FungibleToken;
+use ft_receiver::Deposits;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, BorshStorageKey,
+    PanicOnDefault, Promise, PromiseOrValue,
+};
+use nep_141::receiver::FungibleTokenReceiver;
+use nep_148::{FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC};
+use nep_297::nep_141::{FtBurn, FtMint};
+
+pub use math::FEE_DIVISOR;
+
+/// `msg` of the `ft_transfer_call` to the pool
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+pub enum PoolMsg {
+    AddLiquidity,
+    Swap { min_out: U128 },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PoolView {
+    pub token_a: AccountId,
+    pub token_b: AccountId,
+    pub reserve_a: U128,
+    pub reserve_b: U128,
+    pub fee: u32,
+    pub total_shares: U128,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token_a: AccountId,
+    token_b: AccountId,
+    reserve_a: Balance,
+    reserve_b: Balance,
+    // In basis points, see `FEE_DIVISOR`
+    fee: u32,
+    shares: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    deposits: Deposits,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Shares,
+    Metadata,
+    Deposits,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(token_a: AccountId, token_b: AccountId, fee: u32) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        require!(token_a != token_b, "The tokens must be different");
+        require!(
+            fee < FEE_DIVISOR,
+            "The fee must be less than 10000 basis points"
+        );
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: format!("LP shares of {} and {}", token_a, token_b),
+            symbol: "LP".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        };
+        Self {
+            token_a,
+            token_b,
+            reserve_a: 0,
+            reserve_b: 0,
+            fee,
+            shares: FungibleToken::new(StorageKey::Shares),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            deposits: Deposits::new(StorageKey::Deposits),
+        }
+    }
+
+    /// Burns `shares` of the caller for their part of both reserves, which is sent to them.
+    /// Panics if it is less than `min_a` or `min_b`.
+    #[payable]
+    pub fn remove_liquidity(&mut self, shares: U128, min_a: U128, min_b: U128) {
+        assert_one_yocto();
+        require!(shares.0 > 0, "Shares should be larger than 0");
+        let account_id = env::predecessor_account_id();
+        let total_shares = self.shares.total_supply;
+        require!(shares.0 <= total_shares, "Not enough shares");
+
+        let (amount_a, amount_b) = self.internal_share_of_reserves(shares.0, total_shares);
+        require!(
+            amount_a > 0 && amount_b > 0,
+            "Not enough shares to remove any liquidity"
+        );
+        require!(
+            amount_a >= min_a.0 && amount_b >= min_b.0,
+            "The reserves changed, the amounts are less than the minimums"
+        );
+
+        self.shares.internal_withdraw(&account_id, shares.0);
+        FtBurn {
+            owner_id: &account_id,
+            amount: &shares,
+            memo: Some("Remove liquidity"),
+        }
+        .emit();
+        self.internal_send_reserves(account_id, amount_a, amount_b);
+    }
+
+    /// Sends `amount` of the caller's deposit of `token_id` back to them. Returns the amount
+    /// sent, 0 if it couldn't be sent and was deposited again.
+    #[payable]
+    pub fn withdraw(&mut self, token_id: AccountId, amount: U128) -> Promise {
+        assert_one_yocto();
+        require!(amount.0 > 0, "Amount should be larger than 0");
+        self.deposits
+            .withdraw(token_id, env::predecessor_account_id(), amount.0)
+    }
+
+    pub fn get_pool(&self) -> PoolView {
+        PoolView {
+            token_a: self.token_a.clone(),
+            token_b: self.token_b.clone(),
+            reserve_a: U128(self.reserve_a),
+            reserve_b: U128(self.reserve_b),
+            fee: self.fee,
+            total_shares: U128(self.shares.total_supply),
+        }
+    }
+
+    /// What swapping `amount_in` of `token_in` would return now
+    pub fn get_return(&self, token_in: AccountId, amount_in: U128) -> U128 {
+        require!(self.is_in_pool(&token_in), "The token is not in the pool");
+        U128(self.internal_get_return(&token_in, amount_in.0))
+    }
+
+    pub fn get_deposit(&self, token_id: AccountId, account_id: AccountId) -> U128 {
+        U128(self.deposits.balance_of(&token_id, &account_id))
+    }
+
+    fn is_in_pool(&self, token_id: &AccountId) -> bool {
+        *token_id == self.token_a || *token_id == self.token_b
+    }
+
+    fn internal_get_return(&self, token_in: &AccountId, amount_in: Balance) -> Balance {
+        if *token_in == self.token_a {
+            math::get_amount_out(amount_in, self.reserve_a, self.reserve_b, self.fee)
+        } else {
+            math::get_amount_out(amount_in, self.reserve_b, self.reserve_a, self.fee)
+        }
+    }
+
+    // The part of the reserves `shares` out of `total_shares` are worth
+    fn internal_share_of_reserves(
+        &self,
+        shares: Balance,
+        total_shares: Balance,
+    ) -> (Balance, Balance) {
+        (
+            math::mul_div(shares, self.reserve_a, total_shares),
+            math::mul_div(shares, self.reserve_b, total_shares),
+        )
+    }
+
+    // Takes the amounts out of the reserves and sends them to `account_id`
+    fn internal_send_reserves(
+        &mut self,
+        account_id: AccountId,
+        amount_a: Balance,
+        amount_b: Balance,
+    ) {
+        self.reserve_a -= amount_a;
+        self.reserve_b -= amount_b;
+
+        // Both sends are detached, a joint promise can't be returned
+        if amount_a > 0 {
+            self.deposits
+                .send(self.token_a.clone(), account_id.clone(), amount_a);
+        }
+        if amount_b > 0 {
+            self.deposits
+                .send(self.token_b.clone(), account_id, amount_b);
+        }
+    }
+
+    fn internal_add_liquidity(
+        &mut self,
+        token_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Result<Balance, String> {
+        if !self.shares.balance_map.contains_key(account_id) {
+            return Err(format!(
+                "{} must register with storage_deposit first",
+                account_id
+            ));
+        }
+        self.deposits.deposit(token_id, account_id, amount);
+
+        let deposit_a = self.deposits.balance_of(&self.token_a, account_id);
+        let deposit_b = self.deposits.balance_of(&self.token_b, account_id);
+        if deposit_a > 0 && deposit_b > 0 {
+            self.internal_mint_shares(account_id, deposit_a, deposit_b);
+        }
+        Ok(amount)
+    }
+
+    // Moves as much of the deposits as the ratio of the reserves allows to the reserves
+    fn internal_mint_shares(
+        &mut self,
+        account_id: &AccountId,
+        deposit_a: Balance,
+        deposit_b: Balance,
+    ) {
+        // The reserves left over once every share was burnt still set the ratio
+        let (amount_a, amount_b) = if self.reserve_a == 0 || self.reserve_b == 0 {
+            (deposit_a, deposit_b)
+        } else {
+            let amount_b = math::mul_div_ceil(deposit_a, self.reserve_b, self.reserve_a);
+            if amount_b <= deposit_b {
+                (deposit_a, amount_b)
+            } else {
+                let amount_a = math::mul_div_ceil(deposit_b, self.reserve_a, self.reserve_b);
+                (amount_a, deposit_b)
+            }
+        };
+        let total_shares = self.shares.total_supply;
+        let shares = if total_shares == 0 {
+            amount_a
+        } else {
+            // one of the amounts is rounded up, the shares follow the other one
+            let shares_a = math::mul_div(amount_a, total_shares, self.reserve_a);
+            let shares_b = math::mul_div(amount_b, total_shares, self.reserve_b);
+            shares_a.min(shares_b)
+        };
+        if shares == 0 {
+            return;
+        }
+
+        self.deposits
+            .internal_withdraw(&self.token_a, account_id, amount_a);
+        self.deposits
+            .internal_withdraw(&self.token_b, account_id, amount_b);
+        self.reserve_a += amount_a;
+        self.reserve_b += amount_b;
+        self.shares.internal_deposit(account_id, shares);
+        FtMint {
+            owner_id: account_id,
+            amount: &U128(shares),
+            memo: Some("Add liquidity"),
+        }
+        .emit();
+    }
+
+    fn internal_swap(
+        &mut self,
+        token_in: &AccountId,
+        account_id: &AccountId,
+        amount_in: Balance,
+        min_out: Balance,
+    ) -> Result<Balance, String> {
+        if self.shares.total_supply == 0 {
+            return Err("The pool has no liquidity".to_string());
+        }
+        let amount_out = self.internal_get_return(token_in, amount_in);
+        if amount_out == 0 || amount_out < min_out {
+            return Err(format!(
+                "The swap returns {}, min_out is {}",
+                amount_out, min_out
+            ));
+        }
+
+        let token_out = if *token_in == self.token_a {
+            self.reserve_a += amount_in;
+            self.reserve_b -= amount_out;
+            self.token_b.clone()
+        } else {
+            self.reserve_b += amount_in;
+            self.reserve_a -= amount_out;
+            self.token_a.clone()
+        };
+        log!(
+            "{} swapped {} {} for {} {}",
+            account_id,
+            amount_in,
+            token_in,
+            amount_out,
+            token_out
+        );
+        self.deposits
+            .send(token_out, account_id.clone(), amount_out);
+        Ok(amount_in)
+    }
+
+    // `storage_unregister` with `force` burnt the shares of the account, they are paid out
+    // like `remove_liquidity` does so the reserves aren't orphaned
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        if balance == 0 {
+            return;
+        }
+        FtBurn {
+            owner_id: &account_id,
+            amount: &U128(balance),
+            memo: Some("Account closed"),
+        }
+        .emit();
+
+        // the total supply no longer counts the burnt shares
+        let total_shares = self.shares.total_supply + balance;
+        let (amount_a, amount_b) = self.internal_share_of_reserves(balance, total_shares);
+        self.internal_send_reserves(account_id, amount_a, amount_b);
+    }
+}
+
+nep_141::impl_fungible_token_core!(Contract, shares);
+nep_141::impl_fungible_token_storage!(Contract, shares, on_account_closed);
+ft_receiver::impl_ft_withdraw!(Contract, deposits);
+nep_330::impl_contract_source_metadata!(
+    Contract,
+    [
+        ("nep141", "1.0.0"),
+        ("nep145", "1.0.0"),
+        ("nep148", "1.0.0"),
+        ("nep297", "1.0.0"),
+    ]
+);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Unused tokens are refunded: all of them if the swap or the liquidity is refused
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        ft_receiver::on_ft_transfer(amount, &msg, |msg| {
+            if !self.is_in_pool(&token_id) {
+                return Err(format!("{} is not in the pool", token_id));
+            }
+            match msg {
+                PoolMsg::AddLiquidity => {
+                    self.internal_add_liquidity(&token_id, &sender_id, amount.0)
+                }
+                PoolMsg::Swap { min_out } => {
+                    self.internal_swap(&token_id, &sender_id, amount.0, min_out.0)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::PromiseResult;
+    use test_utils::{set_callback_result, set_context, unused_amount};
+
+    const TOKEN_A: &str = "token_a";
+    const TOKEN_B: &str = "token_b";
+    const ALICE: &str = "alice";
+    const BOB: &str = "bob";
+    const ADD_LIQUIDITY: &str = r#"{"action": "add_liquidity"}"#;
+    const NEAR: u128 = 1000000000000000000000000;
+
+    #[test]
+    fn first_liquidity_sets_the_ratio() {
+        let mut contract = new_contract();
+        register(&mut contract, ALICE);
+
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_A, ALICE, 1000, ADD_LIQUIDITY),
+            0
+        );
+        // nothing is added until both tokens are deposited
+        assert_eq!(contract.get_pool().total_shares.0, 0);
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_B, ALICE, 4000, ADD_LIQUIDITY),
+            0
+        );
+
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (1000, 4000));
+        assert_eq!(contract.ft_balance_of(account(ALICE)).0, 1000);
+        assert_eq!(contract.get_deposit(account(TOKEN_A), account(ALICE)).0, 0);
+    }
+
+    #[test]
+    fn liquidity_is_added_at_the_ratio() {
+        let mut contract = new_pool();
+        register(&mut contract, BOB);
+
+        on_transfer(&mut contract, TOKEN_A, BOB, 100, ADD_LIQUIDITY);
+        on_transfer(&mut contract, TOKEN_B, BOB, 1000, ADD_LIQUIDITY);
+
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (1100, 4400));
+        assert_eq!(contract.ft_balance_of(account(BOB)).0, 100);
+        // the rest of token_b stays deposited
+        assert_eq!(contract.get_deposit(account(TOKEN_B), account(BOB)).0, 600);
+    }
+
+    #[test]
+    fn unregistered_liquidity_is_refunded() {
+        let mut contract = new_pool();
+
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_A, BOB, 100, ADD_LIQUIDITY),
+            100
+        );
+        assert_eq!(contract.get_deposit(account(TOKEN_A), account(BOB)).0, 0);
+    }
+
+    #[test]
+    fn swap_keeps_the_fee_in_the_pool() {
+        let mut contract = new_pool();
+        let amount_out = contract.get_return(account(TOKEN_A), U128(1000)).0;
+        // 1000 * 4000 / 2000 without the fee
+        assert!(amount_out < 2000);
+
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_A, BOB, 1000, &swap_msg(amount_out)),
+            0
+        );
+
+        let pool = contract.get_pool();
+        assert_eq!(
+            (pool.reserve_a.0, pool.reserve_b.0),
+            (2000, 4000 - amount_out)
+        );
+        assert!(pool.reserve_a.0 * pool.reserve_b.0 > 1000 * 4000);
+    }
+
+    #[test]
+    fn swap_below_min_out_is_refunded() {
+        let mut contract = new_pool();
+        let amount_out = contract.get_return(account(TOKEN_B), U128(1000)).0;
+
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_B, BOB, 1000, &swap_msg(amount_out + 1)),
+            1000
+        );
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (1000, 4000));
+    }
+
+    #[test]
+    fn refunds_without_liquidity_or_from_other_tokens() {
+        let mut contract = new_contract();
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN_A, BOB, 10, &swap_msg(0)),
+            10
+        );
+
+        let mut contract = new_pool();
+        assert_eq!(
+            on_transfer(&mut contract, "other", BOB, 10, &swap_msg(0)),
+            10
+        );
+        assert_eq!(on_transfer(&mut contract, TOKEN_A, BOB, 10, "swap"), 10);
+    }
+
+    #[test]
+    fn remove_liquidity_burns_shares() {
+        let mut contract = new_pool();
+
+        set_context(ALICE, 1);
+        contract.remove_liquidity(U128(250), U128(250), U128(1000));
+
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (750, 3000));
+        assert_eq!(pool.total_shares.0, 750);
+        assert_eq!(contract.ft_balance_of(account(ALICE)).0, 750);
+    }
+
+    #[test]
+    #[should_panic(expected = "The reserves changed, the amounts are less than the minimums")]
+    fn remove_liquidity_checks_the_minimums() {
+        let mut contract = new_pool();
+
+        set_context(ALICE, 1);
+        contract.remove_liquidity(U128(250), U128(251), U128(1000));
+    }
+
+    #[test]
+    fn failed_send_is_deposited() {
+        let mut contract = new_pool();
+        set_context(ALICE, 1);
+        contract.remove_liquidity(U128(250), U128(0), U128(0));
+
+        set_callback_result(PromiseResult::Failed);
+        assert_eq!(
+            contract
+                .ft_resolve_withdraw(account(TOKEN_A), account(ALICE), U128(250))
+                .0,
+            0
+        );
+        assert_eq!(
+            contract.get_deposit(account(TOKEN_A), account(ALICE)).0,
+            250
+        );
+    }
+
+    #[test]
+    fn force_unregister_pays_the_shares_out() {
+        let mut contract = new_pool();
+
+        set_context(ALICE, 1);
+        assert!(contract.storage_unregister(Some(true)));
+
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (0, 0));
+        assert_eq!(pool.total_shares.0, 0);
+        assert!(contract.deposits.has_pending(&account(ALICE)));
+    }
+
+    #[test]
+    fn first_shares_follow_the_leftover_reserves() {
+        let mut contract = new_contract();
+        register(&mut contract, BOB);
+        // left over by rounding once every share was burnt
+        contract.reserve_a = 3;
+        contract.reserve_b = 12;
+
+        on_transfer(&mut contract, TOKEN_A, BOB, 100, ADD_LIQUIDITY);
+        on_transfer(&mut contract, TOKEN_B, BOB, 1000, ADD_LIQUIDITY);
+
+        let pool = contract.get_pool();
+        assert_eq!((pool.reserve_a.0, pool.reserve_b.0), (103, 412));
+        assert_eq!(pool.total_shares.0, 100);
+        assert_eq!(contract.get_deposit(account(TOKEN_B), account(BOB)).0, 600);
+    }
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    fn swap_msg(min_out: Balance) -> String {
+        format!(r#"{{"action": "swap", "min_out": "{}"}}"#, min_out)
+    }
+
+    fn new_contract() -> Contract {
+        set_context(ALICE, 0);
+        Contract::new(account(TOKEN_A), account(TOKEN_B), 30)
+    }
+
+    // alice provided 1000 token_a and 4000 token_b, for 1000 shares
+    fn new_pool() -> Contract {
+        let mut contract = new_contract();
+        register(&mut contract, ALICE);
+        on_transfer(&mut contract, TOKEN_A, ALICE, 1000, ADD_LIQUIDITY);
+        on_transfer(&mut contract, TOKEN_B, ALICE, 4000, ADD_LIQUIDITY);
+        contract
+    }
+
+    fn register(contract: &mut Contract, account_id: &str) {
+        set_context(account_id, NEAR / 100);
+        contract.storage_deposit(None, None);
+    }
+
+    // Returns the unused amount
+    fn on_transfer(
+        contract: &mut Contract,
+        token: &str,
+        sender: &str,
+        amount: Balance,
+        msg: &str,
+    ) -> Balance {
+        set_context(token, 0);
+        unused_amount(contract.ft_on_transfer(account(sender), U128(amount), msg.to_string()))
+    }
+}
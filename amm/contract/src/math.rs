@@ -0,0 +1,68 @@
+use near_sdk::Balance;
+
+mod u256 {
+    #![allow(
+        clippy::assign_op_pattern,
+        clippy::ptr_offset_with_cast,
+        clippy::manual_range_contains
+    )]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+use u256::U256;
+
+/// Fees are in basis points, 1/10000 of the amount in
+pub const FEE_DIVISOR: u32 = 10_000;
+
+/// `a * b / c` rounded down, without overflowing in between
+pub fn mul_div(a: Balance, b: Balance, c: Balance) -> Balance {
+    (U256::from(a) * U256::from(b) / U256::from(c)).as_u128()
+}
+
+/// `a * b / c` rounded up
+pub fn mul_div_ceil(a: Balance, b: Balance, c: Balance) -> Balance {
+    let (quotient, remainder) = (U256::from(a) * U256::from(b)).div_mod(U256::from(c));
+    if remainder.is_zero() {
+        quotient.as_u128()
+    } else {
+        quotient.as_u128() + 1
+    }
+}
+
+/// What swapping `amount_in` returns, keeping `reserve_in * reserve_out` constant after the fee
+pub fn get_amount_out(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee: u32,
+) -> Balance {
+    let amount_in = U256::from(amount_in) * U256::from(FEE_DIVISOR - fee);
+    let numerator = amount_in * U256::from(reserve_out);
+    let denominator = U256::from(reserve_in) * U256::from(FEE_DIVISOR) + amount_in;
+    (numerator / denominator).as_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_does_not_overflow() {
+        let big = 10u128.pow(30);
+        assert_eq!(mul_div(big, big, big), big);
+        assert_eq!(mul_div(7, 3, 2), 10);
+        assert_eq!(mul_div_ceil(7, 3, 2), 11);
+        assert_eq!(mul_div_ceil(8, 3, 2), 12);
+    }
+
+    #[test]
+    fn amount_out_keeps_the_product() {
+        // without a fee, 1000 * 1000 = 2000 * 500
+        assert_eq!(get_amount_out(1000, 1000, 1000, 0), 500);
+        // the fee stays in the pool
+        assert_eq!(get_amount_out(1000, 1000, 1000, 30), 499);
+        assert_eq!(get_amount_out(1, 1000, 1000, 30), 0);
+    }
+}
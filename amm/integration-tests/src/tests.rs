@@ -0,0 +1,332 @@
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::{env, fs};
+use workspaces::{Account, AccountId, Contract};
+
+const ADD_LIQUIDITY: &str = r#"{"action": "add_liquidity"}"#;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let amm_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let amm_wasm_filepath = fs::canonicalize(env::current_dir()?.join(amm_wasm_arg))?;
+    let ft_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let ft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(ft_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let amm = worker.dev_deploy(&fs::read(amm_wasm_filepath)?).await?;
+    let ft_wasm = fs::read(ft_wasm_filepath)?;
+    let token_a = worker.dev_deploy(&ft_wasm).await?;
+    let token_b = worker.dev_deploy(&ft_wasm).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let carol = account
+        .create_subaccount("carol")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice owns both tokens, the pool and bob are registered on both, carol only on token_b
+    for (token, account_ids) in [
+        (&token_a, vec![amm.id(), bob.id()]),
+        (&token_b, vec![amm.id(), bob.id(), carol.id()]),
+    ] {
+        token
+            .call("new_default_meta")
+            .args_json(json!({ "owner_id": alice.id(), "total_supply": "1000000" }))
+            .transact()
+            .await?
+            .into_result()?;
+        for account_id in account_ids {
+            alice
+                .call(token.id(), "storage_deposit")
+                .args_json(json!({ "account_id": account_id }))
+                .deposit(parse_near!("0.01 N"))
+                .transact()
+                .await?
+                .into_result()?;
+        }
+    }
+    for (token, receiver) in [(&token_a, &bob), (&token_b, &bob), (&token_b, &carol)] {
+        alice
+            .call(token.id(), "ft_transfer")
+            .args_json(json!({ "receiver_id": receiver.id(), "amount": "10000" }))
+            .deposit(1)
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    amm.call("new")
+        .args_json(json!({ "token_a": token_a.id(), "token_b": token_b.id(), "fee": 30 }))
+        .transact()
+        .await?
+        .into_result()?;
+    // alice registers for LP shares
+    alice
+        .call(amm.id(), "storage_deposit")
+        .args_json(json!({}))
+        .deposit(parse_near!("0.01 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_add_liquidity(&alice, &amm, &token_a, &token_b).await?;
+    test_unregistered_liquidity_is_refunded(&bob, &amm, &token_a).await?;
+    test_swap(&bob, &amm, &token_a, &token_b).await?;
+    test_swap_below_min_out_is_refunded(&bob, &amm, &token_a, &token_b).await?;
+    test_failed_swap_output_is_deposited(&alice, &carol, &amm, &token_a, &token_b).await?;
+    test_shares_are_transferable(&alice, &bob, &amm).await?;
+    test_remove_liquidity(&alice, &amm, &token_a, &token_b).await?;
+    Ok(())
+}
+
+async fn ft_balance_of(user: &Account, ft: &AccountId) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(ft, "ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+async fn get_pool(user: &Account, amm: &Contract) -> anyhow::Result<Value> {
+    let pool: Value = user.call(amm.id(), "get_pool").transact().await?.json()?;
+    Ok(pool)
+}
+
+async fn get_return(
+    user: &Account,
+    amm: &Contract,
+    token_in: &Contract,
+    amount_in: u128,
+) -> anyhow::Result<u128> {
+    let amount_out: String = user
+        .call(amm.id(), "get_return")
+        .args_json(json!({ "token_in": token_in.id(), "amount_in": amount_in.to_string() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(amount_out.parse()?)
+}
+
+// Returns the amount the pool used, as reported by `ft_resolve_transfer`
+async fn transfer_call(
+    sender: &Account,
+    amm: &Contract,
+    token: &Contract,
+    amount: u128,
+    msg: &str,
+) -> anyhow::Result<u128> {
+    let used: String = sender
+        .call(token.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": amm.id(),
+            "amount": amount.to_string(),
+            "msg": msg,
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    Ok(used.parse()?)
+}
+
+fn swap_msg(min_out: u128) -> String {
+    json!({ "action": "swap", "min_out": min_out.to_string() }).to_string()
+}
+
+async fn test_add_liquidity(
+    alice: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+    token_b: &Contract,
+) -> anyhow::Result<()> {
+    assert_eq!(
+        transfer_call(alice, amm, token_a, 10000, ADD_LIQUIDITY).await?,
+        10000
+    );
+    assert_eq!(
+        transfer_call(alice, amm, token_b, 40000, ADD_LIQUIDITY).await?,
+        40000
+    );
+
+    let pool = get_pool(alice, amm).await?;
+    assert_eq!(pool["reserve_a"], "10000");
+    assert_eq!(pool["reserve_b"], "40000");
+    assert_eq!(pool["total_shares"], "10000");
+    assert_eq!(ft_balance_of(alice, amm.id()).await?, 10000);
+    println!("      Passed ✅ add liquidity");
+    Ok(())
+}
+
+async fn test_unregistered_liquidity_is_refunded(
+    bob: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+) -> anyhow::Result<()> {
+    let used = transfer_call(bob, amm, token_a, 1000, ADD_LIQUIDITY).await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(ft_balance_of(bob, token_a.id()).await?, 10000);
+    println!("      Passed ✅ liquidity of unregistered accounts is refunded");
+    Ok(())
+}
+
+async fn test_swap(
+    bob: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+    token_b: &Contract,
+) -> anyhow::Result<()> {
+    let amount_out = get_return(bob, amm, token_a, 1000).await?;
+    // 1000 * 40000 / 11000 without the fee
+    assert!(amount_out > 0 && amount_out < 3636);
+
+    let used = transfer_call(bob, amm, token_a, 1000, &swap_msg(amount_out)).await?;
+
+    assert_eq!(used, 1000);
+    assert_eq!(ft_balance_of(bob, token_a.id()).await?, 9000);
+    assert_eq!(ft_balance_of(bob, token_b.id()).await?, 10000 + amount_out);
+    let pool = get_pool(bob, amm).await?;
+    assert_eq!(pool["reserve_a"], "11000");
+    assert_eq!(pool["reserve_b"], (40000 - amount_out).to_string());
+    println!("      Passed ✅ swap");
+    Ok(())
+}
+
+async fn test_swap_below_min_out_is_refunded(
+    bob: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+    token_b: &Contract,
+) -> anyhow::Result<()> {
+    let pool = get_pool(bob, amm).await?;
+    let amount_out = get_return(bob, amm, token_b, 1000).await?;
+
+    let used = transfer_call(bob, amm, token_b, 1000, &swap_msg(amount_out + 1)).await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(get_pool(bob, amm).await?, pool);
+    println!("      Passed ✅ swaps below min_out are refunded");
+    Ok(())
+}
+
+async fn test_failed_swap_output_is_deposited(
+    alice: &Account,
+    carol: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+    token_b: &Contract,
+) -> anyhow::Result<()> {
+    // carol isn't registered on token_a, so the output of her swap can't be sent
+    let amount_out = get_return(carol, amm, token_b, 1000).await?;
+    let used = transfer_call(carol, amm, token_b, 1000, &swap_msg(amount_out)).await?;
+    assert_eq!(used, 1000);
+
+    let deposit: String = carol
+        .call(amm.id(), "get_deposit")
+        .args_json(json!({ "token_id": token_a.id(), "account_id": carol.id() }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(deposit, amount_out.to_string());
+
+    // once registered, she withdraws it
+    alice
+        .call(token_a.id(), "storage_deposit")
+        .args_json(json!({ "account_id": carol.id() }))
+        .deposit(parse_near!("0.01 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let withdrawn: String = carol
+        .call(amm.id(), "withdraw")
+        .args_json(json!({ "token_id": token_a.id(), "amount": amount_out.to_string() }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(withdrawn, amount_out.to_string());
+    assert_eq!(ft_balance_of(carol, token_a.id()).await?, amount_out);
+    println!("      Passed ✅ the output of a swap which can't be sent is deposited");
+    Ok(())
+}
+
+async fn test_shares_are_transferable(
+    alice: &Account,
+    bob: &Account,
+    amm: &Contract,
+) -> anyhow::Result<()> {
+    bob.call(amm.id(), "storage_deposit")
+        .args_json(json!({}))
+        .deposit(parse_near!("0.01 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    alice
+        .call(amm.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "amount": "1000" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(ft_balance_of(alice, amm.id()).await?, 9000);
+    assert_eq!(ft_balance_of(bob, amm.id()).await?, 1000);
+    println!("      Passed ✅ LP shares are a fungible token");
+    Ok(())
+}
+
+async fn test_remove_liquidity(
+    alice: &Account,
+    amm: &Contract,
+    token_a: &Contract,
+    token_b: &Contract,
+) -> anyhow::Result<()> {
+    let pool = get_pool(alice, amm).await?;
+    let reserve_a: u128 = pool["reserve_a"].as_str().unwrap().parse()?;
+    let reserve_b: u128 = pool["reserve_b"].as_str().unwrap().parse()?;
+    let balance_a = ft_balance_of(alice, token_a.id()).await?;
+    let balance_b = ft_balance_of(alice, token_b.id()).await?;
+
+    // alice removes half of the shares, the fees of the swaps included
+    alice
+        .call(amm.id(), "remove_liquidity")
+        .args_json(json!({ "shares": "5000", "min_a": "0", "min_b": "0" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(ft_balance_of(alice, amm.id()).await?, 4000);
+    assert_eq!(
+        ft_balance_of(alice, token_a.id()).await?,
+        balance_a + reserve_a / 2
+    );
+    assert_eq!(
+        ft_balance_of(alice, token_b.id()).await?,
+        balance_b + reserve_b / 2
+    );
+    let pool = get_pool(alice, amm).await?;
+    assert_eq!(pool["total_shares"], "5000");
+    println!("      Passed ✅ remove liquidity");
+    Ok(())
+}
@@ -0,0 +1,471 @@
+/*!
+A multisig DAO, which can own the `ft`, `nft` and factory contracts in place of a single key.
+NOTES:
+  - Members propose function calls on any contract, which run once `threshold` members approved
+    them. Proposing counts as an approval.
+  - Proposals expire `proposal_period` nanoseconds after they were made, they can't be voted on
+    or executed after that.
+  - Once approved, any member `execute`s the proposal: its calls are sent as one batch of
+    `Promise::function_call`s from the DAO account, so the DAO pays their deposits. If one of them
+    fails they all revert, and the proposal can be executed again until it expires.
+  - The members, the threshold and the proposal period change through proposals calling the DAO
+    itself, their methods are `#[private]`.
+  - The DAO pays for the storage of the proposals, only members can make them.
+*/
+mod proposal;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{
+    env, is_promise_success, log, near_bindgen, require, AccountId, BorshStorageKey, Duration, Gas,
+    PanicOnDefault, Promise,
+};
+
+pub use proposal::{FunctionCall, Proposal, ProposalStatus, ProposalView};
+
+/// One week, in nanoseconds
+pub const DEFAULT_PROPOSAL_PERIOD: Duration = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+const GAS_FOR_ON_EXECUTE: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    members: UnorderedSet<AccountId>,
+    // Approvals needed to execute a proposal
+    threshold: u32,
+    proposal_period: Duration,
+    proposals: UnorderedMap<u64, Proposal>,
+    next_proposal_id: u64,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Members,
+    Proposals,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(members: Vec<AccountId>, threshold: u32, proposal_period: Option<U64>) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        let mut this = Self {
+            members: UnorderedSet::new(StorageKey::Members),
+            threshold,
+            proposal_period: proposal_period.map_or(DEFAULT_PROPOSAL_PERIOD, u64::from),
+            proposals: UnorderedMap::new(StorageKey::Proposals),
+            next_proposal_id: 0,
+        };
+        this.members.extend(members);
+        this.assert_valid_threshold();
+        this
+    }
+
+    /// Proposes to call `actions` on `receiver_id`, approved by the caller. Returns the ID of the
+    /// proposal.
+    pub fn add_proposal(
+        &mut self,
+        description: String,
+        receiver_id: AccountId,
+        actions: Vec<FunctionCall>,
+    ) -> U64 {
+        let proposer = self.assert_member();
+        require!(
+            !actions.is_empty(),
+            "A proposal needs at least one function call"
+        );
+
+        let mut proposal = Proposal {
+            proposer: proposer.clone(),
+            description,
+            receiver_id,
+            actions,
+            approvals: vec![proposer],
+            rejections: vec![],
+            status: ProposalStatus::InProgress,
+            expires_at: env::block_timestamp() + self.proposal_period,
+        };
+        self.internal_update_status(&mut proposal);
+
+        let proposal_id = self.next_proposal_id;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.next_proposal_id += 1;
+        U64(proposal_id)
+    }
+
+    pub fn approve(&mut self, proposal_id: U64) -> ProposalStatus {
+        let member = self.assert_member();
+        let mut proposal = self.internal_get_votable_proposal(proposal_id.0, &member);
+        proposal.approvals.push(member);
+        self.internal_update_status(&mut proposal);
+        self.proposals.insert(&proposal_id.0, &proposal);
+        proposal.status
+    }
+
+    pub fn reject(&mut self, proposal_id: U64) -> ProposalStatus {
+        let member = self.assert_member();
+        let mut proposal = self.internal_get_votable_proposal(proposal_id.0, &member);
+        proposal.rejections.push(member);
+        self.internal_update_status(&mut proposal);
+        self.proposals.insert(&proposal_id.0, &proposal);
+        proposal.status
+    }
+
+    /// Sends the calls of an approved proposal. Attach enough gas for all of them.
+    pub fn execute(&mut self, proposal_id: U64) -> Promise {
+        self.assert_member();
+        let mut proposal = self.internal_get_proposal(proposal_id.0);
+        match proposal.status_at(env::block_timestamp()) {
+            ProposalStatus::Approved | ProposalStatus::Failed => {}
+            ProposalStatus::Expired => env::panic_str("The proposal expired"),
+            _ => env::panic_str("The proposal is not approved"),
+        }
+        // Members may have been removed, or the threshold raised, since it was approved
+        require!(
+            self.count_votes(&proposal.approvals) >= self.threshold as usize,
+            "The proposal is not approved"
+        );
+        // Executed until the calls say otherwise, so that it can't be executed twice meanwhile
+        proposal.status = ProposalStatus::Executed;
+        self.proposals.insert(&proposal_id.0, &proposal);
+
+        let mut promise = Promise::new(proposal.receiver_id);
+        for action in proposal.actions {
+            promise = promise.function_call(
+                action.method_name,
+                action.args.into(),
+                action.deposit.0,
+                Gas(action.gas.0),
+            );
+        }
+        promise.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_ON_EXECUTE)
+                .on_execute(proposal_id),
+        )
+    }
+
+    /// Returns whether the calls of the proposal succeeded
+    #[private]
+    pub fn on_execute(&mut self, proposal_id: U64) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        let mut proposal = self.internal_get_proposal(proposal_id.0);
+        proposal.status = ProposalStatus::Failed;
+        self.proposals.insert(&proposal_id.0, &proposal);
+        log!("The calls of proposal {} failed", proposal_id.0);
+        false
+    }
+
+    #[private]
+    pub fn add_member(&mut self, member_id: AccountId) {
+        require!(self.members.insert(&member_id), "Already a member");
+    }
+
+    #[private]
+    pub fn remove_member(&mut self, member_id: AccountId) {
+        require!(self.members.remove(&member_id), "Not a member");
+        self.assert_valid_threshold();
+    }
+
+    #[private]
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.threshold = threshold;
+        self.assert_valid_threshold();
+    }
+
+    #[private]
+    pub fn set_proposal_period(&mut self, proposal_period: U64) {
+        self.proposal_period = proposal_period.0;
+    }
+
+    pub fn get_members(&self) -> Vec<AccountId> {
+        self.members.to_vec()
+    }
+
+    pub fn get_threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn get_proposal_period(&self) -> U64 {
+        U64(self.proposal_period)
+    }
+
+    pub fn get_proposal(&self, proposal_id: U64) -> Option<ProposalView> {
+        self.proposals
+            .get(&proposal_id.0)
+            .map(|proposal| proposal.to_view(proposal_id.0, env::block_timestamp()))
+    }
+
+    pub fn get_proposals(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<ProposalView> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        let now = env::block_timestamp();
+
+        self.proposals
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(proposal_id, proposal)| proposal.to_view(proposal_id, now))
+            .collect()
+    }
+
+    fn assert_member(&self) -> AccountId {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.members.contains(&account_id),
+            "Only members can do this"
+        );
+        account_id
+    }
+
+    fn assert_valid_threshold(&self) {
+        require!(
+            self.threshold > 0 && u64::from(self.threshold) <= self.members.len(),
+            "The threshold must be between 1 and the number of members"
+        );
+    }
+
+    fn internal_get_proposal(&self, proposal_id: u64) -> Proposal {
+        self.proposals
+            .get(&proposal_id)
+            .unwrap_or_else(|| env::panic_str("Unknown proposal"))
+    }
+
+    fn internal_get_votable_proposal(&self, proposal_id: u64, member: &AccountId) -> Proposal {
+        let proposal = self.internal_get_proposal(proposal_id);
+        match proposal.status_at(env::block_timestamp()) {
+            ProposalStatus::InProgress => {}
+            ProposalStatus::Expired => env::panic_str("The proposal expired"),
+            _ => env::panic_str("The proposal is not in progress"),
+        }
+        require!(!proposal.has_voted(member), "Already voted");
+        proposal
+    }
+
+    // Only the votes of current members count, against the current threshold
+    fn internal_update_status(&self, proposal: &mut Proposal) {
+        let threshold = self.threshold as usize;
+        if self.count_votes(&proposal.approvals) >= threshold {
+            proposal.status = ProposalStatus::Approved;
+        } else if self.count_votes(&proposal.rejections) > self.members.len() as usize - threshold {
+            proposal.status = ProposalStatus::Rejected;
+        }
+    }
+
+    fn count_votes(&self, votes: &[AccountId]) -> usize {
+        votes
+            .iter()
+            .filter(|account_id| self.members.contains(account_id))
+            .count()
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Contract);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::{PromiseResult, Timestamp};
+
+    const ALICE: &str = "alice";
+    const BOB: &str = "bob";
+    const CAROL: &str = "carol";
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn executes_once_approved() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        assert_eq!(status(&contract, proposal_id), ProposalStatus::InProgress);
+
+        set_context(BOB, 0);
+        assert_eq!(contract.approve(proposal_id), ProposalStatus::Approved);
+        contract.execute(proposal_id);
+
+        set_execute_result(PromiseResult::Successful(vec![]));
+        assert!(contract.on_execute(proposal_id));
+        assert_eq!(status(&contract, proposal_id), ProposalStatus::Executed);
+    }
+
+    #[test]
+    #[should_panic(expected = "The proposal is not approved")]
+    fn needs_the_threshold() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+
+        set_context(ALICE, 0);
+        contract.execute(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The proposal is not approved")]
+    fn executes_only_once() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        set_context(BOB, 0);
+        contract.approve(proposal_id);
+        contract.execute(proposal_id);
+
+        contract.execute(proposal_id);
+    }
+
+    #[test]
+    fn failed_calls_can_be_executed_again() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        set_context(BOB, 0);
+        contract.approve(proposal_id);
+        contract.execute(proposal_id);
+
+        set_execute_result(PromiseResult::Failed);
+        assert!(!contract.on_execute(proposal_id));
+        assert_eq!(status(&contract, proposal_id), ProposalStatus::Failed);
+
+        set_context(CAROL, 0);
+        contract.execute(proposal_id);
+        assert_eq!(status(&contract, proposal_id), ProposalStatus::Executed);
+    }
+
+    #[test]
+    fn rejected_once_the_threshold_is_out_of_reach() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+
+        set_context(BOB, 0);
+        assert_eq!(contract.reject(proposal_id), ProposalStatus::InProgress);
+        set_context(CAROL, 0);
+        assert_eq!(contract.reject(proposal_id), ProposalStatus::Rejected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Already voted")]
+    fn votes_once() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+
+        set_context(ALICE, 0);
+        contract.approve(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only members can do this")]
+    fn only_members_propose() {
+        let mut contract = new_contract();
+        propose(&mut contract, "dave");
+    }
+
+    #[test]
+    #[should_panic(expected = "The proposal expired")]
+    fn expired_proposals_cant_be_approved() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        assert_eq!(
+            contract.get_proposal(proposal_id).unwrap().expires_at.0,
+            DEFAULT_PROPOSAL_PERIOD
+        );
+
+        set_context(BOB, DEFAULT_PROPOSAL_PERIOD);
+        assert_eq!(status(&contract, proposal_id), ProposalStatus::Expired);
+        contract.approve(proposal_id);
+    }
+
+    #[test]
+    fn members_and_threshold_change_through_the_dao() {
+        let mut contract = new_contract();
+
+        set_context("dao", 0);
+        contract.add_member("dave".parse().unwrap());
+        contract.set_threshold(3);
+        contract.set_proposal_period(U64(SECOND));
+        assert_eq!(contract.get_members().len(), 4);
+        assert_eq!(contract.get_threshold(), 3);
+        assert_eq!(contract.get_proposal_period().0, SECOND);
+    }
+
+    #[test]
+    #[should_panic(expected = "The proposal is not approved")]
+    fn approvals_of_removed_members_dont_count() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        set_context(BOB, 0);
+        contract.approve(proposal_id);
+
+        set_context("dao", 0);
+        contract.remove_member(BOB.parse().unwrap());
+
+        set_context(ALICE, 0);
+        contract.execute(proposal_id);
+    }
+
+    #[test]
+    fn removed_members_dont_approve() {
+        let mut contract = new_contract();
+        let proposal_id = propose(&mut contract, ALICE);
+        set_context("dao", 0);
+        contract.remove_member(ALICE.parse().unwrap());
+
+        // the proposer's approval is gone with them
+        set_context(BOB, 0);
+        assert_eq!(contract.approve(proposal_id), ProposalStatus::InProgress);
+        set_context(CAROL, 0);
+        assert_eq!(contract.approve(proposal_id), ProposalStatus::Approved);
+    }
+
+    #[test]
+    #[should_panic(expected = "The threshold must be between 1 and the number of members")]
+    fn threshold_stays_reachable() {
+        let mut contract = new_contract();
+
+        set_context("dao", 0);
+        contract.remove_member(ALICE.parse().unwrap());
+        contract.remove_member(BOB.parse().unwrap());
+    }
+
+    fn new_contract() -> Contract {
+        set_context(ALICE, 0);
+        Contract::new(
+            vec![
+                ALICE.parse().unwrap(),
+                BOB.parse().unwrap(),
+                CAROL.parse().unwrap(),
+            ],
+            2,
+            None,
+        )
+    }
+
+    fn propose(contract: &mut Contract, proposer: &str) -> U64 {
+        set_context(proposer, 0);
+        contract.add_proposal(
+            "Mint".to_string(),
+            "token".parse().unwrap(),
+            vec![FunctionCall {
+                method_name: "ft_mint".to_string(),
+                args: Base64VecU8(br#"{"account_id": "bob", "amount": "100"}"#.to_vec()),
+                deposit: U128(1),
+                gas: U64(10 * 1_000_000_000_000),
+            }],
+        )
+    }
+
+    fn status(contract: &Contract, proposal_id: U64) -> ProposalStatus {
+        contract.get_proposal(proposal_id).unwrap().status
+    }
+
+    fn set_context(predecessor: &str, timestamp: Timestamp) {
+        test_utils::set_context_with(predecessor, 0, |builder| {
+            builder.current_account_id("dao".parse().unwrap());
+            builder.block_timestamp(timestamp);
+        });
+    }
+
+    fn set_execute_result(result: PromiseResult) {
+        test_utils::set_callback_result_with(result, |builder| {
+            builder.current_account_id("dao".parse().unwrap());
+        });
+    }
+}
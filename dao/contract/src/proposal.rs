@@ -0,0 +1,88 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Timestamp};
+
+/// A function call of a proposal, with `args` in base64 like `near call --base64`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FunctionCall {
+    pub method_name: String,
+    pub args: Base64VecU8,
+    pub deposit: U128,
+    pub gas: U64,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalStatus {
+    InProgress,
+    // Enough members approved, it can be executed
+    Approved,
+    // Too many members rejected to ever reach the threshold
+    Rejected,
+    Executed,
+    // The calls failed, it can be executed again
+    Failed,
+    // Only in views, proposals which are neither rejected nor executed expire
+    Expired,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Proposal {
+    pub proposer: AccountId,
+    pub description: String,
+    pub receiver_id: AccountId,
+    pub actions: Vec<FunctionCall>,
+    pub approvals: Vec<AccountId>,
+    pub rejections: Vec<AccountId>,
+    pub status: ProposalStatus,
+    pub expires_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalView {
+    pub id: U64,
+    pub proposer: AccountId,
+    pub description: String,
+    pub receiver_id: AccountId,
+    pub actions: Vec<FunctionCall>,
+    pub approvals: Vec<AccountId>,
+    pub rejections: Vec<AccountId>,
+    pub status: ProposalStatus,
+    pub expires_at: U64,
+}
+
+impl Proposal {
+    pub fn has_voted(&self, account_id: &AccountId) -> bool {
+        self.approvals.contains(account_id) || self.rejections.contains(account_id)
+    }
+
+    pub fn status_at(&self, now: Timestamp) -> ProposalStatus {
+        match self.status {
+            ProposalStatus::InProgress | ProposalStatus::Approved | ProposalStatus::Failed
+                if now >= self.expires_at =>
+            {
+                ProposalStatus::Expired
+            }
+            status => status,
+        }
+    }
+
+    pub fn to_view(&self, id: u64, now: Timestamp) -> ProposalView {
+        ProposalView {
+            id: U64(id),
+            proposer: self.proposer.clone(),
+            description: self.description.clone(),
+            receiver_id: self.receiver_id.clone(),
+            actions: self.actions.clone(),
+            approvals: self.approvals.clone(),
+            rejections: self.rejections.clone(),
+            status: self.status_at(now),
+            expires_at: U64(self.expires_at),
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::{env, fs};
+use workspaces::result::ExecutionFinalResult;
+use workspaces::{Account, AccountId, Contract};
+
+const TGAS: u64 = 1_000_000_000_000;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let dao_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let dao_wasm_filepath = fs::canonicalize(env::current_dir()?.join(dao_wasm_arg))?;
+    let ft_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let ft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(ft_wasm_arg))?;
+    let nft_wasm_arg: &str = &(env::args().nth(3).unwrap());
+    let nft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(nft_wasm_arg))?;
+    let factory_wasm_arg: &str = &(env::args().nth(4).unwrap());
+    let factory_wasm_filepath = fs::canonicalize(env::current_dir()?.join(factory_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let dao = worker.dev_deploy(&fs::read(dao_wasm_filepath)?).await?;
+    let ft = worker.dev_deploy(&fs::read(ft_wasm_filepath)?).await?;
+    let nft = worker.dev_deploy(&fs::read(nft_wasm_filepath)?).await?;
+    let factory = worker.dev_deploy(&fs::read(factory_wasm_filepath)?).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let carol = account
+        .create_subaccount("carol")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice, bob and carol are the members, two of them must approve
+    dao.call("new")
+        .args_json(json!({
+            "members": [alice.id(), bob.id(), carol.id()],
+            "threshold": 2,
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice launches the tokens and the factory, bob is registered on the fungible token
+    ft.call("new_default_meta")
+        .args_json(json!({ "owner_id": alice.id(), "total_supply": "1000000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    alice
+        .call(ft.id(), "storage_deposit")
+        .args_json(json!({ "account_id": bob.id() }))
+        .deposit(parse_near!("0.01 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    nft.call("new_default_meta")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    factory
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_ownership_is_handed_over(&alice, &dao, &ft, &nft, &factory).await?;
+    test_executes_once_approved(&alice, &bob, &carol, &dao, &ft).await?;
+    test_rejected(&alice, &bob, &carol, &dao, &ft).await?;
+    test_mint_nft(&alice, &bob, &carol, &dao, &nft).await?;
+    test_set_factory_fee(&alice, &bob, &dao, &factory).await?;
+    test_failed_calls(&alice, &bob, &dao, &ft).await?;
+    test_members_change_through_the_dao(&alice, &bob, &dao).await?;
+    test_proposals_expire(&alice, &bob, &carol, &dao, &ft).await?;
+    Ok(())
+}
+
+// Returns the ID of the proposal
+async fn propose(
+    member: &Account,
+    dao: &Contract,
+    receiver_id: &AccountId,
+    method_name: &str,
+    args: Value,
+    deposit: u128,
+) -> anyhow::Result<String> {
+    let proposal_id: String = member
+        .call(dao.id(), "add_proposal")
+        .args_json(json!({
+            "description": format!("Call {} on {}", method_name, receiver_id),
+            "receiver_id": receiver_id,
+            "actions": [{
+                "method_name": method_name,
+                "args": base64::encode(args.to_string()),
+                "deposit": deposit.to_string(),
+                "gas": (50 * TGAS).to_string(),
+            }],
+        }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(proposal_id)
+}
+
+async fn approve(member: &Account, dao: &Contract, proposal_id: &str) -> anyhow::Result<String> {
+    let status: String = member
+        .call(dao.id(), "approve")
+        .args_json(json!({ "proposal_id": proposal_id }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(status)
+}
+
+async fn execute(
+    member: &Account,
+    dao: &Contract,
+    proposal_id: &str,
+) -> anyhow::Result<ExecutionFinalResult> {
+    let result = member
+        .call(dao.id(), "execute")
+        .args_json(json!({ "proposal_id": proposal_id }))
+        .max_gas()
+        .transact()
+        .await?;
+    Ok(result)
+}
+
+async fn status(user: &Account, dao: &Contract, proposal_id: &str) -> anyhow::Result<String> {
+    let proposal: Value = user
+        .call(dao.id(), "get_proposal")
+        .args_json(json!({ "proposal_id": proposal_id }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(proposal["status"].as_str().unwrap().to_string())
+}
+
+async fn ft_balance_of(user: &Account, ft: &Contract) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(ft.id(), "ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+async fn test_ownership_is_handed_over(
+    alice: &Account,
+    dao: &Contract,
+    ft: &Contract,
+    nft: &Contract,
+    factory: &Contract,
+) -> anyhow::Result<()> {
+    for contract in [ft, nft, factory] {
+        alice
+            .call(contract.id(), "set_owner")
+            .args_json(json!({ "owner_id": dao.id() }))
+            .deposit(1)
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    for contract in [ft, nft, factory] {
+        let owner: AccountId = alice
+            .call(contract.id(), "get_owner")
+            .transact()
+            .await?
+            .json()?;
+        assert_eq!(&owner, dao.id());
+    }
+    let result = alice
+        .call(ft.id(), "ft_mint")
+        .args_json(json!({ "account_id": alice.id(), "amount": "1000" }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", result.into_result()).contains("Unauthorized"));
+    println!("      Passed ✅ the dao owns the ft, nft and factory contracts");
+    Ok(())
+}
+
+async fn test_executes_once_approved(
+    alice: &Account,
+    bob: &Account,
+    carol: &Account,
+    dao: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let args = json!({ "account_id": bob.id(), "amount": "1000" });
+    let proposal_id = propose(alice, dao, ft.id(), "ft_mint", args, 1).await?;
+    assert_eq!(status(alice, dao, &proposal_id).await?, "InProgress");
+
+    // alice's approval alone is not enough
+    let result = execute(alice, dao, &proposal_id).await?;
+    assert!(format!("{:?}", result.into_result()).contains("The proposal is not approved"));
+
+    assert_eq!(approve(bob, dao, &proposal_id).await?, "Approved");
+    let executed: bool = execute(carol, dao, &proposal_id).await?.json()?;
+
+    assert!(executed);
+    assert_eq!(status(alice, dao, &proposal_id).await?, "Executed");
+    assert_eq!(ft_balance_of(bob, ft).await?, 1000);
+
+    // and it runs only once
+    let result = execute(carol, dao, &proposal_id).await?;
+    assert!(result.is_failure());
+    assert_eq!(ft_balance_of(bob, ft).await?, 1000);
+    println!("      Passed ✅ proposals execute once approved");
+    Ok(())
+}
+
+async fn test_rejected(
+    alice: &Account,
+    bob: &Account,
+    carol: &Account,
+    dao: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let args = json!({ "account_id": alice.id(), "amount": "1000000" });
+    let proposal_id = propose(alice, dao, ft.id(), "ft_mint", args, 1).await?;
+
+    for member in [bob, carol] {
+        member
+            .call(dao.id(), "reject")
+            .args_json(json!({ "proposal_id": proposal_id }))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    assert_eq!(status(alice, dao, &proposal_id).await?, "Rejected");
+    assert!(execute(alice, dao, &proposal_id).await?.is_failure());
+    println!("      Passed ✅ proposals are rejected once they can't reach the threshold");
+    Ok(())
+}
+
+async fn test_mint_nft(
+    alice: &Account,
+    bob: &Account,
+    carol: &Account,
+    dao: &Contract,
+    nft: &Contract,
+) -> anyhow::Result<()> {
+    let args = json!({
+        "token_id": "1",
+        "receiver_id": carol.id(),
+        "token_metadata": { "title": "Olympus Mons" },
+        "royalties": {},
+    });
+    // the dao pays for the storage of the token
+    let proposal_id = propose(alice, dao, nft.id(), "nft_mint", args, parse_near!("0.1 N")).await?;
+    approve(bob, dao, &proposal_id).await?;
+    let executed: bool = execute(bob, dao, &proposal_id).await?.json()?;
+    assert!(executed);
+
+    let token: Value = carol
+        .call(nft.id(), "nft_token")
+        .args_json(json!({ "token_id": "1" }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(token["owner_id"], carol.id().to_string());
+    println!("      Passed ✅ the dao mints nfts");
+    Ok(())
+}
+
+async fn test_set_factory_fee(
+    alice: &Account,
+    bob: &Account,
+    dao: &Contract,
+    factory: &Contract,
+) -> anyhow::Result<()> {
+    let result = alice
+        .call(factory.id(), "set_fee")
+        .args_json(json!({ "fee": "1000" }))
+        .transact()
+        .await?;
+    assert!(format!("{:?}", result.into_result()).contains("Unauthorized"));
+
+    let args = json!({ "fee": "1000" });
+    let proposal_id = propose(bob, dao, factory.id(), "set_fee", args, 0).await?;
+    approve(alice, dao, &proposal_id).await?;
+    execute(alice, dao, &proposal_id).await?.into_result()?;
+
+    let fee: String = alice
+        .call(factory.id(), "get_fee")
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(fee, "1000");
+    println!("      Passed ✅ the dao sets the fee of the factory");
+    Ok(())
+}
+
+async fn test_failed_calls(
+    alice: &Account,
+    bob: &Account,
+    dao: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    // minting to an account which isn't registered on the token fails
+    let args = json!({ "account_id": "unregistered.near", "amount": "1000" });
+    let proposal_id = propose(alice, dao, ft.id(), "ft_mint", args, 1).await?;
+    approve(bob, dao, &proposal_id).await?;
+
+    let executed: bool = execute(alice, dao, &proposal_id).await?.json()?;
+    assert!(!executed);
+    assert_eq!(status(alice, dao, &proposal_id).await?, "Failed");
+    println!("      Passed ✅ failed calls leave the proposal to execute again");
+    Ok(())
+}
+
+async fn test_members_change_through_the_dao(
+    alice: &Account,
+    bob: &Account,
+    dao: &Contract,
+) -> anyhow::Result<()> {
+    let result = alice
+        .call(dao.id(), "add_member")
+        .args_json(json!({ "member_id": "dave.near" }))
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    // the dao calls itself, here with two calls in one proposal
+    let proposal_id: String = alice
+        .call(dao.id(), "add_proposal")
+        .args_json(json!({
+            "description": "Add dave, and make proposals expire after a second",
+            "receiver_id": dao.id(),
+            "actions": [{
+                "method_name": "add_member",
+                "args": base64::encode(json!({ "member_id": "dave.near" }).to_string()),
+                "deposit": "0",
+                "gas": (10 * TGAS).to_string(),
+            }, {
+                "method_name": "set_proposal_period",
+                "args": base64::encode(json!({ "proposal_period": "1000000000" }).to_string()),
+                "deposit": "0",
+                "gas": (10 * TGAS).to_string(),
+            }],
+        }))
+        .transact()
+        .await?
+        .json()?;
+    approve(bob, dao, &proposal_id).await?;
+    let executed: bool = execute(alice, dao, &proposal_id).await?.json()?;
+    assert!(executed);
+
+    let members: Vec<String> = alice
+        .call(dao.id(), "get_members")
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(members.len(), 4);
+    assert!(members.contains(&"dave.near".to_string()));
+    let proposal_period: String = alice
+        .call(dao.id(), "get_proposal_period")
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(proposal_period, "1000000000");
+    println!("      Passed ✅ members change through the dao");
+    Ok(())
+}
+
+async fn test_proposals_expire(
+    alice: &Account,
+    bob: &Account,
+    carol: &Account,
+    dao: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let args = json!({ "account_id": bob.id(), "amount": "1000" });
+    let proposal_id = propose(alice, dao, ft.id(), "ft_mint", args, 1).await?;
+
+    // proposals expire after a second now
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    let result = bob
+        .call(dao.id(), "approve")
+        .args_json(json!({ "proposal_id": proposal_id }))
+        .transact()
+        .await?;
+    assert!(format!("{:?}", result.into_result()).contains("The proposal expired"));
+
+    assert_eq!(status(carol, dao, &proposal_id).await?, "Expired");
+    println!("      Passed ✅ proposals expire");
+    Ok(())
+}
@@ -1,9 +1,13 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::LazyOption;
 use near_sdk::json_types::U128;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue};
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault,
+    PromiseOrValue,
+};
+use nep_297::nep_141::FtMint;
 
-pub use core_impl::FungibleToken;
+pub use crate::core_impl::FungibleToken;
 pub use nep_141::receiver::ext_ft_receiver;
 pub use nep_148::{FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC};
 
@@ -12,6 +16,15 @@ pub use nep_148::{FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METAD
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    // Mints tokens, `set_owner` hands it over, e.g. to a `dao` contract
+    owner_id: AccountId,
+}
+
+// The layout of the token before it had an owner
+#[derive(BorshDeserialize)]
+struct OldContract {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
@@ -41,11 +54,40 @@ impl Contract {
         let mut this = Self {
             token: FungibleToken::new(b"t".to_vec()),
             metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            owner_id: owner_id.clone(),
         };
         this.token.internal_register_account(&owner_id);
         this.token.internal_deposit(&owner_id, total_supply.into());
         this
     }
+
+    /// Gives a token deployed before `ft_mint` its owner
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old: OldContract = env::state_read().expect("No state to migrate");
+        Self {
+            token: old.token,
+            metadata: old.metadata,
+            owner_id,
+        }
+    }
+
+    /// Mints `amount` to `account_id`, which must be registered. Only the owner can mint, attaching
+    /// 1 yoctoNEAR.
+    #[payable]
+    pub fn ft_mint(&mut self, account_id: AccountId, amount: U128) {
+        assert_one_yocto();
+        ownable::assert_owner(&self.owner_id);
+        require!(amount.0 > 0, "Amount should be larger than 0");
+        self.token.internal_deposit(&account_id, amount.0);
+        FtMint {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
+    }
     fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
         log!("Closed @{} with {}", account_id, balance);
     }
@@ -54,8 +96,18 @@ impl Contract {
     }
 }
 
+ownable::impl_ownable!(Contract, owner_id);
 nep_141::impl_fungible_token_core!(Contract, token, on_tokens_burned);
 nep_141::impl_fungible_token_storage!(Contract, token, on_account_closed);
+nep_330::impl_contract_source_metadata!(
+    Contract,
+    [
+        ("nep141", "1.0.0"),
+        ("nep145", "1.0.0"),
+        ("nep148", "1.0.0"),
+        ("nep297", "1.0.0"),
+    ]
+);
 
 #[near_bindgen]
 impl FungibleTokenMetadataProvider for Contract {
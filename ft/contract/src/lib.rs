@@ -1,2 +1,6 @@
-mod core_impl;
+pub mod core_impl;
+#[cfg(feature = "contract")]
+mod ft;
 mod storage_impl;
+
+pub use core_impl::FungibleToken;
@@ -0,0 +1,206 @@
+use meta_tx::{calls, DelegateAction, Relayer, RpcClient};
+use near_crypto::SecretKey;
+use near_units::parse_near;
+use serde_json::json;
+use std::str::FromStr;
+use std::{env, fs};
+use workspaces::{Account, Contract};
+
+// Enough for the storage of the account and its key, but not for the gas of a transaction
+const GASLESS_BALANCE: u128 = parse_near!("0.002 N");
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let wasm_filepath = fs::canonicalize(env::current_dir()?.join(wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&fs::read(wasm_filepath)?).await?;
+    let rpc = RpcClient::new(&worker.rpc_addr());
+
+    // create accounts, alice has no NEAR to pay for gas
+    let relayer_account = worker.dev_create_account().await?;
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(GASLESS_BALANCE)
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let relayer = Relayer::new(
+        &worker.rpc_addr(),
+        relayer_account.id().as_str(),
+        secret_key(&relayer_account)?,
+    );
+
+    // the contract owns the supply, registers alice and bob and gives alice 100 tokens
+    contract
+        .call("new_default_meta")
+        .args_json(json!({ "owner_id": contract.id(), "total_supply": "1000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    for user in [&alice, &bob] {
+        contract
+            .call("storage_deposit")
+            .args_json(json!({ "account_id": user.id() }))
+            .deposit(parse_near!("0.01 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    contract
+        .call("ft_transfer")
+        .args_json(json!({ "receiver_id": alice.id(), "amount": "100" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_user_cannot_pay_for_gas(&alice, &bob, &contract).await?;
+    test_relayed_transfer(&alice, &bob, &contract, &rpc, &relayer).await?;
+    test_replay_fails(&alice, &bob, &contract, &rpc, &relayer).await?;
+    test_forged_signature_is_not_relayed(&alice, &bob, &contract, &rpc, &relayer).await?;
+    test_key_of_another_account_is_not_relayed(&alice, &bob, &contract, &rpc, &relayer).await?;
+    Ok(())
+}
+
+fn secret_key(account: &Account) -> anyhow::Result<SecretKey> {
+    Ok(SecretKey::from_str(&account.secret_key().to_string())?)
+}
+
+// reads go through the contract account, alice cannot pay for them
+async fn ft_balance_of(user: &Account, contract: &Contract) -> anyhow::Result<String> {
+    Ok(contract
+        .call("ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?)
+}
+
+async fn transfer_to_bob(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+) -> anyhow::Result<DelegateAction> {
+    let key = secret_key(alice)?;
+    DelegateAction::prepare(
+        rpc,
+        alice.id().as_str(),
+        key.public_key(),
+        contract.id().as_str(),
+        vec![calls::ft_transfer(
+            bob.id().as_str(),
+            40,
+            Some("paid by the relayer"),
+        )],
+    )
+    .await
+}
+
+async fn test_user_cannot_pay_for_gas(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let result = alice
+        .call(contract.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "amount": "40" }))
+        .deposit(1)
+        .gas(calls::GAS_FOR_TRANSFER)
+        .transact()
+        .await;
+
+    assert!(result.map_or(true, |outcome| outcome.is_failure()));
+    assert_eq!(ft_balance_of(alice, contract).await?, "100");
+    println!("      Passed ✅ a user without NEAR cannot send ft_transfer");
+    Ok(())
+}
+
+async fn test_relayed_transfer(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    let balance = alice.view_account().await?.balance;
+    let delegate_action = transfer_to_bob(alice, bob, contract, rpc).await?;
+
+    let outcome = relayer
+        .submit(delegate_action.sign(&secret_key(alice)?))
+        .await?;
+
+    assert!(outcome.is_success(), "{:?}", outcome.failures());
+    assert_eq!(ft_balance_of(alice, contract).await?, "60");
+    assert_eq!(ft_balance_of(bob, contract).await?, "40");
+    // the relayer paid the yoctoNEAR ft_transfer requires too
+    assert_eq!(alice.view_account().await?.balance, balance);
+    println!("      Passed ✅ the relayer pays for alice's ft_transfer");
+    Ok(())
+}
+
+async fn test_replay_fails(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    let signed = transfer_to_bob(alice, bob, contract, rpc)
+        .await?
+        .sign(&secret_key(alice)?);
+
+    assert!(relayer.submit(signed.clone()).await?.is_success());
+
+    // the relayer sees the nonce was used and doesn't pay for it again
+    assert!(relayer.submit(signed).await.is_err());
+    assert_eq!(ft_balance_of(alice, contract).await?, "20");
+    println!("      Passed ✅ a delegate action runs only once");
+    Ok(())
+}
+
+async fn test_forged_signature_is_not_relayed(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    // bob signs a transfer out of alice's account
+    let forged = transfer_to_bob(alice, bob, contract, rpc)
+        .await?
+        .sign(&secret_key(bob)?);
+
+    assert!(relayer.submit(forged).await.is_err());
+    assert_eq!(ft_balance_of(alice, contract).await?, "20");
+    println!("      Passed ✅ delegate actions not signed by their sender are not relayed");
+    Ok(())
+}
+
+async fn test_key_of_another_account_is_not_relayed(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    // bob validly signs with their own key, but names alice as the sender
+    let mut delegate_action = transfer_to_bob(alice, bob, contract, rpc).await?;
+    delegate_action.public_key = secret_key(bob)?.public_key();
+    let signed = delegate_action.sign(&secret_key(bob)?);
+
+    assert!(relayer.submit(signed).await.is_err());
+    assert_eq!(ft_balance_of(alice, contract).await?, "20");
+    println!("      Passed ✅ delegate actions signed with keys of other accounts are not relayed");
+    Ok(())
+}
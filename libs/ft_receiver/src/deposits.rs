@@ -0,0 +1,187 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, ext_contract, is_promise_success, log, AccountId, Balance, Gas, IntoStorageKey, Promise,
+};
+use nep_141::core::ext_ft_core;
+
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGAS
+pub const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[ext_contract(ext_ft_withdraw_resolver)]
+pub trait FtWithdrawResolver {
+    fn ft_resolve_withdraw(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> U128;
+}
+
+/// Balances of accounts in tokens, keyed by `(token_id, account_id)`
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Deposits {
+    balances: LookupMap<(AccountId, AccountId), Balance>,
+    // Transfers to each account waiting for their callback, which may credit them back
+    pending: LookupMap<AccountId, u32>,
+}
+
+impl Deposits {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            balances: LookupMap::new(prefix.clone()),
+            pending: LookupMap::new([prefix, b"p".to_vec()].concat()),
+        }
+    }
+
+    pub fn balance_of(&self, token_id: &AccountId, account_id: &AccountId) -> Balance {
+        self.balances
+            .get(&(token_id.clone(), account_id.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Whether tokens sent to `account_id` are waiting for their callback. Contracts should keep
+    /// such accounts registered, since a failed transfer deposits the tokens again.
+    pub fn has_pending(&self, account_id: &AccountId) -> bool {
+        self.pending.contains_key(account_id)
+    }
+
+    pub fn deposit(&mut self, token_id: &AccountId, account_id: &AccountId, amount: Balance) {
+        let balance = self.balance_of(token_id, account_id);
+        let new_balance = balance
+            .checked_add(amount)
+            .unwrap_or_else(|| env::panic_str("Balance overflow"));
+        self.balances
+            .insert(&(token_id.clone(), account_id.clone()), &new_balance);
+    }
+
+    pub fn internal_withdraw(
+        &mut self,
+        token_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let balance = self.balance_of(token_id, account_id);
+        let new_balance = balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("Not enough deposited"));
+        let key = (token_id.clone(), account_id.clone());
+        if new_balance == 0 {
+            self.balances.remove(&key);
+        } else {
+            self.balances.insert(&key, &new_balance);
+        }
+    }
+
+    /// Takes `amount` out of the deposit of `account_id` and sends it to them. The contract must
+    /// call [`impl_ft_withdraw!`](crate::impl_ft_withdraw), whose callback credits the tokens back
+    /// if the transfer fails.
+    pub fn withdraw(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: Balance,
+    ) -> Promise {
+        self.internal_withdraw(&token_id, &account_id, amount);
+        self.send(token_id, account_id, amount)
+    }
+
+    /// Sends `amount` the contract owes to `account_id` without it being deposited, like the
+    /// output of a swap. It is deposited if it can't be sent, see [`Deposits::withdraw`].
+    pub fn send(&mut self, token_id: AccountId, account_id: AccountId, amount: Balance) -> Promise {
+        let pending = self.pending.get(&account_id).unwrap_or(0);
+        self.pending.insert(&account_id, &(pending + 1));
+
+        ext_ft_core::ext(token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(account_id.clone(), U128(amount), None)
+            .then(
+                ext_ft_withdraw_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_WITHDRAW)
+                    .ft_resolve_withdraw(token_id, account_id, U128(amount)),
+            )
+    }
+
+    /// Returns the withdrawn amount, or 0 if the tokens couldn't be sent and were credited back
+    pub fn resolve_withdraw(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        match self.pending.get(&account_id).unwrap_or(0) {
+            0 | 1 => self.pending.remove(&account_id),
+            pending => self.pending.insert(&account_id, &(pending - 1)),
+        };
+
+        if is_promise_success() {
+            return amount;
+        }
+        log!(
+            "{} of {} could not be sent to {}, they are deposited again",
+            amount.0,
+            token_id,
+            account_id
+        );
+        self.deposit(&token_id, &account_id, amount.0);
+        U128(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::PromiseResult;
+    use test_utils::set_callback_result;
+
+    #[test]
+    fn deposits_are_per_token_and_account() {
+        let mut deposits = Deposits::new(b"d".to_vec());
+        deposits.deposit(&account("token"), &account("alice"), 10);
+        deposits.deposit(&account("token"), &account("alice"), 5);
+        deposits.deposit(&account("other"), &account("alice"), 1);
+
+        assert_eq!(
+            deposits.balance_of(&account("token"), &account("alice")),
+            15
+        );
+        assert_eq!(deposits.balance_of(&account("other"), &account("alice")), 1);
+        assert_eq!(deposits.balance_of(&account("token"), &account("bob")), 0);
+
+        deposits.internal_withdraw(&account("token"), &account("alice"), 15);
+        assert_eq!(deposits.balance_of(&account("token"), &account("alice")), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough deposited")]
+    fn withdraw_at_most_the_balance() {
+        let mut deposits = Deposits::new(b"d".to_vec());
+        deposits.deposit(&account("token"), &account("alice"), 10);
+        deposits.internal_withdraw(&account("token"), &account("alice"), 11);
+    }
+
+    #[test]
+    fn transfers_are_pending_until_resolved() {
+        let mut deposits = Deposits::new(b"d".to_vec());
+        deposits.deposit(&account("token"), &account("alice"), 10);
+        deposits.withdraw(account("token"), account("alice"), 4);
+        deposits.send(account("token"), account("alice"), 1);
+        assert!(deposits.has_pending(&account("alice")));
+
+        set_callback_result(PromiseResult::Successful(vec![]));
+        deposits.resolve_withdraw(account("token"), account("alice"), U128(4));
+        assert!(deposits.has_pending(&account("alice")));
+        deposits.resolve_withdraw(account("token"), account("alice"), U128(1));
+        assert!(!deposits.has_pending(&account("alice")));
+    }
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+}
@@ -0,0 +1,36 @@
+//! Helpers for the receivers of `ft_transfer_call`, like vaults and pools.
+//!
+//! - [`on_ft_transfer`] parses the typed `msg` of the transfer (see `receiver_msg`) and lets the
+//!   receiver accept part of the amount. The rest is returned as unused, for the token to refund.
+//! - [`Deposits`] keeps the balance of each account in each token, and sends them back with
+//!   [`Deposits::withdraw`]. [`Deposits::send`] sends tokens which weren't deposited, like the
+//!   output of a swap.
+//! - [`impl_ft_withdraw!`] adds the `ft_resolve_withdraw` callback of [`Deposits::withdraw`],
+//!   which credits the tokens back when they couldn't be sent.
+mod deposits;
+mod macros;
+
+use near_sdk::json_types::U128;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::{require, Balance, PromiseOrValue};
+
+pub use deposits::{Deposits, GAS_FOR_FT_TRANSFER, GAS_FOR_RESOLVE_WITHDRAW};
+
+/// For `ft_on_transfer`: `handle` returns how much of `amount` it accepted, the rest is refunded.
+/// When `msg` is malformed or `handle` returns an error, the whole amount is refunded, so `handle`
+/// must not change the state before returning an error.
+pub fn on_ft_transfer<M: DeserializeOwned>(
+    amount: U128,
+    msg: &str,
+    handle: impl FnOnce(M) -> Result<Balance, String>,
+) -> PromiseOrValue<U128> {
+    receiver_msg::on_ft_transfer(msg, amount, |msg| {
+        let accepted = handle(msg)?;
+        // Panicking reverts what `handle` did, and the token refunds everything
+        require!(
+            accepted <= amount.0,
+            "Accepted more than the transferred amount"
+        );
+        Ok(PromiseOrValue::Value(U128(amount.0 - accepted)))
+    })
+}
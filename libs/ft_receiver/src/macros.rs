@@ -0,0 +1,20 @@
+/// Adds the `ft_resolve_withdraw` callback used by [`Deposits::withdraw`](crate::Deposits::withdraw)
+/// to `$contract`, which keeps the [`Deposits`](crate::Deposits) in its `$deposits` field.
+#[macro_export]
+macro_rules! impl_ft_withdraw {
+    ($contract: ident, $deposits: ident) => {
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            pub fn ft_resolve_withdraw(
+                &mut self,
+                token_id: near_sdk::AccountId,
+                account_id: near_sdk::AccountId,
+                amount: near_sdk::json_types::U128,
+            ) -> near_sdk::json_types::U128 {
+                self.$deposits
+                    .resolve_withdraw(token_id, account_id, amount)
+            }
+        }
+    };
+}
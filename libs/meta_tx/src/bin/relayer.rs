@@ -0,0 +1,45 @@
+//! Relays signed delegate actions to a node, paying for their gas and deposits.
+//!
+//!     relayer <rpc-url> <relayer-account-id> <relayer-secret-key> [signed-delegate-action]
+//!
+//! The signed delegate action is the base64 of its borsh serialization. Without one, every line
+//! of stdin is relayed in turn.
+use std::env;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use meta_tx::{Relayer, SignedDelegateAction};
+use near_crypto::SecretKey;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 4 {
+        anyhow::bail!(
+            "Usage: {} <rpc-url> <relayer-account-id> <relayer-secret-key> [signed-delegate-action]",
+            args[0]
+        );
+    }
+    let relayer = Relayer::new(&args[1], &args[2], SecretKey::from_str(&args[3])?);
+
+    let encoded: Vec<String> = match args.get(4) {
+        Some(signed) => vec![signed.clone()],
+        None => io::stdin().lock().lines().collect::<Result<_, _>>()?,
+    };
+    for signed in encoded.iter().filter(|line| !line.trim().is_empty()) {
+        let signed = SignedDelegateAction::from_base64(signed)?;
+        let sender_id = signed.delegate_action.sender_id.clone();
+        let outcome = relayer.submit(signed).await?;
+        if outcome.is_success() {
+            println!("Relayed for {}: {}", sender_id, outcome.transaction_hash());
+        } else {
+            println!(
+                "Relayed for {} but it failed: {} {:?}",
+                sender_id,
+                outcome.transaction_hash(),
+                outcome.failures()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! The actions a user signs to move their tokens.
+use serde_json::json;
+
+use crate::{FunctionCallAction, NonDelegateAction};
+
+/// Gas of the transfers, paid by the relayer
+pub const GAS_FOR_TRANSFER: u64 = 30_000_000_000_000;
+
+/// `ft_transfer` of `amount` to `receiver_id`, with the 1 yoctoNEAR deposit it requires
+pub fn ft_transfer(receiver_id: &str, amount: u128, memo: Option<&str>) -> NonDelegateAction {
+    function_call(
+        "ft_transfer",
+        json!({ "receiver_id": receiver_id, "amount": amount.to_string(), "memo": memo }),
+    )
+}
+
+/// `nft_transfer` of `token_id` to `receiver_id`, with the 1 yoctoNEAR deposit it requires
+pub fn nft_transfer(
+    receiver_id: &str,
+    token_id: &str,
+    approval_id: Option<u64>,
+    memo: Option<&str>,
+) -> NonDelegateAction {
+    function_call(
+        "nft_transfer",
+        json!({
+            "receiver_id": receiver_id,
+            "token_id": token_id,
+            "approval_id": approval_id,
+            "memo": memo,
+        }),
+    )
+}
+
+fn function_call(method_name: &str, args: serde_json::Value) -> NonDelegateAction {
+    NonDelegateAction::FunctionCall(FunctionCallAction {
+        method_name: method_name.to_string(),
+        args: args.to_string().into_bytes(),
+        gas: GAS_FOR_TRANSFER,
+        deposit: 1,
+    })
+}
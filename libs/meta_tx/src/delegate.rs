@@ -0,0 +1,152 @@
+use std::io::{self, Write};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{PublicKey, SecretKey, Signature};
+use sha2::{Digest, Sha256};
+
+use crate::RpcClient;
+
+/// Prefix of the signed payload, so a delegate action can never be mistaken for a transaction
+/// signed with the same key ([NEP-461](https://github.com/near/NEPs/blob/master/neps/nep-0461.md))
+pub const NEP_366_DISCRIMINANT: u32 = (1 << 30) + 366;
+
+/// How many blocks a delegate action stays valid after it is built
+pub const MAX_BLOCK_HEIGHT_DELTA: u64 = 100;
+
+// Indices of the actions in the protocol's `Action` enum
+const FUNCTION_CALL_ACTION: u8 = 2;
+const TRANSFER_ACTION: u8 = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCallAction {
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub gas: u64,
+    pub deposit: u128,
+}
+
+/// An action a delegate action can run on behalf of its sender, anything but another delegate
+/// action. Only the ones the ft and nft contracts need are supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NonDelegateAction {
+    FunctionCall(FunctionCallAction),
+    Transfer { deposit: u128 },
+}
+
+// Written by hand to keep the protocol's enum indices
+impl BorshSerialize for NonDelegateAction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::FunctionCall(action) => {
+                FUNCTION_CALL_ACTION.serialize(writer)?;
+                action.serialize(writer)
+            }
+            Self::Transfer { deposit } => {
+                TRANSFER_ACTION.serialize(writer)?;
+                deposit.serialize(writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for NonDelegateAction {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        match u8::deserialize(buf)? {
+            FUNCTION_CALL_ACTION => Ok(Self::FunctionCall(FunctionCallAction::deserialize(buf)?)),
+            TRANSFER_ACTION => Ok(Self::Transfer {
+                deposit: u128::deserialize(buf)?,
+            }),
+            index => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported action {}", index),
+            )),
+        }
+    }
+}
+
+/// The actions `sender_id` runs on `receiver_id`, through a transaction sent by someone else
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DelegateAction {
+    pub sender_id: String,
+    pub receiver_id: String,
+    pub actions: Vec<NonDelegateAction>,
+    /// Must be greater than the nonce of the sender's access key, like the one of a transaction
+    pub nonce: u64,
+    /// The delegate action is rejected once the chain is past this height
+    pub max_block_height: u64,
+    pub public_key: PublicKey,
+}
+
+impl DelegateAction {
+    /// Builds a delegate action signed with `public_key`, valid for the next
+    /// [`MAX_BLOCK_HEIGHT_DELTA`] blocks.
+    pub async fn prepare(
+        rpc: &RpcClient,
+        sender_id: &str,
+        public_key: PublicKey,
+        receiver_id: &str,
+        actions: Vec<NonDelegateAction>,
+    ) -> anyhow::Result<Self> {
+        let nonce = rpc.access_key_nonce(sender_id, &public_key).await?;
+        let (height, _) = rpc.latest_block().await?;
+        Ok(Self {
+            sender_id: sender_id.to_string(),
+            receiver_id: receiver_id.to_string(),
+            actions,
+            nonce: nonce + 1,
+            max_block_height: height + MAX_BLOCK_HEIGHT_DELTA,
+            public_key,
+        })
+    }
+
+    /// Total deposit the actions attach, which the relayer pays
+    pub fn deposit(&self) -> u128 {
+        self.actions
+            .iter()
+            .map(|action| match action {
+                NonDelegateAction::FunctionCall(call) => call.deposit,
+                NonDelegateAction::Transfer { deposit } => *deposit,
+            })
+            .sum()
+    }
+
+    /// The hash the sender signs: sha256 of the discriminant and the borsh-serialized action
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(NEP_366_DISCRIMINANT.to_le_bytes());
+        hasher.update(self.try_to_vec().expect("Failed to serialize"));
+        hasher.finalize().into()
+    }
+
+    pub fn sign(self, secret_key: &SecretKey) -> SignedDelegateAction {
+        let signature = secret_key.sign(&self.hash());
+        SignedDelegateAction {
+            delegate_action: self,
+            signature,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedDelegateAction {
+    pub delegate_action: DelegateAction,
+    pub signature: Signature,
+}
+
+impl SignedDelegateAction {
+    pub fn verify(&self) -> bool {
+        self.signature.verify(
+            &self.delegate_action.hash(),
+            &self.delegate_action.public_key,
+        )
+    }
+
+    /// Base64 of the borsh-serialized action, as the `relayer` binary reads it
+    pub fn to_base64(&self) -> String {
+        base64::encode(self.try_to_vec().expect("Failed to serialize"))
+    }
+
+    pub fn from_base64(encoded: &str) -> anyhow::Result<Self> {
+        Ok(Self::try_from_slice(&base64::decode(encoded.trim())?)?)
+    }
+}
@@ -0,0 +1,26 @@
+//! Meta transactions ([NEP-366]) for the ft and nft contracts.
+//!
+//! - [`DelegateAction`] holds the actions a user wants to run, and is signed by them into a
+//!   [`SignedDelegateAction`]. The user needs no NEAR: the transaction of the relayer pays
+//!   for the gas and for the deposits the actions attach.
+//! - [`calls`] builds the `ft_transfer` and `nft_transfer` actions.
+//! - [`Relayer`] wraps a signed delegate action into a transaction of its own and pays for it,
+//!   once it checked that the key is the sender's. The `relayer` binary does the same from the
+//!   command line.
+//! - [`RpcClient`] is the small JSON-RPC client both sides use to read nonces and blocks.
+//!
+//! Delegate actions need protocol version 59 (nearcore 1.33). With an older sandbox, point
+//! `NEAR_SANDBOX_BIN_PATH` to a newer `near-sandbox` binary.
+//!
+//! [NEP-366]: https://github.com/near/NEPs/blob/master/neps/nep-0366.md
+pub mod calls;
+mod delegate;
+mod relayer;
+mod rpc;
+
+pub use delegate::{
+    DelegateAction, FunctionCallAction, NonDelegateAction, SignedDelegateAction,
+    MAX_BLOCK_HEIGHT_DELTA, NEP_366_DISCRIMINANT,
+};
+pub use relayer::{Action, Relayer, SignedTransaction, Transaction};
+pub use rpc::{Outcome, RpcClient};
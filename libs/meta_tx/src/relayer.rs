@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+
+use borsh::BorshSerialize;
+use near_crypto::{PublicKey, SecretKey, Signature};
+use sha2::{Digest, Sha256};
+
+use crate::{Outcome, RpcClient, SignedDelegateAction};
+
+// Index of `Delegate` in the protocol's `Action` enum
+const DELEGATE_ACTION: u8 = 8;
+
+/// The actions of the transactions the relayer sends
+#[derive(Clone, Debug)]
+pub enum Action {
+    Delegate(Box<SignedDelegateAction>),
+}
+
+impl BorshSerialize for Action {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Delegate(signed) => {
+                DELEGATE_ACTION.serialize(writer)?;
+                signed.serialize(writer)
+            }
+        }
+    }
+}
+
+#[derive(BorshSerialize, Clone, Debug)]
+pub struct Transaction {
+    pub signer_id: String,
+    pub public_key: PublicKey,
+    pub nonce: u64,
+    pub receiver_id: String,
+    pub block_hash: [u8; 32],
+    pub actions: Vec<Action>,
+}
+
+impl Transaction {
+    pub fn sign(self, secret_key: &SecretKey) -> SignedTransaction {
+        let hash: [u8; 32] = Sha256::digest(self.try_to_vec().expect("Failed to serialize")).into();
+        SignedTransaction {
+            signature: secret_key.sign(&hash),
+            transaction: self,
+        }
+    }
+}
+
+#[derive(BorshSerialize, Clone, Debug)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub signature: Signature,
+}
+
+// The yoctoNEAR `ft_transfer` and `nft_transfer` require
+const DEFAULT_MAX_DEPOSIT: u128 = 1;
+
+/// Sends delegate actions to the chain, paying for their gas and deposits with its own account
+pub struct Relayer {
+    rpc: RpcClient,
+    account_id: String,
+    secret_key: SecretKey,
+    max_deposit: u128,
+}
+
+impl Relayer {
+    pub fn new(rpc_url: &str, account_id: &str, secret_key: SecretKey) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url),
+            account_id: account_id.to_string(),
+            secret_key,
+            max_deposit: DEFAULT_MAX_DEPOSIT,
+        }
+    }
+
+    /// The most the actions of a delegate action can attach in total, 1 yoctoNEAR by default
+    pub fn max_deposit(mut self, max_deposit: u128) -> Self {
+        self.max_deposit = max_deposit;
+        self
+    }
+
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    /// Wraps the signed delegate action in a transaction to its sender, signed by the relayer.
+    /// Delegate actions are only paid for if they are signed by a key of their sender that
+    /// hasn't used their nonce yet, and attach at most `max_deposit`.
+    pub async fn submit(&self, signed: SignedDelegateAction) -> anyhow::Result<Outcome> {
+        anyhow::ensure!(signed.verify(), "Invalid delegate action signature");
+        let delegate_action = &signed.delegate_action;
+        anyhow::ensure!(
+            delegate_action.deposit() <= self.max_deposit,
+            "The actions attach more than {} yoctoNEAR",
+            self.max_deposit
+        );
+        // Fails if the key is not one of the sender's
+        let sender_nonce = self
+            .rpc
+            .access_key_nonce(&delegate_action.sender_id, &delegate_action.public_key)
+            .await?;
+        anyhow::ensure!(
+            delegate_action.nonce > sender_nonce,
+            "The nonce of the delegate action was already used"
+        );
+
+        let public_key = self.secret_key.public_key();
+        let nonce = self
+            .rpc
+            .access_key_nonce(&self.account_id, &public_key)
+            .await?;
+        let (_, block_hash) = self.rpc.latest_block().await?;
+        let transaction = Transaction {
+            signer_id: self.account_id.clone(),
+            public_key,
+            nonce: nonce + 1,
+            receiver_id: signed.delegate_action.sender_id.clone(),
+            block_hash,
+            actions: vec![Action::Delegate(Box::new(signed))],
+        };
+        self.rpc.send(&transaction.sign(&self.secret_key)).await
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{anyhow, bail};
+use borsh::BorshSerialize;
+use near_crypto::PublicKey;
+use serde_json::{json, Value};
+
+use crate::relayer::SignedTransaction;
+
+/// The few JSON-RPC methods the relayer and its users need
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": "meta_tx", "method": method, "params": params }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// The nonce of the access key, which the next transaction or delegate action must exceed
+    pub async fn access_key_nonce(
+        &self,
+        account_id: &str,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<u64> {
+        let result = self
+            .call(
+                "query",
+                json!({
+                    "request_type": "view_access_key",
+                    "finality": "optimistic",
+                    "account_id": account_id,
+                    "public_key": public_key.to_string(),
+                }),
+            )
+            .await?;
+        result["nonce"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("No access key {} on {}: {}", public_key, account_id, result))
+    }
+
+    /// Height and hash of the last final block
+    pub async fn latest_block(&self) -> anyhow::Result<(u64, [u8; 32])> {
+        let result = self.call("block", json!({ "finality": "final" })).await?;
+        let header = &result["header"];
+        let height = header["height"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("No block height: {}", result))?;
+        let hash = bs58::decode(header["hash"].as_str().unwrap_or_default()).into_vec()?;
+        Ok((
+            height,
+            hash.try_into().map_err(|_| anyhow!("Invalid block hash"))?,
+        ))
+    }
+
+    /// Sends the transaction and waits for all of its receipts
+    pub async fn send(&self, transaction: &SignedTransaction) -> anyhow::Result<Outcome> {
+        let encoded = base64::encode(transaction.try_to_vec()?);
+        Ok(Outcome(
+            self.call("broadcast_tx_commit", json!([encoded])).await?,
+        ))
+    }
+}
+
+/// The final execution outcome of a transaction, as returned by the RPC
+#[derive(Debug)]
+pub struct Outcome(pub Value);
+
+impl Outcome {
+    pub fn transaction_hash(&self) -> &str {
+        self.0["transaction"]["hash"].as_str().unwrap_or_default()
+    }
+
+    /// The failures of the transaction and its receipts. A delegate action succeeds as soon as
+    /// it is accepted, so the failures of the actions it carries only show up in the receipts.
+    pub fn failures(&self) -> Vec<&Value> {
+        let receipts = self.0["receipts_outcome"].as_array().into_iter().flatten();
+        std::iter::once(&self.0["transaction_outcome"])
+            .chain(receipts)
+            .filter_map(|outcome| outcome["outcome"]["status"].get("Failure"))
+            .chain(self.0["status"].get("Failure"))
+            .collect()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures().is_empty()
+    }
+}
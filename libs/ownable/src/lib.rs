@@ -0,0 +1,33 @@
+//! A transferable owner for contracts, which can be another contract like the `dao` multisig.
+//!
+//! - [`assert_owner`] guards the methods only the owner can call.
+//! - [`impl_ownable!`] adds `get_owner` and `set_owner` to a contract, for the `AccountId` field
+//!   holding its owner.
+use near_sdk::{env, require, AccountId};
+
+pub fn assert_owner(owner_id: &AccountId) {
+    require!(env::predecessor_account_id() == *owner_id, "Unauthorized");
+}
+
+/// Adds `get_owner` and `set_owner` to `$contract`, whose owner is the `AccountId` at
+/// `self.$owner`, e.g. `impl_ownable!(Contract, tokens.owner_id)`. `set_owner` requires 1
+/// yoctoNEAR, so that it can't be called with a function call access key.
+#[macro_export]
+macro_rules! impl_ownable {
+    ($contract: ident, $($owner: ident).+) => {
+        #[near_bindgen]
+        impl $contract {
+            pub fn get_owner(&self) -> near_sdk::AccountId {
+                self.$($owner).+.clone()
+            }
+
+            #[payable]
+            pub fn set_owner(&mut self, owner_id: near_sdk::AccountId) {
+                near_sdk::assert_one_yocto();
+                $crate::assert_owner(&self.$($owner).+);
+                near_sdk::log!("The owner changed from {} to {}", self.$($owner).+, owner_id);
+                self.$($owner).+ = owner_id;
+            }
+        }
+    };
+}
@@ -0,0 +1,200 @@
+//! Typed `msg` for the receivers of `nft_transfer_call`, `nft_approve` and `ft_transfer_call`.
+//!
+//! The `msg` is a JSON enum tagged by `action`, ex. `{"action": "set_message", "message": "Hi"}`
+//! for:
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! #[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+//! pub enum HelloMsg {
+//!     SetMessage { message: String },
+//! }
+//! ```
+//!
+//! [`dispatch`] parses the `msg` and hands it to the receiver. When the `msg` is malformed, or the
+//! receiver rejects it, the error is logged and the receiver returns what gives the tokens back,
+//! instead of panicking:
+//!
+//! - [`on_nft_transfer`] returns `true`, for the NFT to go back to its owner.
+//! - [`on_nft_approve`] returns the error.
+//! - [`on_ft_transfer`] returns the whole amount as unused.
+use near_sdk::json_types::U128;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::{log, PromiseOrValue};
+use std::fmt;
+
+/// Most bytes of an invalid `msg`, and of its parsing error, kept in `MsgError::Invalid`
+pub const MAX_ECHOED_LEN: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MsgError {
+    // The msg is not one of the actions of the receiver
+    Invalid { msg: String, error: String },
+    // The receiver understood the msg but refused it
+    Rejected(String),
+}
+
+impl fmt::Display for MsgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MsgError::Invalid { msg, error } => write!(f, "Invalid msg {msg:?}: {error}"),
+            MsgError::Rejected(reason) => write!(f, "Rejected msg: {reason}"),
+        }
+    }
+}
+
+pub fn parse<M: DeserializeOwned>(msg: &str) -> Result<M, MsgError> {
+    near_sdk::serde_json::from_str(msg).map_err(|error| MsgError::Invalid {
+        msg: truncate(msg),
+        error: truncate(&error.to_string()),
+    })
+}
+
+// The `msg` comes from the sender, so it is cut before being logged
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_ECHOED_LEN {
+        return text.to_string();
+    }
+    let mut end = MAX_ECHOED_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
+
+/// Parses `msg` and hands it to `handle`, which returns an error to reject it. Errors are logged
+/// and turned into a result by `rejected`.
+pub fn dispatch<M, R>(
+    msg: &str,
+    rejected: impl FnOnce(&MsgError) -> R,
+    handle: impl FnOnce(M) -> Result<R, String>,
+) -> R
+where
+    M: DeserializeOwned,
+{
+    match parse(msg).and_then(|msg| handle(msg).map_err(MsgError::Rejected)) {
+        Ok(result) => result,
+        Err(error) => {
+            log!("{}", error);
+            rejected(&error)
+        }
+    }
+}
+
+/// For `nft_on_transfer`: `handle` returns whether the NFT is returned
+pub fn on_nft_transfer<M: DeserializeOwned>(
+    msg: &str,
+    handle: impl FnOnce(M) -> Result<PromiseOrValue<bool>, String>,
+) -> PromiseOrValue<bool> {
+    dispatch(msg, |_| PromiseOrValue::Value(true), handle)
+}
+
+/// For `nft_on_approve`
+pub fn on_nft_approve<M: DeserializeOwned>(
+    msg: &str,
+    handle: impl FnOnce(M) -> Result<PromiseOrValue<String>, String>,
+) -> PromiseOrValue<String> {
+    dispatch(
+        msg,
+        |error| PromiseOrValue::Value(error.to_string()),
+        handle,
+    )
+}
+
+/// For `ft_on_transfer`: `handle` returns the unused amount
+pub fn on_ft_transfer<M: DeserializeOwned>(
+    msg: &str,
+    amount: U128,
+    handle: impl FnOnce(M) -> Result<PromiseOrValue<U128>, String>,
+) -> PromiseOrValue<U128> {
+    dispatch(msg, |_| PromiseOrValue::Value(amount), handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+    enum Msg {
+        SetMessage { message: String },
+        Reset,
+    }
+
+    #[test]
+    fn parses_tagged_actions() {
+        assert_eq!(
+            parse::<Msg>(r#"{"action": "set_message", "message": "Hi"}"#),
+            Ok(Msg::SetMessage {
+                message: "Hi".to_string()
+            })
+        );
+        assert_eq!(parse::<Msg>(r#"{"action": "reset"}"#), Ok(Msg::Reset));
+    }
+
+    #[test]
+    fn tells_why_a_msg_is_invalid() {
+        for msg in [
+            "set_message,Hi",
+            "",
+            r#"{"action": "delete"}"#,
+            r#"{"action": "set_message"}"#,
+        ] {
+            let error = parse::<Msg>(msg).unwrap_err();
+            assert!(matches!(error, MsgError::Invalid { .. }));
+            assert!(error
+                .to_string()
+                .starts_with(&format!("Invalid msg {:?}: ", msg)));
+        }
+        let error = parse::<Msg>(r#"{"action": "delete"}"#).unwrap_err();
+        assert!(error.to_string().contains("unknown variant `delete`"));
+    }
+
+    #[test]
+    fn truncates_long_msgs() {
+        let msg = format!(r#"{{"action": "{}"}}"#, "é".repeat(MAX_ECHOED_LEN));
+        match parse::<Msg>(&msg).unwrap_err() {
+            MsgError::Invalid { msg: echoed, error } => {
+                assert!(echoed.len() <= MAX_ECHOED_LEN + 3);
+                assert!(echoed.ends_with("..."));
+                assert!(msg.starts_with(echoed.trim_end_matches("...")));
+                assert!(error.len() <= MAX_ECHOED_LEN + 3);
+            }
+            MsgError::Rejected(_) => panic!("Expected an invalid msg"),
+        }
+    }
+
+    #[test]
+    fn bounces_invalid_and_rejected_msgs() {
+        let returned = |result| matches!(result, PromiseOrValue::Value(true));
+
+        assert!(returned(on_nft_transfer::<Msg>("set_message,Hi", |_| {
+            Ok(PromiseOrValue::Value(false))
+        })));
+        assert!(returned(on_nft_transfer::<Msg>(
+            r#"{"action": "reset"}"#,
+            |_| Err("Not now".to_string())
+        )));
+        assert!(!returned(on_nft_transfer::<Msg>(
+            r#"{"action": "reset"}"#,
+            |_| Ok(PromiseOrValue::Value(false))
+        )));
+    }
+
+    #[test]
+    fn returns_the_error_of_approvals() {
+        match on_nft_approve::<Msg>(r#"{"action": "reset"}"#, |_| Err("Not now".to_string())) {
+            PromiseOrValue::Value(error) => assert_eq!(error, "Rejected msg: Not now"),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    #[test]
+    fn refunds_the_whole_amount() {
+        match on_ft_transfer::<Msg>("{}", U128(10), |_| Ok(PromiseOrValue::Value(U128(0)))) {
+            PromiseOrValue::Value(unused) => assert_eq!(unused.0, 10),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Fixtures for the unit tests of the contracts, added as a dev-dependency.
+//!
+//! - [`set_context`] makes an account call the contract, attaching a deposit.
+//! - [`set_callback_result`] makes the contract call one of its callbacks, with the result of
+//!   the promise it waited for.
+//! - [`unused_amount`] reads what `ft_on_transfer` returned.
+//!
+//! The `_with` variants tweak the context further, e.g. its block or timestamp.
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, Balance, PromiseOrValue, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+pub fn set_context(predecessor: &str, deposit: Balance) {
+    set_context_with(predecessor, deposit, |_| {});
+}
+
+pub fn set_context_with(
+    predecessor: &str,
+    deposit: Balance,
+    tweak: impl FnOnce(&mut VMContextBuilder),
+) {
+    let mut builder = VMContextBuilder::new();
+    builder.predecessor_account_id(predecessor.parse().unwrap());
+    builder.attached_deposit(deposit);
+    tweak(&mut builder);
+
+    testing_env!(builder.build());
+}
+
+pub fn set_callback_result(result: PromiseResult) {
+    set_callback_result_with(result, |_| {});
+}
+
+/// The predecessor is set to the current account after `tweak`, so it follows its changes
+pub fn set_callback_result_with(result: PromiseResult, tweak: impl FnOnce(&mut VMContextBuilder)) {
+    let mut builder = VMContextBuilder::new();
+    tweak(&mut builder);
+    builder.predecessor_account_id(builder.context.current_account_id.clone());
+
+    testing_env!(
+        builder.build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![result],
+    );
+}
+
+/// Returns the unused amount
+pub fn unused_amount(result: PromiseOrValue<U128>) -> Balance {
+    match result {
+        PromiseOrValue::Value(unused) => unused.0,
+        PromiseOrValue::Promise(_) => panic!("Expected a value"),
+    }
+}
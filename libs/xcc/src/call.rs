@@ -0,0 +1,274 @@
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, log, AccountId, Balance, Gas, GasWeight, Promise, PromiseOrValue};
+
+use crate::{remaining_gas, tgas, CallError, CallResult, GasBudget};
+
+const DEFAULT_CALL_GAS: Gas = tgas(10);
+// Retries never attach more than this to the call, to leave room for the callbacks
+const MAX_CALL_GAS: Gas = tgas(250);
+
+/// Static gas of the `xcc_resolve` callback, which also gets a share of the unused gas
+pub const GAS_FOR_RESOLVE: Gas = tgas(10);
+
+/// How many times a failed call is made again, multiplying its gas each time
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Retry {
+    pub attempts: u8,
+    pub gas_multiplier: u8,
+}
+
+impl Retry {
+    pub fn new(attempts: u8) -> Self {
+        Self {
+            attempts,
+            gas_multiplier: 2,
+        }
+    }
+
+    pub fn gas_multiplier(mut self, gas_multiplier: u8) -> Self {
+        self.gas_multiplier = gas_multiplier;
+        self
+    }
+}
+
+/// Arguments of a method of another contract, which name the method they are for.
+///
+/// ```ignore
+/// #[derive(Serialize)]
+/// #[serde(crate = "near_sdk::serde")]
+/// pub struct SetGreetingArgs {
+///     pub message: String,
+/// }
+///
+/// impl CallArgs for SetGreetingArgs {
+///     const METHOD: &'static str = "set_greeting";
+/// }
+/// ```
+pub trait CallArgs: Serialize {
+    const METHOD: &'static str;
+}
+
+/// A function call on another contract
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Call {
+    receiver_id: AccountId,
+    method: String,
+    args: Base64VecU8,
+    deposit: U128,
+    gas: U64,
+    retry: Option<Retry>,
+    refund_to: Option<AccountId>,
+}
+
+impl Call {
+    pub fn new(receiver_id: AccountId, method: &str) -> Self {
+        Self {
+            receiver_id,
+            method: method.to_string(),
+            args: Base64VecU8(vec![]),
+            deposit: U128(0),
+            gas: U64(DEFAULT_CALL_GAS.0),
+            retry: None,
+            refund_to: None,
+        }
+    }
+
+    /// A call of `A::METHOD` with `args`, typed instead of naming the method
+    pub fn with_args<A: CallArgs>(receiver_id: AccountId, args: &A) -> Self {
+        Self::new(receiver_id, A::METHOD).args_json(args)
+    }
+
+    pub fn args_json<A: Serialize>(self, args: &A) -> Self {
+        self.args(near_sdk::serde_json::to_vec(args).unwrap())
+    }
+
+    pub fn args(mut self, args: Vec<u8>) -> Self {
+        self.args = Base64VecU8(args);
+        self
+    }
+
+    /// Attaches `deposit`, which `resolve` returns to the caller if the call fails
+    pub fn deposit(mut self, deposit: Balance) -> Self {
+        self.deposit = U128(deposit);
+        self.refund_to
+            .get_or_insert_with(env::predecessor_account_id);
+        self
+    }
+
+    /// Returns the deposit to `account_id` instead of the caller
+    pub fn refund_to(mut self, account_id: AccountId) -> Self {
+        self.refund_to = Some(account_id);
+        self
+    }
+
+    pub fn gas(&self) -> Gas {
+        Gas(self.gas.0)
+    }
+
+    pub fn with_gas(mut self, gas: Gas) -> Self {
+        self.gas = U64(gas.0);
+        self
+    }
+
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn promise(&self) -> Promise {
+        self.add_to(Promise::new(self.receiver_id.clone()))
+    }
+
+    /// Adds the call to `batch`, which must be a promise on the same receiver
+    pub fn add_to(&self, batch: Promise) -> Promise {
+        batch.function_call(
+            self.method.clone(),
+            self.args.0.clone(),
+            self.deposit.0,
+            self.gas(),
+        )
+    }
+
+    /// Makes the call and chains `xcc_resolve` (see `impl_xcc_resolve!`), which returns
+    /// a `CallResult<Value>` after refunding the deposit or retrying the call if it failed.
+    pub fn resolve(self) -> Promise {
+        let args = json!({ "call": self }).to_string().into_bytes();
+        self.promise().then(
+            Promise::new(env::current_account_id()).function_call_weight(
+                "xcc_resolve".to_string(),
+                args,
+                0,
+                GAS_FOR_RESOLVE,
+                GasWeight(1),
+            ),
+        )
+    }
+
+    /// The call to make again if it failed, with more gas and one attempt less
+    pub fn next_attempt(&self) -> Option<Call> {
+        let retry = self.retry.filter(|retry| retry.attempts > 0)?;
+        if self.gas.0 >= MAX_CALL_GAS.0 {
+            return None;
+        }
+
+        let gas = (self.gas.0 * retry.gas_multiplier as u64).min(MAX_CALL_GAS.0);
+        Some(Call {
+            gas: U64(gas),
+            retry: Some(Retry {
+                attempts: retry.attempts - 1,
+                ..retry
+            }),
+            ..self.clone()
+        })
+    }
+
+    /// Body of `xcc_resolve`. Only failed calls are retried or refunded: a call that returned
+    /// an invalid result already ran and kept the deposit.
+    pub fn on_result(self, result: CallResult<Value>) -> PromiseOrValue<CallResult<Value>> {
+        let err = match result {
+            Ok(value) => return PromiseOrValue::Value(Ok(value)),
+            Err(CallError::Failed) => CallError::Failed,
+            Err(err) => {
+                log!(
+                    "The call to {} on {} returned: {}",
+                    self.method,
+                    self.receiver_id,
+                    err
+                );
+                return PromiseOrValue::Value(Err(err));
+            }
+        };
+
+        if let Some(call) = self.next_attempt() {
+            // Without enough gas for the retry and its callback, the deposit is refunded below
+            let needed = GasBudget::new()
+                .call(&call)
+                .calls(GAS_FOR_RESOLVE, 1)
+                .total();
+            if remaining_gas() >= needed {
+                log!(
+                    "Calling {} on {} again with {} TGas",
+                    call.method,
+                    call.receiver_id,
+                    call.gas.0 / crate::TGAS
+                );
+                return PromiseOrValue::Promise(call.resolve());
+            }
+            log!(
+                "Not enough gas left to call {} on {} again",
+                call.method,
+                call.receiver_id
+            );
+        }
+
+        log!(
+            "The call to {} on {} failed: {}",
+            self.method,
+            self.receiver_id,
+            err
+        );
+        // The deposit came back to us with the failed receipt
+        if let (Some(refund_to), true) = (self.refund_to, self.deposit.0 > 0) {
+            Promise::new(refund_to).transfer(self.deposit.0);
+        }
+        PromiseOrValue::Value(Err(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn call() -> Call {
+        testing_env!(VMContextBuilder::new().prepaid_gas(tgas(300)).build());
+        Call::new("hello.near".parse().unwrap(), "set_greeting")
+            .deposit(10)
+            .retry(Retry::new(1))
+    }
+
+    fn error(result: PromiseOrValue<CallResult<Value>>) -> CallError {
+        match result {
+            PromiseOrValue::Value(Err(err)) => err,
+            PromiseOrValue::Value(Ok(_)) => panic!("Expected an error"),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    #[test]
+    fn invalid_results_are_not_retried_nor_refunded() {
+        let result = call().on_result(Err(CallError::InvalidResult("not JSON".to_string())));
+
+        assert_eq!(
+            error(result),
+            CallError::InvalidResult("not JSON".to_string())
+        );
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    fn failed_calls_are_retried() {
+        let result = call().on_result(Err(CallError::Failed));
+
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+    }
+
+    #[test]
+    fn failed_calls_without_retries_are_refunded() {
+        let call = Call {
+            retry: None,
+            ..call()
+        };
+
+        assert_eq!(
+            error(call.on_result(Err(CallError::Failed))),
+            CallError::Failed
+        );
+        assert_eq!(get_created_receipts().len(), 1);
+    }
+}
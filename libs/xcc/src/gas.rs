@@ -0,0 +1,65 @@
+use near_sdk::{env, require, Gas};
+
+use crate::Call;
+
+pub const TGAS: u64 = 1_000_000_000_000;
+
+/// Fees charged to the caller for each function call it schedules, to send and execute it
+pub const GAS_FOR_CALL_FEES: Gas = tgas(5);
+
+pub const fn tgas(amount: u64) -> Gas {
+    Gas(amount * TGAS)
+}
+
+/// Gas left to attach to new promises
+pub fn remaining_gas() -> Gas {
+    env::prepaid_gas() - env::used_gas()
+}
+
+/// Adds up the gas attached by a method to its calls and callbacks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasBudget {
+    total: Gas,
+}
+
+impl GasBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(mut self, gas: Gas) -> Self {
+        self.total = self.total + gas;
+        self
+    }
+
+    pub fn call(self, call: &Call) -> Self {
+        self.calls(call.gas(), 1)
+    }
+
+    /// `count` function calls (or callbacks) attaching `gas` each, with their fees
+    pub fn calls(self, gas: Gas, count: u64) -> Self {
+        self.add((gas + GAS_FOR_CALL_FEES) * count)
+    }
+
+    pub fn total(&self) -> Gas {
+        self.total
+    }
+
+    /// Fails early if `method` was not given enough gas, instead of letting its calls or
+    /// its callback run out of gas
+    pub fn require_prepaid(&self, method: &str) {
+        let prepaid = env::prepaid_gas();
+        require!(
+            prepaid >= self.total,
+            format!(
+                "{method} needs at least {} TGas, but only {} TGas were attached",
+                div_ceil(self.total.0, TGAS),
+                prepaid.0 / TGAS
+            )
+        );
+    }
+}
+
+fn div_ceil(a: u64, b: u64) -> u64 {
+    (a + b - 1) / b
+}
@@ -0,0 +1,16 @@
+//! Helpers for cross-contract calls.
+//!
+//! - [`Call`] builds a function call, with arguments typed through [`CallArgs`], a deposit, gas
+//!   and an optional [`Retry`].
+//! - [`CallResult`] is what a call returned, read in callbacks with [`promise_result`].
+//! - [`GasBudget`] adds up the gas a method needs to attach to its calls and callbacks.
+//! - [`impl_xcc_resolve!`] adds the `xcc_resolve` callback used by [`Call::resolve`], which refunds
+//!   the attached deposit when the call fails, and retries it with more gas.
+mod call;
+mod gas;
+mod macros;
+mod result;
+
+pub use call::{Call, CallArgs, Retry, GAS_FOR_RESOLVE};
+pub use gas::{remaining_gas, tgas, GasBudget, GAS_FOR_CALL_FEES, TGAS};
+pub use result::{from_callback, promise_result, CallError, CallResult, LogError};
@@ -0,0 +1,16 @@
+/// Adds the `xcc_resolve` callback used by [`Call::resolve`](crate::Call::resolve) to `$contract`.
+#[macro_export]
+macro_rules! impl_xcc_resolve {
+    ($contract: ident) => {
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            pub fn xcc_resolve(
+                &mut self,
+                call: $crate::Call,
+            ) -> near_sdk::PromiseOrValue<$crate::CallResult<near_sdk::serde_json::Value>> {
+                call.on_result($crate::promise_result(0))
+            }
+        }
+    };
+}
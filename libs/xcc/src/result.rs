@@ -0,0 +1,65 @@
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, log, PromiseError, PromiseResult};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum CallError {
+    Failed,
+    NotReady,
+    // The call succeeded, but returned something else than expected
+    InvalidResult(String),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Failed => write!(f, "the call failed"),
+            CallError::NotReady => write!(f, "the call is not ready"),
+            CallError::InvalidResult(err) => write!(f, "invalid result: {err}"),
+        }
+    }
+}
+
+pub type CallResult<T> = Result<T, CallError>;
+
+/// Result of the `index`-th promise the callback depends on, deserialized from JSON.
+/// Methods that return nothing give an empty result, which is read as `null`.
+pub fn promise_result<T: DeserializeOwned>(index: u64) -> CallResult<T> {
+    match env::promise_result(index) {
+        PromiseResult::Successful(value) => {
+            let value = if value.is_empty() {
+                b"null".to_vec()
+            } else {
+                value
+            };
+            near_sdk::serde_json::from_slice(&value)
+                .map_err(|err| CallError::InvalidResult(err.to_string()))
+        }
+        PromiseResult::Failed => Err(CallError::Failed),
+        PromiseResult::NotReady => Err(CallError::NotReady),
+    }
+}
+
+/// Converts the argument of a `#[callback_result]`
+pub fn from_callback<T>(result: Result<T, PromiseError>) -> CallResult<T> {
+    result.map_err(|err| match err {
+        PromiseError::NotReady => CallError::NotReady,
+        _ => CallError::Failed,
+    })
+}
+
+pub trait LogError {
+    /// Logs what went wrong with the call to `what`, if it did
+    fn log_error(self, what: &str) -> Self;
+}
+
+impl<T> LogError for CallResult<T> {
+    fn log_error(self, what: &str) -> Self {
+        if let Err(err) = &self {
+            log!("The call to {} failed: {}", what, err);
+        }
+        self
+    }
+}
@@ -0,0 +1,163 @@
+use crate::core_impl::{Approvals, MultiToken};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, Balance, Gas, Promise, StorageUsage};
+use nep_171::utils::{assert_at_least_one_yocto, refund_deposit};
+use nep_245::approval::MultiTokenApproval;
+use nep_245::approval_receiver::ext_mt_approval_receiver;
+use nep_245::token::{Approval, TokenId};
+
+const GAS_FOR_MT_APPROVE: Gas = Gas(10_000_000_000_000);
+
+fn expect_approval<T>(option: Option<T>) -> T {
+    option.unwrap_or_else(|| env::panic_str("MT does not support Approval Management"))
+}
+
+// Gives the owner back the deposit of the storage released by revoking approvals
+fn refund_released_storage(account_id: AccountId, initial_storage_usage: StorageUsage) {
+    let released = initial_storage_usage.saturating_sub(env::storage_usage());
+    if released > 0 {
+        Promise::new(account_id).transfer(Balance::from(released) * env::storage_byte_cost());
+    }
+}
+
+impl MultiToken {
+    fn internal_revoke<F>(&mut self, token_ids: Vec<TokenId>, mut revoke: F)
+    where
+        F: FnMut(&mut Approvals),
+    {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let approvals_by_id = expect_approval(self.approvals_by_id.as_mut());
+
+        for token_id in token_ids {
+            let key = (token_id, owner_id.clone());
+            // if the owner approved nobody, do nothing
+            let mut approvals = match approvals_by_id.get(&key) {
+                Some(approvals) => approvals,
+                None => continue,
+            };
+            revoke(&mut approvals);
+
+            if approvals.is_empty() {
+                approvals_by_id.remove(&key);
+            } else {
+                approvals_by_id.insert(&key, &approvals);
+            }
+        }
+
+        refund_released_storage(owner_id, initial_storage_usage);
+    }
+}
+
+impl MultiTokenApproval for MultiToken {
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        assert_at_least_one_yocto();
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token ids and amounts must be equal"
+        );
+
+        let owner_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let mut approval_ids = Vec::with_capacity(token_ids.len());
+
+        for (token_id, amount) in token_ids.iter().zip(amounts.iter()) {
+            require!(
+                self.internal_balance_of(&owner_id, token_id) >= amount.0,
+                "The owner doesn't have enough balance to approve"
+            );
+
+            let approvals_by_id = expect_approval(self.approvals_by_id.as_mut());
+            let next_approval_id_by_id = expect_approval(self.next_approval_id_by_id.as_mut());
+
+            // the approval id is incremented even when re-approving an account
+            let approval_id = next_approval_id_by_id.get(token_id).unwrap_or(1u64);
+            next_approval_id_by_id.insert(token_id, &(approval_id + 1));
+
+            let key = (token_id.clone(), owner_id.clone());
+            let mut approvals = approvals_by_id.get(&key).unwrap_or_default();
+            approvals.insert(
+                account_id.clone(),
+                Approval {
+                    amount: amount.0,
+                    approval_id,
+                },
+            );
+            approvals_by_id.insert(&key, &approvals);
+            approval_ids.push(approval_id);
+        }
+
+        // the owner pays for the storage of the new approvals
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+
+        // call mt_on_approve if msg is given
+        msg.map(|msg| {
+            require!(
+                env::prepaid_gas() > GAS_FOR_MT_APPROVE,
+                "Attach more gas to call mt_on_approve"
+            );
+            ext_mt_approval_receiver::ext(account_id)
+                .with_static_gas(env::prepaid_gas() - GAS_FOR_MT_APPROVE)
+                .mt_on_approve(token_ids, amounts, owner_id, approval_ids, msg)
+        })
+    }
+
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId) {
+        self.internal_revoke(token_ids, |approvals| {
+            approvals.remove(&account_id);
+        })
+    }
+
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>) {
+        self.internal_revoke(token_ids, |approvals| approvals.clear())
+    }
+
+    fn mt_is_approved(
+        &self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool {
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token ids and amounts must be equal"
+        );
+        if let Some(approval_ids) = &approval_ids {
+            require!(
+                token_ids.len() == approval_ids.len(),
+                "The number of token ids and approval ids must be equal"
+            );
+        }
+
+        let approvals_by_id = match self.approvals_by_id.as_ref() {
+            Some(approvals_by_id) => approvals_by_id,
+            // contract does not support approval management
+            None => return false,
+        };
+
+        token_ids.iter().enumerate().all(|(i, token_id)| {
+            let approval = approvals_by_id
+                .get(&(token_id.clone(), owner_id.clone()))
+                .and_then(|mut approvals| approvals.remove(&approved_account_id));
+            match approval {
+                Some(approval) => {
+                    approval.amount >= amounts[i].0
+                        && approval_ids
+                            .as_ref()
+                            .map_or(true, |ids| ids[i] == approval.approval_id)
+                }
+                // account not approved for this token
+                None => false,
+            }
+        })
+    }
+}
@@ -0,0 +1,729 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, require, AccountId, Balance, BorshStorageKey, Gas, IntoStorageKey, PromiseOrValue,
+    PromiseResult,
+};
+use nep_171::utils::{assert_at_least_one_yocto, refund_deposit, refund_deposit_to_account};
+use nep_245::core::MultiTokenCore;
+use nep_245::metadata::{MTBaseTokenMetadata, MTTokenMetadataAll};
+use nep_245::receiver::ext_mt_receiver;
+use nep_245::resolver::{ext_mt_resolver, MultiTokenResolver};
+use nep_245::token::{Approval, ClearedApproval, Token, TokenId};
+use nep_297::nep_245::{MtBurn, MtMint, MtTransfer};
+use std::collections::HashMap;
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN: Gas = Gas(3_000_000_000_000);
+const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000);
+
+/// The accounts an owner approved to transfer a token: approved account -> approval.
+pub type Approvals = HashMap<AccountId, Approval>;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MultiToken {
+    pub owner_id: AccountId,
+
+    // Every token and its total supply, ordered for the enumeration
+    pub supply_by_id: TreeMap<TokenId, Balance>,
+
+    pub balances_by_id: LookupMap<TokenId, LookupMap<AccountId, Balance>>,
+
+    // Only non-fungible tokens have an owner, burnt ones keep their last one
+    pub owner_by_id: LookupMap<TokenId, AccountId>,
+
+    // Metadata
+    pub token_metadata_by_id: Option<LookupMap<TokenId, MTTokenMetadataAll>>,
+
+    // Metadata
+    pub base_metadata_by_id: Option<LookupMap<String, MTBaseTokenMetadata>>,
+
+    // Enumeration
+    pub tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<TokenId>>>,
+
+    // Approval Management, by token and owner
+    pub approvals_by_id: Option<LookupMap<(TokenId, AccountId), Approvals>>,
+
+    // Approval Management
+    pub next_approval_id_by_id: Option<LookupMap<TokenId, u64>>,
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+pub enum StorageKey {
+    Supply,
+    BalancesById,
+    Balances { token_hash: Vec<u8> },
+    Owners,
+    TokensPerOwner { account_hash: Vec<u8> },
+}
+
+impl MultiToken {
+    // Only the extensions given a prefix are enabled
+    pub fn new<Q, R, S, T>(
+        prefix: Q,
+        owner_id: AccountId,
+        token_metadata_prefix: Option<R>,
+        enumeration_prefix: Option<S>,
+        approval_prefix: Option<T>,
+    ) -> Self
+    where
+        Q: IntoStorageKey,
+        R: IntoStorageKey,
+        S: IntoStorageKey,
+        T: IntoStorageKey,
+    {
+        let prefix: Vec<u8> = prefix.into_storage_key();
+        let with_prefix = |key: StorageKey| [prefix.clone(), key.into_storage_key()].concat();
+
+        let (token_metadata_by_id, base_metadata_by_id) =
+            if let Some(prefix) = token_metadata_prefix {
+                let prefix: Vec<u8> = prefix.into_storage_key();
+                (
+                    Some(LookupMap::new(prefix.clone())),
+                    Some(LookupMap::new([prefix, "b".into()].concat())),
+                )
+            } else {
+                (None, None)
+            };
+
+        let (approvals_by_id, next_approval_id_by_id) = if let Some(prefix) = approval_prefix {
+            let prefix: Vec<u8> = prefix.into_storage_key();
+            (
+                Some(LookupMap::new(prefix.clone())),
+                Some(LookupMap::new([prefix, "n".into()].concat())),
+            )
+        } else {
+            (None, None)
+        };
+
+        Self {
+            owner_id,
+            supply_by_id: TreeMap::new(with_prefix(StorageKey::Supply)),
+            balances_by_id: LookupMap::new(with_prefix(StorageKey::BalancesById)),
+            owner_by_id: LookupMap::new(with_prefix(StorageKey::Owners)),
+            token_metadata_by_id,
+            base_metadata_by_id,
+            tokens_per_owner: enumeration_prefix.map(LookupMap::new),
+            approvals_by_id,
+            next_approval_id_by_id,
+        }
+    }
+
+    pub fn internal_balance_of(&self, account_id: &AccountId, token_id: &TokenId) -> Balance {
+        self.balances_by_id
+            .get(token_id)
+            .and_then(|balances| balances.get(account_id))
+            .unwrap_or(0)
+    }
+
+    /// Adds `amount` of `token_id` to the balance of `account_id`, without any check.
+    pub fn internal_deposit(
+        &mut self,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut balances = self.balances_by_id.get(token_id).unwrap_or_else(|| {
+            let balances = LookupMap::new(StorageKey::Balances {
+                token_hash: env::sha256(token_id.as_bytes()),
+            });
+            self.balances_by_id.insert(token_id, &balances);
+            balances
+        });
+        let balance = balances.get(account_id).unwrap_or(0);
+        let new_balance = balance
+            .checked_add(amount)
+            .unwrap_or_else(|| env::panic_str("Balance overflow"));
+        balances.insert(account_id, &new_balance);
+
+        // the receiver is the new owner of a non-fungible token
+        if self.owner_by_id.contains_key(token_id) {
+            self.owner_by_id.insert(token_id, account_id);
+        }
+
+        // Enumeration extension
+        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            if balance == 0 {
+                let mut token_ids = tokens_per_owner.get(account_id).unwrap_or_else(|| {
+                    UnorderedSet::new(StorageKey::TokensPerOwner {
+                        account_hash: env::sha256(account_id.as_bytes()),
+                    })
+                });
+                token_ids.insert(token_id);
+                tokens_per_owner.insert(account_id, &token_ids);
+            }
+        }
+    }
+
+    /// Removes `amount` of `token_id` from the balance of `account_id`, panicking if it is
+    /// not enough.
+    pub fn internal_withdraw(
+        &mut self,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut balances = self
+            .balances_by_id
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        let balance = balances.get(account_id).unwrap_or(0);
+        require!(balance >= amount, "The account doesn't have enough balance");
+
+        let new_balance = balance - amount;
+        if new_balance > 0 {
+            balances.insert(account_id, &new_balance);
+            return;
+        }
+        balances.remove(account_id);
+
+        // Enumeration extension
+        if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            if let Some(mut token_ids) = tokens_per_owner.get(account_id) {
+                token_ids.remove(token_id);
+                if token_ids.is_empty() {
+                    tokens_per_owner.remove(account_id);
+                } else {
+                    tokens_per_owner.insert(account_id, &token_ids);
+                }
+            }
+        }
+    }
+
+    /// Transfers `amount` of `token_id` from its owner to `receiver_id`. The owner is the
+    /// sender, or the account given in `approval` when the sender was approved by it.
+    ///
+    /// Returns the previous owner and the approval used by the sender, if any.
+    pub fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<&str>,
+    ) -> (AccountId, Option<Vec<ClearedApproval>>) {
+        require!(amount > 0, "The amount should be a positive number");
+
+        let (owner_id, cleared_approvals) = match approval {
+            Some((owner_id, approval_id)) if &owner_id != sender_id => {
+                let cleared =
+                    self.internal_use_approval(&owner_id, sender_id, token_id, amount, approval_id);
+                (owner_id, Some(vec![cleared]))
+            }
+            _ => (sender_id.clone(), None),
+        };
+
+        require!(
+            &owner_id != receiver_id,
+            "Current and next owner must differ"
+        );
+
+        self.internal_withdraw(token_id, &owner_id, amount);
+        self.internal_deposit(token_id, receiver_id, amount);
+
+        MtTransfer {
+            old_owner_id: &owner_id,
+            new_owner_id: receiver_id,
+            token_ids: &[token_id],
+            amounts: &[U128(amount)],
+            authorized_id: cleared_approvals.as_ref().map(|_| sender_id),
+            memo,
+        }
+        .emit();
+
+        (owner_id, cleared_approvals)
+    }
+
+    // Decreases the approval of `sender_id` by `amount`, removing it when nothing is left
+    fn internal_use_approval(
+        &mut self,
+        owner_id: &AccountId,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        approval_id: u64,
+    ) -> ClearedApproval {
+        let approvals_by_id = self
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("Unauthorized"));
+
+        let key = (token_id.clone(), owner_id.clone());
+        let mut approvals = approvals_by_id
+            .get(&key)
+            .unwrap_or_else(|| env::panic_str("Sender not approved"));
+        let approval = approvals
+            .get_mut(sender_id)
+            .unwrap_or_else(|| env::panic_str("Sender not approved"));
+
+        require!(
+            approval.approval_id == approval_id,
+            format!(
+                "The actual approval_id {} is different from the given approval_id {}",
+                approval.approval_id, approval_id
+            )
+        );
+        require!(approval.amount >= amount, "Not enough approved");
+
+        approval.amount -= amount;
+        if approval.amount == 0 {
+            approvals.remove(sender_id);
+        }
+
+        if approvals.is_empty() {
+            approvals_by_id.remove(&key);
+        } else {
+            approvals_by_id.insert(&key, &approvals);
+        }
+
+        (sender_id.clone(), approval_id, U128(amount))
+    }
+
+    // Gives back up to `amount` of the approvals cleared by a reverted transfer. Approvals
+    // renewed since then, with another approval id, are left alone.
+    fn internal_restore_approvals(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+        cleared_approvals: Vec<ClearedApproval>,
+        mut amount: Balance,
+    ) {
+        let approvals_by_id = match self.approvals_by_id.as_mut() {
+            Some(approvals_by_id) => approvals_by_id,
+            None => return,
+        };
+
+        let key = (token_id.clone(), owner_id.clone());
+        let mut approvals = approvals_by_id.get(&key).unwrap_or_default();
+        for (account_id, approval_id, cleared) in cleared_approvals {
+            let restored = amount.min(cleared.0);
+            if restored == 0 {
+                break;
+            }
+            amount -= restored;
+
+            let approval = approvals.entry(account_id).or_insert(Approval {
+                amount: 0,
+                approval_id,
+            });
+            if approval.approval_id == approval_id {
+                approval.amount += restored;
+            }
+        }
+        approvals_by_id.insert(&key, &approvals);
+    }
+
+    pub fn internal_batch_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<&str>,
+    ) -> (Vec<AccountId>, Vec<Option<Vec<ClearedApproval>>>) {
+        require!(!token_ids.is_empty(), "Nothing to transfer");
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token ids and amounts must be equal"
+        );
+        let approvals = approvals.unwrap_or_else(|| vec![None; token_ids.len()]);
+        require!(
+            token_ids.len() == approvals.len(),
+            "The number of token ids and approvals must be equal"
+        );
+
+        token_ids
+            .iter()
+            .zip(amounts)
+            .zip(approvals)
+            .map(|((token_id, amount), approval)| {
+                self.internal_transfer(sender_id, receiver_id, token_id, amount.0, approval, memo)
+            })
+            .unzip()
+    }
+
+    fn internal_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        assert_at_least_one_yocto();
+        let gas_for_resolve =
+            GAS_FOR_RESOLVE_TRANSFER + GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN * token_ids.len() as u64;
+        require!(
+            env::prepaid_gas() > GAS_FOR_MT_TRANSFER_CALL + gas_for_resolve,
+            "More gas is required"
+        );
+
+        let sender_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let (previous_owner_ids, cleared_approvals) = self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo.as_deref(),
+        );
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+        let cleared_approvals = self.approvals_by_id.as_ref().map(|_| cleared_approvals);
+
+        // Initiating receiver's call and the callback
+        ext_mt_receiver::ext(receiver_id.clone())
+            .with_static_gas(env::prepaid_gas() - GAS_FOR_MT_TRANSFER_CALL - gas_for_resolve)
+            .mt_on_transfer(
+                sender_id,
+                previous_owner_ids.clone(),
+                token_ids.clone(),
+                amounts.clone(),
+                msg,
+            )
+            .then(
+                ext_mt_resolver::ext(env::current_account_id())
+                    .with_static_gas(gas_for_resolve)
+                    .mt_resolve_transfer(
+                        previous_owner_ids,
+                        receiver_id,
+                        token_ids,
+                        amounts,
+                        cleared_approvals,
+                    ),
+            )
+            .into()
+    }
+
+    /// Mints `amount` of a fungible token, creating it if it doesn't exist yet. Existing tokens
+    /// keep their metadata, new ones must be given one when the metadata extension is used.
+    ///
+    /// `refund_id` is the account to refund the unused deposit to, usually the owner. If None,
+    /// nothing is refunded, which is useful to delay the refund until several mints are done.
+    ///
+    /// Returns the minted token and emits the mint event.
+    pub fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        token_owner_id: AccountId,
+        amount: Balance,
+        token_metadata: Option<MTTokenMetadataAll>,
+        refund_id: Option<AccountId>,
+    ) -> Token {
+        require!(amount > 0, "The amount should be a positive number");
+        require!(
+            !self.owner_by_id.contains_key(&token_id),
+            "Non-fungible tokens can not be minted again"
+        );
+        let initial_storage_usage = refund_id.map(|account_id| (account_id, env::storage_usage()));
+
+        let supply = match self.supply_by_id.get(&token_id) {
+            Some(supply) => supply,
+            None => {
+                self.internal_register_metadata(&token_id, token_metadata);
+                0
+            }
+        };
+        let new_supply = supply
+            .checked_add(amount)
+            .unwrap_or_else(|| env::panic_str("Total supply overflow"));
+        self.supply_by_id.insert(&token_id, &new_supply);
+        self.internal_deposit(&token_id, &token_owner_id, amount);
+
+        MtMint {
+            owner_id: &token_owner_id,
+            token_ids: &[&token_id],
+            amounts: &[U128(amount)],
+            memo: None,
+        }
+        .emit();
+
+        if let Some((id, storage_usage)) = initial_storage_usage {
+            refund_deposit_to_account(env::storage_usage() - storage_usage, id)
+        }
+
+        Token {
+            token_id,
+            owner_id: None,
+        }
+    }
+
+    /// Mints a non-fungible token, of which there is a single copy with an owner.
+    ///
+    /// Returns the minted token and emits the mint event.
+    pub fn internal_mint_unique(
+        &mut self,
+        token_id: TokenId,
+        token_owner_id: AccountId,
+        token_metadata: Option<MTTokenMetadataAll>,
+        refund_id: Option<AccountId>,
+    ) -> Token {
+        let initial_storage_usage = refund_id.map(|account_id| (account_id, env::storage_usage()));
+
+        if self.supply_by_id.get(&token_id).is_some() {
+            env::panic_str("token_id must be unique");
+        }
+        self.internal_register_metadata(&token_id, token_metadata);
+        self.supply_by_id.insert(&token_id, &1);
+        self.owner_by_id.insert(&token_id, &token_owner_id);
+        self.internal_deposit(&token_id, &token_owner_id, 1);
+
+        MtMint {
+            owner_id: &token_owner_id,
+            token_ids: &[&token_id],
+            amounts: &[U128(1)],
+            memo: None,
+        }
+        .emit();
+
+        if let Some((id, storage_usage)) = initial_storage_usage {
+            refund_deposit_to_account(env::storage_usage() - storage_usage, id)
+        }
+
+        Token {
+            token_id,
+            owner_id: Some(token_owner_id),
+        }
+    }
+
+    fn internal_register_metadata(
+        &mut self,
+        token_id: &TokenId,
+        token_metadata: Option<MTTokenMetadataAll>,
+    ) {
+        let (token_metadata_by_id, base_metadata_by_id) = match (
+            self.token_metadata_by_id.as_mut(),
+            self.base_metadata_by_id.as_mut(),
+        ) {
+            (Some(token_metadata_by_id), Some(base_metadata_by_id)) => {
+                (token_metadata_by_id, base_metadata_by_id)
+            }
+            _ => return,
+        };
+
+        let mut metadata =
+            token_metadata.unwrap_or_else(|| env::panic_str("Must provide metadata"));
+        metadata.base.assert_valid();
+        metadata.token.assert_valid();
+
+        // tokens sharing a base metadata id share the first one registered
+        match base_metadata_by_id.get(&metadata.base.id) {
+            Some(base) => metadata.base = base,
+            None => {
+                base_metadata_by_id.insert(&metadata.base.id, &metadata.base);
+            }
+        }
+        token_metadata_by_id.insert(token_id, &metadata);
+    }
+
+    /// Burns `amount` of `token_id` of `owner_id`, lowering its total supply.
+    pub fn internal_burn(
+        &mut self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_withdraw(token_id, owner_id, amount);
+
+        // A burnt non-fungible token keeps its entry in `owner_by_id`, so `internal_mint`
+        // can't mint its id again as a fungible token
+        let supply = self.supply_by_id.get(token_id).unwrap_or_default();
+        self.supply_by_id.insert(token_id, &(supply - amount));
+
+        MtBurn {
+            owner_id,
+            token_ids: &[token_id],
+            amounts: &[U128(amount)],
+            authorized_id: None,
+            memo,
+        }
+        .emit();
+    }
+
+    pub(crate) fn internal_token(&self, token_id: TokenId) -> Option<Token> {
+        let supply = self.supply_by_id.get(&token_id)?;
+        // burnt non-fungible tokens have no owner
+        let owner_id = self.owner_by_id.get(&token_id).filter(|_| supply > 0);
+        Some(Token { token_id, owner_id })
+    }
+}
+
+impl MultiTokenCore for MultiToken {
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    ) {
+        assert_at_least_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        self.internal_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            amount.0,
+            approval,
+            memo.as_deref(),
+        );
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) {
+        assert_at_least_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo.as_deref(),
+        );
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_transfer_call(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            Some(vec![approval]),
+            memo,
+            msg,
+        )
+    }
+
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_transfer_call(receiver_id, token_ids, amounts, approvals, memo, msg)
+    }
+
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.internal_token(token_id))
+            .collect()
+    }
+
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+        U128(self.internal_balance_of(&account_id, &token_id))
+    }
+
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+        token_ids
+            .iter()
+            .map(|token_id| U128(self.internal_balance_of(&account_id, token_id)))
+            .collect()
+    }
+
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+        self.supply_by_id.get(&token_id).map(U128)
+    }
+
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+        token_ids
+            .iter()
+            .map(|token_id| self.supply_by_id.get(token_id).map(U128))
+            .collect()
+    }
+}
+
+impl MultiTokenResolver for MultiToken {
+    /// Returns the amounts that were transferred to `receiver_id` in the end.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<Vec<ClearedApproval>>>>,
+    ) -> Vec<U128> {
+        // The unused amounts returned by the receiver. Everything is returned if the call
+        // failed or the receiver answered with something else.
+        let unused_amounts = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                match near_sdk::serde_json::from_slice::<Vec<U128>>(&value) {
+                    Ok(unused) if unused.len() == amounts.len() => unused,
+                    _ => amounts.clone(),
+                }
+            }
+            PromiseResult::Failed => amounts.clone(),
+        };
+        let mut approvals = approvals.unwrap_or_default().into_iter();
+
+        previous_owner_ids
+            .iter()
+            .zip(token_ids.iter())
+            .zip(amounts.iter().zip(unused_amounts))
+            .map(|((previous_owner_id, token_id), (amount, unused))| {
+                let cleared_approvals = approvals.next().flatten();
+                let unused = unused.0.min(amount.0);
+                if unused == 0 {
+                    return *amount;
+                }
+
+                // The receiver may have already transferred or burned some of the tokens
+                let refund = unused.min(self.internal_balance_of(&receiver_id, token_id));
+                if refund == 0 {
+                    return *amount;
+                }
+
+                self.internal_withdraw(token_id, &receiver_id, refund);
+                self.internal_deposit(token_id, previous_owner_id, refund);
+                if let Some(cleared_approvals) = cleared_approvals {
+                    self.internal_restore_approvals(
+                        previous_owner_id,
+                        token_id,
+                        cleared_approvals,
+                        refund,
+                    );
+                }
+
+                MtTransfer {
+                    old_owner_id: &receiver_id,
+                    new_owner_id: previous_owner_id,
+                    token_ids: &[token_id],
+                    amounts: &[U128(refund)],
+                    authorized_id: None,
+                    memo: None,
+                }
+                .emit();
+
+                U128(amount.0 - refund)
+            })
+            .collect()
+    }
+}
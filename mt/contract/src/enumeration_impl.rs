@@ -0,0 +1,65 @@
+use crate::core_impl::MultiToken;
+use near_sdk::json_types::U128;
+use near_sdk::{env, require, AccountId};
+use nep_245::enumeration::MultiTokenEnumeration;
+use nep_245::token::Token;
+
+impl MultiTokenEnumeration for MultiToken {
+    fn mt_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        // Get starting index, whether or not it was explicitly given.
+        // Defaults to 0 based on the spec.
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        require!(
+            (self.supply_by_id.len() as u128) >= start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        self.supply_by_id
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|(token_id, supply)| {
+                // burnt non-fungible tokens have no owner
+                let owner_id = self.owner_by_id.get(&token_id).filter(|_| supply > 0);
+                Token { token_id, owner_id }
+            })
+            .collect()
+    }
+
+    fn mt_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        let tokens_per_owner = self.tokens_per_owner.as_ref().unwrap_or_else(|| {
+            env::panic_str(
+                "Could not find tokens_per_owner when calling a method on the \
+                enumeration standard.",
+            )
+        });
+        let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+            token_set
+        } else {
+            return vec![];
+        };
+
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        require!(
+            token_set.len() as u128 > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        token_set
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|token_id| {
+                let owner_id = self.owner_by_id.get(&token_id);
+                Token { token_id, owner_id }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,182 @@
+/*!
+Multi Token implementation with JSON serialization.
+NOTES:
+  - A single contract holds fungible tokens, of which many accounts hold a balance, and
+    non-fungible tokens, which have a single copy and an owner. Games can keep their gold and
+    their unique swords side by side.
+  - The maximum balance value is limited by U128 (2**128 - 1).
+  - JSON calls should pass U128 as a base-10 string. E.g. "100".
+  - Minting and approving track the change in storage before and after the call, and require
+    the caller to attach enough deposit to cover it. The unused deposit is refunded, so it's
+    safe to attach more deposit than required.
+  - Transfers charge the sender for the storage of the balances they create, ex. when the
+    receiver didn't hold the token yet. Attach 1 yoctoNEAR when the receiver already holds it.
+  - Revoking approvals refunds the deposit of the storage that was released.
+  - To prevent the deployed contract from being modified or deleted, it should not have any access
+    keys on its account.
+*/
+
+pub mod approval_impl;
+pub mod core_impl;
+pub mod enumeration_impl;
+pub mod metadata_impl;
+
+use core_impl::MultiToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault,
+    Promise, PromiseOrValue,
+};
+use nep_245::metadata::{
+    MTBaseTokenMetadata, MTContractMetadata, MTTokenMetadata, MTTokenMetadataAll,
+    MultiTokenMetadataProvider, MT_METADATA_SPEC,
+};
+use nep_245::token::{Token, TokenId};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: MultiToken,
+    metadata: LazyOption<MTContractMetadata>,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    MultiToken,
+    Metadata,
+    TokenMetadata,
+    Enumeration,
+    Approval,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Initializes the contract owned by `owner_id` with
+    /// default metadata (for example purposes only).
+    #[init]
+    pub fn new_default_meta(owner_id: AccountId) -> Self {
+        Self::new(
+            owner_id,
+            MTContractMetadata {
+                spec: MT_METADATA_SPEC.to_string(),
+                name: "Example NEAR multi token".to_string(),
+            },
+        )
+    }
+
+    #[init]
+    pub fn new(owner_id: AccountId, metadata: MTContractMetadata) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        metadata.assert_valid();
+        Self {
+            tokens: MultiToken::new(
+                StorageKey::MultiToken,
+                owner_id,
+                Some(StorageKey::TokenMetadata),
+                Some(StorageKey::Enumeration),
+                Some(StorageKey::Approval),
+            ),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+        }
+    }
+
+    /// Mint `amount` of the fungible token with ID=`token_id` to `receiver_id`. Minting an
+    /// existing token increases its supply and keeps its metadata, so `token_metadata` is
+    /// only required the first time.
+    ///
+    /// Only the `owner_id` given in initialization call to `new` can mint, attaching enough
+    /// deposit to cover the storage.
+    #[payable]
+    pub fn mt_mint(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        amount: U128,
+        token_metadata: Option<MTTokenMetadataAll>,
+    ) -> Token {
+        self.assert_owner();
+        self.tokens.internal_mint(
+            token_id,
+            receiver_id,
+            amount.0,
+            token_metadata,
+            Some(env::predecessor_account_id()),
+        )
+    }
+
+    /// Mint the non-fungible token with ID=`token_id`, of which there is a single copy
+    /// belonging to `receiver_id`.
+    #[payable]
+    pub fn mt_mint_unique(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: MTTokenMetadataAll,
+    ) -> Token {
+        self.assert_owner();
+        self.tokens.internal_mint_unique(
+            token_id,
+            receiver_id,
+            Some(token_metadata),
+            Some(env::predecessor_account_id()),
+        )
+    }
+
+    /// Burn `amount` of `token_id` of the caller, ex. when an item is used up in a game.
+    #[payable]
+    pub fn mt_burn(&mut self, token_id: TokenId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        self.tokens
+            .internal_burn(&token_id, &owner_id, amount.0, memo.as_deref());
+    }
+
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.tokens.owner_id,
+            "Unauthorized"
+        );
+    }
+}
+
+pub mod macros;
+
+impl_multi_token_core!(Contract, tokens);
+impl_multi_token_approval!(Contract, tokens);
+impl_multi_token_enumeration!(Contract, tokens);
+nep_330::impl_contract_source_metadata!(Contract, [("nep245", "1.0.0"), ("nep297", "1.0.0")]);
+
+#[near_bindgen]
+impl MultiTokenMetadataProvider for Contract {
+    fn mt_metadata_contract(&self) -> MTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+
+    fn mt_metadata_token_all(&self, token_ids: Vec<TokenId>) -> Vec<Option<MTTokenMetadataAll>> {
+        self.tokens.mt_metadata_token_all(token_ids)
+    }
+
+    fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadata>> {
+        self.tokens.mt_metadata_token_by_token_id(token_ids)
+    }
+
+    fn mt_metadata_base_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        self.tokens.mt_metadata_base_by_token_id(token_ids)
+    }
+
+    fn mt_metadata_base_by_metadata_id(
+        &self,
+        base_metadata_ids: Vec<String>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        self.tokens
+            .mt_metadata_base_by_metadata_id(base_metadata_ids)
+    }
+}
@@ -0,0 +1,194 @@
+/// The core methods for a basic multi token. Extension standards may be
+/// added in addition to this macro.
+#[macro_export]
+macro_rules! impl_multi_token_core {
+    ($contract: ident, $token: ident) => {
+        use nep_245::core::MultiTokenCore;
+        use nep_245::resolver::MultiTokenResolver;
+        use nep_245::token::ClearedApproval;
+
+        #[near_bindgen]
+        impl MultiTokenCore for $contract {
+            #[payable]
+            fn mt_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: TokenId,
+                amount: U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+            ) {
+                self.$token
+                    .mt_transfer(receiver_id, token_id, amount, approval, memo)
+            }
+
+            #[payable]
+            fn mt_batch_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<TokenId>,
+                amounts: Vec<U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+            ) {
+                self.$token
+                    .mt_batch_transfer(receiver_id, token_ids, amounts, approvals, memo)
+            }
+
+            #[payable]
+            fn mt_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: TokenId,
+                amount: U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<U128>> {
+                self.$token
+                    .mt_transfer_call(receiver_id, token_id, amount, approval, memo, msg)
+            }
+
+            #[payable]
+            fn mt_batch_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<TokenId>,
+                amounts: Vec<U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<U128>> {
+                self.$token.mt_batch_transfer_call(
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                    memo,
+                    msg,
+                )
+            }
+
+            fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>> {
+                self.$token.mt_token(token_ids)
+            }
+
+            fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+                self.$token.mt_balance_of(account_id, token_id)
+            }
+
+            fn mt_batch_balance_of(
+                &self,
+                account_id: AccountId,
+                token_ids: Vec<TokenId>,
+            ) -> Vec<U128> {
+                self.$token.mt_batch_balance_of(account_id, token_ids)
+            }
+
+            fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+                self.$token.mt_supply(token_id)
+            }
+
+            fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+                self.$token.mt_batch_supply(token_ids)
+            }
+        }
+
+        #[near_bindgen]
+        impl MultiTokenResolver for $contract {
+            #[private]
+            fn mt_resolve_transfer(
+                &mut self,
+                previous_owner_ids: Vec<AccountId>,
+                receiver_id: AccountId,
+                token_ids: Vec<TokenId>,
+                amounts: Vec<U128>,
+                approvals: Option<Vec<Option<Vec<ClearedApproval>>>>,
+            ) -> Vec<U128> {
+                self.$token.mt_resolve_transfer(
+                    previous_owner_ids,
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                )
+            }
+        }
+    };
+}
+
+/// Multi token approval management allows an owner to let other accounts transfer
+/// an amount of its tokens.
+#[macro_export]
+macro_rules! impl_multi_token_approval {
+    ($contract: ident, $token: ident) => {
+        use nep_245::approval::MultiTokenApproval;
+
+        #[near_bindgen]
+        impl MultiTokenApproval for $contract {
+            #[payable]
+            fn mt_approve(
+                &mut self,
+                token_ids: Vec<TokenId>,
+                amounts: Vec<U128>,
+                account_id: AccountId,
+                msg: Option<String>,
+            ) -> Option<Promise> {
+                self.$token.mt_approve(token_ids, amounts, account_id, msg)
+            }
+
+            #[payable]
+            fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId) {
+                self.$token.mt_revoke(token_ids, account_id)
+            }
+
+            #[payable]
+            fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>) {
+                self.$token.mt_revoke_all(token_ids)
+            }
+
+            fn mt_is_approved(
+                &self,
+                owner_id: AccountId,
+                token_ids: Vec<TokenId>,
+                approved_account_id: AccountId,
+                amounts: Vec<U128>,
+                approval_ids: Option<Vec<u64>>,
+            ) -> bool {
+                self.$token.mt_is_approved(
+                    owner_id,
+                    token_ids,
+                    approved_account_id,
+                    amounts,
+                    approval_ids,
+                )
+            }
+        }
+    };
+}
+
+/// Multi token enumeration adds the extension standard offering view-only
+/// methods to page through all tokens and the tokens of an account.
+#[macro_export]
+macro_rules! impl_multi_token_enumeration {
+    ($contract: ident, $token: ident) => {
+        use nep_245::enumeration::MultiTokenEnumeration;
+
+        #[near_bindgen]
+        impl MultiTokenEnumeration for $contract {
+            fn mt_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+                self.$token.mt_tokens(from_index, limit)
+            }
+
+            fn mt_tokens_for_owner(
+                &self,
+                account_id: AccountId,
+                from_index: Option<U128>,
+                limit: Option<u64>,
+            ) -> Vec<Token> {
+                self.$token
+                    .mt_tokens_for_owner(account_id, from_index, limit)
+            }
+        }
+    };
+}
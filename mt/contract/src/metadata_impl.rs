@@ -0,0 +1,54 @@
+use crate::core_impl::MultiToken;
+use nep_245::metadata::{MTBaseTokenMetadata, MTTokenMetadata, MTTokenMetadataAll};
+use nep_245::token::TokenId;
+
+/// The token level metadata. The contract level metadata is kept by the contract itself.
+impl MultiToken {
+    pub fn mt_metadata_token_all(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadataAll>> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                self.token_metadata_by_id
+                    .as_ref()
+                    .and_then(|by_id| by_id.get(token_id))
+            })
+            .collect()
+    }
+
+    pub fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadata>> {
+        self.mt_metadata_token_all(token_ids)
+            .into_iter()
+            .map(|metadata| metadata.map(|metadata| metadata.token))
+            .collect()
+    }
+
+    pub fn mt_metadata_base_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        self.mt_metadata_token_all(token_ids)
+            .into_iter()
+            .map(|metadata| metadata.map(|metadata| metadata.base))
+            .collect()
+    }
+
+    pub fn mt_metadata_base_by_metadata_id(
+        &self,
+        base_metadata_ids: Vec<String>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        base_metadata_ids
+            .iter()
+            .map(|id| {
+                self.base_metadata_by_id
+                    .as_ref()
+                    .and_then(|by_id| by_id.get(id))
+            })
+            .collect()
+    }
+}
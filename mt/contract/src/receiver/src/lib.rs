@@ -0,0 +1,46 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{env, log, near_bindgen, AccountId, PromiseOrValue};
+pub use nep_245::receiver::MultiTokenReceiver;
+pub use nep_245::token::TokenId;
+
+// A receiver for the tests of mt_transfer_call. What it does with the tokens depends on `msg`
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Receiver {}
+
+#[near_bindgen]
+impl MultiTokenReceiver for Receiver {
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        log!(
+            "Received {:?} of {:?} from {:?}, sent by {}",
+            amounts,
+            token_ids,
+            previous_owner_ids,
+            sender_id
+        );
+        match msg.as_str() {
+            // keeps every token
+            "keep" => PromiseOrValue::Value(vec![U128(0); amounts.len()]),
+            // keeps half of each amount, returns the rest
+            "keep-half" => PromiseOrValue::Value(
+                amounts
+                    .into_iter()
+                    .map(|amount| U128(amount.0 - amount.0 / 2))
+                    .collect(),
+            ),
+            // returns every token
+            "return" => PromiseOrValue::Value(amounts),
+            _ => env::panic_str("Unknown msg"),
+        }
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Receiver);
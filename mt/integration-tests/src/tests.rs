@@ -0,0 +1,440 @@
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::{env, fs};
+use workspaces::{Account, Contract};
+
+const STORAGE_DEPOSIT: u128 = parse_near!("0.1 N");
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mt_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let mt_wasm_filepath = fs::canonicalize(env::current_dir()?.join(mt_wasm_arg))?;
+    let receiver_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let receiver_wasm_filepath = fs::canonicalize(env::current_dir()?.join(receiver_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&fs::read(mt_wasm_filepath)?).await?;
+    let receiver = worker
+        .dev_deploy(&fs::read(receiver_wasm_filepath)?)
+        .await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // the contract is its own owner, it mints gold and a sword for alice
+    contract
+        .call("new_default_meta")
+        .args_json(json!({ "owner_id": contract.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    contract
+        .call("mt_mint")
+        .args_json(json!({
+            "token_id": "gold",
+            "receiver_id": alice.id(),
+            "amount": "100",
+            "token_metadata": metadata("gold", "Gold"),
+        }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?
+        .into_result()?;
+    contract
+        .call("mt_mint_unique")
+        .args_json(json!({
+            "token_id": "sword",
+            "receiver_id": alice.id(),
+            "token_metadata": metadata("sword", "Silver Sword"),
+        }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_minted_tokens(&alice, &contract).await?;
+    test_only_owner_mints(&alice, &contract).await?;
+    test_batch_transfer(&alice, &bob, &contract).await?;
+    test_transfer_call_keeps_part(&bob, &receiver, &contract).await?;
+    test_transfer_call_returns_all(&bob, &receiver, &contract).await?;
+    test_transfer_call_reverts_on_failure(&alice, &receiver, &contract).await?;
+    test_approved_transfer(&alice, &bob, &contract).await?;
+    test_burn(&alice, &contract).await?;
+    test_burnt_unique_tokens_are_not_minted_again(&contract).await?;
+    test_source_metadata(&alice, &contract).await?;
+    Ok(())
+}
+
+fn metadata(id: &str, name: &str) -> Value {
+    json!({
+        "base": {
+            "name": name,
+            "id": id,
+            "symbol": null,
+            "icon": null,
+            "decimals": null,
+            "base_uri": null,
+            "reference": null,
+            "copies": null,
+            "reference_hash": null,
+        },
+        "token": { "title": name },
+    })
+}
+
+async fn balance_of(user: &Account, contract: &Contract, token_id: &str) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(contract.id(), "mt_balance_of")
+        .args_json(json!({ "account_id": user.id(), "token_id": token_id }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+async fn test_minted_tokens(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    let tokens: Value = user
+        .call(contract.id(), "mt_token")
+        .args_json(json!({ "token_ids": ["gold", "sword", "shield"] }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(
+        tokens,
+        json!([
+            { "token_id": "gold", "owner_id": null },
+            { "token_id": "sword", "owner_id": user.id() },
+            null,
+        ])
+    );
+
+    let supplies: Value = user
+        .call(contract.id(), "mt_batch_supply")
+        .args_json(json!({ "token_ids": ["gold", "sword"] }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(supplies, json!(["100", "1"]));
+
+    let metadata: Value = user
+        .call(contract.id(), "mt_metadata_base_by_token_id")
+        .args_json(json!({ "token_ids": ["sword"] }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(metadata[0]["name"], "Silver Sword");
+    println!("      Passed ✅ mints fungible and non-fungible tokens");
+    Ok(())
+}
+
+async fn test_only_owner_mints(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    let result = user
+        .call(contract.id(), "mt_mint")
+        .args_json(json!({ "token_id": "gold", "receiver_id": user.id(), "amount": "100" }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+    assert_eq!(balance_of(user, contract, "gold").await?, 100);
+    println!("      Passed ✅ only the owner mints");
+    Ok(())
+}
+
+async fn test_batch_transfer(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let batch_transfer = |deposit: u128| {
+        alice
+            .call(contract.id(), "mt_batch_transfer")
+            .args_json(json!({
+                "receiver_id": bob.id(),
+                "token_ids": ["gold", "sword"],
+                "amounts": ["40", "1"],
+            }))
+            .deposit(deposit)
+            .transact()
+    };
+
+    // alice pays for the storage of bob's new balances
+    let result = batch_transfer(1).await?;
+    assert!(format!("{:?}", result.into_result()).contains("to cover storage"));
+    batch_transfer(STORAGE_DEPOSIT).await?.into_result()?;
+
+    assert_eq!(balance_of(alice, contract, "gold").await?, 60);
+    assert_eq!(balance_of(bob, contract, "gold").await?, 40);
+    assert_eq!(balance_of(bob, contract, "sword").await?, 1);
+
+    let tokens: Value = bob
+        .call(contract.id(), "mt_tokens_for_owner")
+        .args_json(json!({ "account_id": bob.id() }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(
+        tokens,
+        json!([
+            { "token_id": "gold", "owner_id": null },
+            { "token_id": "sword", "owner_id": bob.id() },
+        ])
+    );
+
+    // alice has no sword anymore
+    let result = alice
+        .call(contract.id(), "mt_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "token_id": "sword", "amount": "1" }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+    println!("      Passed ✅ transfers tokens in batches");
+    Ok(())
+}
+
+async fn test_transfer_call_keeps_part(
+    user: &Account,
+    receiver: &Contract,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let transferred: Vec<String> = user
+        .call(contract.id(), "mt_transfer_call")
+        .args_json(json!({
+            "receiver_id": receiver.id(),
+            "token_id": "gold",
+            "amount": "10",
+            "msg": "keep-half",
+        }))
+        .deposit(STORAGE_DEPOSIT)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(transferred, vec!["5"]);
+    assert_eq!(balance_of(user, contract, "gold").await?, 35);
+    assert_eq!(
+        balance_of(receiver.as_account(), contract, "gold").await?,
+        5
+    );
+    println!("      Passed ✅ mt_transfer_call refunds the unused amounts");
+    Ok(())
+}
+
+async fn test_transfer_call_returns_all(
+    user: &Account,
+    receiver: &Contract,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let transferred: Vec<String> = user
+        .call(contract.id(), "mt_batch_transfer_call")
+        .args_json(json!({
+            "receiver_id": receiver.id(),
+            "token_ids": ["gold", "sword"],
+            "amounts": ["10", "1"],
+            "msg": "return",
+        }))
+        .deposit(STORAGE_DEPOSIT)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(transferred, vec!["0", "0"]);
+    assert_eq!(balance_of(user, contract, "gold").await?, 35);
+    assert_eq!(balance_of(user, contract, "sword").await?, 1);
+
+    let tokens: Value = user
+        .call(contract.id(), "mt_token")
+        .args_json(json!({ "token_ids": ["sword"] }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(tokens[0]["owner_id"], json!(user.id()));
+    println!("      Passed ✅ mt_batch_transfer_call gives back the returned tokens");
+    Ok(())
+}
+
+async fn test_transfer_call_reverts_on_failure(
+    user: &Account,
+    receiver: &Contract,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let transferred: Vec<String> = user
+        .call(contract.id(), "mt_transfer_call")
+        .args_json(json!({
+            "receiver_id": receiver.id(),
+            "token_id": "gold",
+            "amount": "10",
+            "msg": "not understood",
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(transferred, vec!["0"]);
+    assert_eq!(balance_of(user, contract, "gold").await?, 60);
+    println!("      Passed ✅ mt_transfer_call is reverted when the receiver fails");
+    Ok(())
+}
+
+async fn test_approved_transfer(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    alice
+        .call(contract.id(), "mt_approve")
+        .args_json(json!({ "token_ids": ["gold"], "amounts": ["10"], "account_id": bob.id() }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?
+        .into_result()?;
+
+    // bob spends 4 of the 10 approved
+    bob.call(contract.id(), "mt_transfer")
+        .args_json(json!({
+            "receiver_id": bob.id(),
+            "token_id": "gold",
+            "amount": "4",
+            "approval": [alice.id(), 1],
+        }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(balance_of(alice, contract, "gold").await?, 56);
+
+    let is_approved = |amount: &'static str| {
+        alice
+            .call(contract.id(), "mt_is_approved")
+            .args_json(json!({
+                "owner_id": alice.id(),
+                "token_ids": ["gold"],
+                "approved_account_id": bob.id(),
+                "amounts": [amount],
+            }))
+            .transact()
+    };
+    assert!(is_approved("6").await?.json::<bool>()?);
+    assert!(!is_approved("7").await?.json::<bool>()?);
+
+    let result = bob
+        .call(contract.id(), "mt_transfer")
+        .args_json(json!({
+            "receiver_id": bob.id(),
+            "token_id": "gold",
+            "amount": "7",
+            "approval": [alice.id(), 1],
+        }))
+        .deposit(1)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", result.into_result()).contains("Not enough approved"));
+
+    alice
+        .call(contract.id(), "mt_revoke")
+        .args_json(json!({ "token_ids": ["gold"], "account_id": bob.id() }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(!is_approved("1").await?.json::<bool>()?);
+    println!("      Passed ✅ approved accounts transfer up to the approved amount");
+    Ok(())
+}
+
+async fn test_burn(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    user.call(contract.id(), "mt_burn")
+        .args_json(json!({ "token_id": "gold", "amount": "6" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(balance_of(user, contract, "gold").await?, 50);
+    let supply: String = user
+        .call(contract.id(), "mt_supply")
+        .args_json(json!({ "token_id": "gold" }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(supply, "94");
+    println!("      Passed ✅ burns tokens");
+    Ok(())
+}
+
+async fn test_burnt_unique_tokens_are_not_minted_again(contract: &Contract) -> anyhow::Result<()> {
+    contract
+        .call("mt_mint_unique")
+        .args_json(json!({
+            "token_id": "ring",
+            "receiver_id": contract.id(),
+            "token_metadata": metadata("ring", "Gold Ring"),
+        }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?
+        .into_result()?;
+    contract
+        .call("mt_burn")
+        .args_json(json!({ "token_id": "ring", "amount": "1" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let result = contract
+        .call("mt_mint")
+        .args_json(json!({ "token_id": "ring", "receiver_id": contract.id(), "amount": "100" }))
+        .deposit(STORAGE_DEPOSIT)
+        .transact()
+        .await?;
+    assert!(format!("{:?}", result.into_result()).contains("can not be minted again"));
+
+    let tokens: Value = contract
+        .call("mt_token")
+        .args_json(json!({ "token_ids": ["ring"] }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(tokens, json!([{ "token_id": "ring", "owner_id": null }]));
+    println!("      Passed ✅ burnt non-fungible tokens are not minted again");
+    Ok(())
+}
+
+async fn test_source_metadata(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    let metadata: Value = user
+        .call(contract.id(), "contract_source_metadata")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(metadata["version"], "1.0.0");
+    assert_eq!(
+        metadata["standards"],
+        json!([
+            { "standard": "nep330", "version": "1.1.0" },
+            { "standard": "nep245", "version": "1.0.0" },
+            { "standard": "nep297", "version": "1.0.0" },
+        ])
+    );
+    println!("      Passed ✅ tells which standards it implements");
+    Ok(())
+}
@@ -1,11 +1,19 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PromiseOrValue};
+use near_sdk::serde::Deserialize;
+use near_sdk::{near_bindgen, AccountId, PromiseOrValue};
 pub use nep_171::receiver::NonFungibleTokenReceiver;
 pub use nep_171::token::TokenId;
 pub use nep_178::approval_receiver::NonFungibleTokenApprovalReceiver;
 // Define the default message
 const DEFAULT_MESSAGE: &str = "Default";
 
+/// `msg` of `nft_transfer_call` and `nft_approve`, ex. `{"action": "set_message", "message": "Hi"}`
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+pub enum HelloMsg {
+    SetMessage { message: String },
+}
+
 // Define the contract structure
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -35,6 +43,8 @@ impl Hello {
 
 #[near_bindgen]
 impl NonFungibleTokenReceiver for Hello {
+    /// Keeps the NFT when `msg` is understood, and returns it otherwise
+    #[allow(unused_variables)]
     fn nft_on_transfer(
         &mut self,
         sender_id: AccountId,
@@ -42,19 +52,17 @@ impl NonFungibleTokenReceiver for Hello {
         token_id: TokenId,
         msg: String,
     ) -> PromiseOrValue<bool> {
-        let split_messages: Vec<&str> = msg.split(",").collect(); //ex) set_message,Hello
-        match split_messages[0] {
-            "set_message" => {
-                Self::ext(env::current_account_id()).set_message(split_messages[1].to_string());
-                PromiseOrValue::Value(false)
-            }
-            _ => PromiseOrValue::Value(true),
-        }
+        receiver_msg::on_nft_transfer(&msg, |msg| {
+            self.handle(msg)?;
+            Ok(PromiseOrValue::Value(false))
+        })
     }
 }
 
 #[near_bindgen]
 impl NonFungibleTokenApprovalReceiver for Hello {
+    /// Returns "success" when `msg` is understood, and the error otherwise
+    #[allow(unused_variables)]
     fn nft_on_approve(
         &mut self,
         token_id: TokenId,
@@ -62,13 +70,81 @@ impl NonFungibleTokenApprovalReceiver for Hello {
         approval_id: u64,
         msg: String,
     ) -> PromiseOrValue<String> {
-        let split_messages: Vec<&str> = msg.split(",").collect(); //ex) set_message,Hello
-        match split_messages[0] {
-            "set_message" => {
-                Self::ext(env::current_account_id()).set_message(split_messages[1].to_string());
-                PromiseOrValue::Value("success".to_string())
+        receiver_msg::on_nft_approve(&msg, |msg| {
+            self.handle(msg)?;
+            Ok(PromiseOrValue::Value("success".to_string()))
+        })
+    }
+}
+
+impl Hello {
+    fn handle(&mut self, msg: HelloMsg) -> Result<(), String> {
+        match msg {
+            HelloMsg::SetMessage { message } => {
+                if message.is_empty() {
+                    return Err("The message can't be empty".to_string());
+                }
+                self.set_message(message);
             }
-            _ => PromiseOrValue::Value("fail".to_string()),
         }
+        Ok(())
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Hello);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_nft_when_the_msg_is_understood() {
+        let mut contract = Hello::default();
+        let msg = r#"{"action": "set_message", "message": "Hi"}"#;
+
+        assert!(matches!(
+            transfer(&mut contract, msg),
+            PromiseOrValue::Value(false)
+        ));
+        assert_eq!(contract.get_message(), "Hi");
+    }
+
+    #[test]
+    fn returns_the_nft_when_the_msg_is_malformed() {
+        let mut contract = Hello::default();
+
+        for msg in [
+            "set_message",
+            "set_message,Hi",
+            r#"{"action": "delete"}"#,
+            r#"{"action": "set_message", "message": ""}"#,
+        ] {
+            assert!(matches!(
+                transfer(&mut contract, msg),
+                PromiseOrValue::Value(true)
+            ));
+        }
+        assert_eq!(contract.get_message(), DEFAULT_MESSAGE);
+    }
+
+    #[test]
+    fn tells_why_an_approval_is_refused() {
+        let mut contract = Hello::default();
+        let result = contract.nft_on_approve(
+            "1".to_string(),
+            "alice.near".parse().unwrap(),
+            0,
+            "set_message".to_string(),
+        );
+
+        match result {
+            PromiseOrValue::Value(error) => assert!(error.starts_with("Invalid msg")),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    fn transfer(contract: &mut Hello, msg: &str) -> PromiseOrValue<bool> {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        contract.nft_on_transfer(alice.clone(), alice, "1".to_string(), msg.to_string())
     }
 }
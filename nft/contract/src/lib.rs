@@ -97,7 +97,7 @@ impl Contract {
     /// `StorageKey::TokenMetadata` was provided at initialization.
     ///
     /// `self.tokens.mint` will enforce `predecessor_account_id` to equal the `owner_id` given in
-    /// initialization call to `new`.
+    /// initialization call to `new`, or the one it was handed over to with `set_owner`.
     #[payable]
     pub fn nft_mint(
         &mut self,
@@ -113,10 +113,22 @@ impl Contract {
 
 pub mod macros;
 
+ownable::impl_ownable!(Contract, tokens.owner_id);
 impl_non_fungible_token_core!(Contract, tokens);
 impl_non_fungible_token_approval!(Contract, tokens);
 impl_non_fungible_token_enumeration!(Contract, tokens);
 impl_non_fungible_token_royalty!(Contract, tokens);
+nep_330::impl_contract_source_metadata!(
+    Contract,
+    [
+        ("nep171", "1.1.0"),
+        ("nep177", "2.0.0"),
+        ("nep178", "1.1.0"),
+        ("nep181", "1.0.0"),
+        ("nep199", "2.0.0"),
+        ("nep297", "1.0.0"),
+    ]
+);
 
 #[near_bindgen]
 impl NonFungibleTokenMetadataProvider for Contract {
@@ -0,0 +1,177 @@
+use meta_tx::{calls, DelegateAction, Relayer, RpcClient};
+use near_crypto::SecretKey;
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::{env, fs};
+use workspaces::{Account, Contract};
+
+// Enough for the storage of the account and its key, but not for the gas of a transaction
+const GASLESS_BALANCE: u128 = parse_near!("0.002 N");
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let wasm_filepath = fs::canonicalize(env::current_dir()?.join(wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&fs::read(wasm_filepath)?).await?;
+    let rpc = RpcClient::new(&worker.rpc_addr());
+
+    // create accounts, alice has no NEAR to pay for gas
+    let relayer_account = worker.dev_create_account().await?;
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(GASLESS_BALANCE)
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let relayer = Relayer::new(
+        &worker.rpc_addr(),
+        relayer_account.id().as_str(),
+        secret_key(&relayer_account)?,
+    );
+
+    // the contract is its own owner, it mints a token for alice
+    contract
+        .call("new_default_meta")
+        .args_json(json!({ "owner_id": contract.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": "1",
+            "receiver_id": alice.id(),
+            "token_metadata": { "title": "Olympus Mons" },
+            "royalties": {},
+        }))
+        .deposit(parse_near!("0.1 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_user_cannot_pay_for_gas(&alice, &bob, &contract).await?;
+    test_forged_signature_is_not_relayed(&alice, &bob, &contract, &rpc, &relayer).await?;
+    test_key_of_another_account_is_not_relayed(&alice, &bob, &contract, &rpc, &relayer).await?;
+    test_relayed_transfer(&alice, &bob, &contract, &rpc, &relayer).await?;
+    Ok(())
+}
+
+fn secret_key(account: &Account) -> anyhow::Result<SecretKey> {
+    Ok(SecretKey::from_str(&account.secret_key().to_string())?)
+}
+
+// reads go through the contract account, alice cannot pay for them
+async fn nft_owner(contract: &Contract, token_id: &str) -> anyhow::Result<Value> {
+    let token: Value = contract
+        .call("nft_token")
+        .args_json(json!({ "token_id": token_id }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(token["owner_id"].clone())
+}
+
+async fn transfer_to_bob(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+) -> anyhow::Result<DelegateAction> {
+    let key = secret_key(alice)?;
+    DelegateAction::prepare(
+        rpc,
+        alice.id().as_str(),
+        key.public_key(),
+        contract.id().as_str(),
+        vec![calls::nft_transfer(bob.id().as_str(), "1", None, None)],
+    )
+    .await
+}
+
+async fn test_user_cannot_pay_for_gas(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let result = alice
+        .call(contract.id(), "nft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "token_id": "1" }))
+        .deposit(1)
+        .gas(calls::GAS_FOR_TRANSFER)
+        .transact()
+        .await;
+
+    assert!(result.map_or(true, |outcome| outcome.is_failure()));
+    assert_eq!(nft_owner(contract, "1").await?, json!(alice.id()));
+    println!("      Passed ✅ a user without NEAR cannot send nft_transfer");
+    Ok(())
+}
+
+async fn test_forged_signature_is_not_relayed(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    // bob signs a transfer of alice's token
+    let forged = transfer_to_bob(alice, bob, contract, rpc)
+        .await?
+        .sign(&secret_key(bob)?);
+
+    assert!(relayer.submit(forged).await.is_err());
+    assert_eq!(nft_owner(contract, "1").await?, json!(alice.id()));
+    println!("      Passed ✅ delegate actions not signed by their sender are not relayed");
+    Ok(())
+}
+
+async fn test_key_of_another_account_is_not_relayed(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    // bob validly signs with their own key, but names alice as the sender
+    let mut delegate_action = transfer_to_bob(alice, bob, contract, rpc).await?;
+    delegate_action.public_key = secret_key(bob)?.public_key();
+    let signed = delegate_action.sign(&secret_key(bob)?);
+
+    assert!(relayer.submit(signed).await.is_err());
+    assert_eq!(nft_owner(contract, "1").await?, json!(alice.id()));
+    println!("      Passed ✅ delegate actions signed with keys of other accounts are not relayed");
+    Ok(())
+}
+
+async fn test_relayed_transfer(
+    alice: &Account,
+    bob: &Account,
+    contract: &Contract,
+    rpc: &RpcClient,
+    relayer: &Relayer,
+) -> anyhow::Result<()> {
+    let balance = alice.view_account().await?.balance;
+    let delegate_action = transfer_to_bob(alice, bob, contract, rpc).await?;
+
+    let outcome = relayer
+        .submit(delegate_action.sign(&secret_key(alice)?))
+        .await?;
+
+    assert!(outcome.is_success(), "{:?}", outcome.failures());
+    assert_eq!(nft_owner(contract, "1").await?, json!(bob.id()));
+    // the relayer paid the yoctoNEAR nft_transfer requires too
+    assert_eq!(alice.view_account().await?.balance, balance);
+    println!("      Passed ✅ the relayer pays for alice's nft_transfer");
+    Ok(())
+}
@@ -0,0 +1,471 @@
+/*!
+Staking of NFTs for rewards in the fungible token of the `ft` contract.
+NOTES:
+  - NFTs are staked with `nft_transfer_call` on their collection. Only the collections the owner
+    whitelisted are accepted, `nft_on_transfer` returns `true` for the others so that the NFT goes
+    back to its owner.
+  - Each staked NFT earns `reward_per_block` tokens per block until it is unstaked.
+  - `claim` sends the rewards earned so far, `unstake` sends the NFT back and keeps its rewards
+    claimable. Both are undone if the transfer fails.
+  - The owner funds the rewards with `ft_transfer` to the contract, which must be registered on
+    the token. Stakers must be registered too, to receive their rewards.
+  - The contract pays for the storage of the stakes.
+*/
+mod stake;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, is_promise_success, log, near_bindgen, require, AccountId,
+    Balance, BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue,
+};
+use nep_141::core::ext_ft_core;
+use nep_171::receiver::NonFungibleTokenReceiver;
+use nep_171::token::TokenId;
+
+pub use stake::{stake_id, Stake, StakeId, StakeView};
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGAS
+const GAS_FOR_NFT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGAS
+const GAS_FOR_RESOLVE: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[ext_contract(ext_nft)]
+pub trait NonFungibleToken {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    pub owner_id: AccountId,
+    pub reward_token_id: AccountId,
+    // Rewards of each staked NFT per block
+    pub reward_per_block: Balance,
+    collections: UnorderedSet<AccountId>,
+    stakes: UnorderedMap<StakeId, Stake>,
+    stakes_per_owner: LookupMap<AccountId, UnorderedSet<StakeId>>,
+    // Rewards of unstaked NFTs, and of staked ones up to their `accrued_until`
+    unclaimed: LookupMap<AccountId, Balance>,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Collections,
+    Stakes,
+    StakesPerOwner,
+    OwnerStakes { account_hash: Vec<u8> },
+    Unclaimed,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(
+        owner_id: AccountId,
+        reward_token_id: AccountId,
+        reward_per_block: U128,
+        collections: Vec<AccountId>,
+    ) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        let mut this = Self {
+            owner_id,
+            reward_token_id,
+            reward_per_block: reward_per_block.0,
+            collections: UnorderedSet::new(StorageKey::Collections),
+            stakes: UnorderedMap::new(StorageKey::Stakes),
+            stakes_per_owner: LookupMap::new(StorageKey::StakesPerOwner),
+            unclaimed: LookupMap::new(StorageKey::Unclaimed),
+        };
+        this.collections.extend(collections);
+        this
+    }
+
+    pub fn add_collection(&mut self, collection_id: AccountId) {
+        self.assert_owner();
+        self.collections.insert(&collection_id);
+    }
+
+    /// NFTs of the collection which are already staked stay staked
+    pub fn remove_collection(&mut self, collection_id: AccountId) {
+        self.assert_owner();
+        self.collections.remove(&collection_id);
+    }
+
+    /// Sends the NFT back to the caller, its rewards stay claimable
+    #[payable]
+    pub fn unstake(&mut self, collection_id: AccountId, token_id: TokenId) -> Promise {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let stake_id = stake_id(&collection_id, &token_id);
+        let stake = self
+            .stakes
+            .get(&stake_id)
+            .unwrap_or_else(|| env::panic_str("The NFT is not staked"));
+        require!(
+            stake.owner_id == owner_id,
+            "Only the owner of the NFT can unstake it"
+        );
+
+        self.internal_accrue(&stake_id, stake);
+        self.internal_remove_stake(&owner_id, &stake_id);
+
+        ext_nft::ext(collection_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_NFT_TRANSFER)
+            .nft_transfer(
+                owner_id.clone(),
+                token_id.clone(),
+                None,
+                Some("Unstaked".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE)
+                    .resolve_unstake(owner_id, collection_id, token_id),
+            )
+    }
+
+    /// Returns whether the NFT was sent back, it is staked again otherwise
+    #[private]
+    pub fn resolve_unstake(
+        &mut self,
+        owner_id: AccountId,
+        collection_id: AccountId,
+        token_id: TokenId,
+    ) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        log!("{} could not be sent back, it is staked again", token_id);
+        let stake = Stake::new(owner_id, collection_id, token_id, env::block_height());
+        self.internal_add_stake(&stake);
+        false
+    }
+
+    /// Sends the caller the rewards of all of their NFTs
+    #[payable]
+    pub fn claim(&mut self) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        for stake_id in self.internal_stake_ids(&account_id) {
+            let stake = self.stakes.get(&stake_id).unwrap();
+            self.internal_accrue(&stake_id, stake);
+        }
+        let amount = self.unclaimed.remove(&account_id).unwrap_or(0);
+        require!(amount > 0, "Nothing to claim");
+
+        ext_ft_core::ext(self.reward_token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(
+                account_id.clone(),
+                U128(amount),
+                Some("Staking rewards".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE)
+                    .resolve_claim(account_id, U128(amount)),
+            )
+    }
+
+    /// Returns the claimed amount, or 0 if the transfer failed and the rewards are unclaimed again
+    #[private]
+    pub fn resolve_claim(&mut self, account_id: AccountId, amount: U128) -> U128 {
+        if is_promise_success() {
+            return amount;
+        }
+        log!(
+            "The rewards could not be sent, is {} registered on the token?",
+            account_id
+        );
+        self.internal_add_unclaimed(&account_id, amount.0);
+        U128(0)
+    }
+
+    pub fn get_collections(&self) -> Vec<AccountId> {
+        self.collections.to_vec()
+    }
+
+    pub fn get_stake(&self, collection_id: AccountId, token_id: TokenId) -> Option<StakeView> {
+        self.stakes
+            .get(&stake_id(&collection_id, &token_id))
+            .map(|stake| stake.to_view(self.reward_per_block, env::block_height()))
+    }
+
+    pub fn get_stakes_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<StakeView> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        let now = env::block_height();
+
+        self.internal_stake_ids(&account_id)
+            .into_iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|stake_id| {
+                self.stakes
+                    .get(&stake_id)
+                    .unwrap()
+                    .to_view(self.reward_per_block, now)
+            })
+            .collect()
+    }
+
+    /// What `claim` would send to `account_id` now
+    pub fn get_rewards(&self, account_id: AccountId) -> U128 {
+        let now = env::block_height();
+        let accrued: Balance = self
+            .internal_stake_ids(&account_id)
+            .iter()
+            .map(|stake_id| {
+                self.stakes
+                    .get(stake_id)
+                    .unwrap()
+                    .accrued(self.reward_per_block, now)
+            })
+            .sum();
+        U128(self.unclaimed.get(&account_id).unwrap_or(0) + accrued)
+    }
+
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Unauthorized"
+        );
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenReceiver for Contract {
+    /// Stakes the NFT for `previous_owner_id`. Returns `true`, for the collection to give the NFT
+    /// back, when the collection is not whitelisted.
+    #[allow(unused_variables)]
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let collection_id = env::predecessor_account_id();
+        if !self.collections.contains(&collection_id) {
+            log!("{} is not whitelisted, the NFT is returned", collection_id);
+            return PromiseOrValue::Value(true);
+        }
+
+        let stake = Stake::new(
+            previous_owner_id,
+            collection_id,
+            token_id,
+            env::block_height(),
+        );
+        self.internal_add_stake(&stake);
+        log!(
+            "{} staked {} of {}",
+            stake.owner_id,
+            stake.token_id,
+            stake.collection_id
+        );
+        PromiseOrValue::Value(false)
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Contract);
+
+impl Contract {
+    fn internal_stake_ids(&self, account_id: &AccountId) -> Vec<StakeId> {
+        self.stakes_per_owner
+            .get(account_id)
+            .map_or_else(Vec::new, |ids| ids.to_vec())
+    }
+
+    fn internal_add_stake(&mut self, stake: &Stake) {
+        let stake_id = stake_id(&stake.collection_id, &stake.token_id);
+        self.stakes.insert(&stake_id, stake);
+
+        let owner_id = &stake.owner_id;
+        let mut ids = self.stakes_per_owner.get(owner_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::OwnerStakes {
+                account_hash: env::sha256(owner_id.as_bytes()),
+            })
+        });
+        ids.insert(&stake_id);
+        self.stakes_per_owner.insert(owner_id, &ids);
+    }
+
+    fn internal_remove_stake(&mut self, owner_id: &AccountId, stake_id: &StakeId) {
+        self.stakes.remove(stake_id);
+        let mut ids = self.stakes_per_owner.get(owner_id).unwrap();
+        ids.remove(stake_id);
+        if ids.is_empty() {
+            self.stakes_per_owner.remove(owner_id);
+        } else {
+            self.stakes_per_owner.insert(owner_id, &ids);
+        }
+    }
+
+    /// Moves the rewards of the stake up to now to the unclaimed rewards of its owner
+    fn internal_accrue(&mut self, stake_id: &StakeId, mut stake: Stake) {
+        let now = env::block_height();
+        let accrued = stake.accrued(self.reward_per_block, now);
+        stake.accrued_until = now;
+        self.stakes.insert(stake_id, &stake);
+        self.internal_add_unclaimed(&stake.owner_id, accrued);
+    }
+
+    fn internal_add_unclaimed(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount > 0 {
+            let unclaimed = self.unclaimed.get(account_id).unwrap_or(0);
+            self.unclaimed.insert(account_id, &(unclaimed + amount));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{BlockHeight, PromiseResult};
+
+    const OWNER: &str = "owner";
+    const TOKEN: &str = "token";
+    const COLLECTION: &str = "collection";
+    const BOB: &str = "bob";
+
+    #[test]
+    fn bounces_collections_not_whitelisted() {
+        let mut contract = new_contract();
+        assert!(stake(&mut contract, "other", "1", 0));
+        assert!(contract.get_stakes_for_owner(bob(), None, None).is_empty());
+    }
+
+    #[test]
+    fn accrues_rewards_per_block() {
+        let mut contract = new_contract();
+        assert!(!stake(&mut contract, COLLECTION, "1", 10));
+        assert!(!stake(&mut contract, COLLECTION, "2", 20));
+
+        set_context(BOB, 0, 30);
+        // 20 blocks of the first NFT and 10 of the second
+        assert_eq!(contract.get_rewards(bob()).0, 300);
+        assert_eq!(contract.get_stakes_for_owner(bob(), None, None).len(), 2);
+    }
+
+    #[test]
+    fn unstaking_keeps_the_rewards() {
+        let mut contract = new_contract();
+        stake(&mut contract, COLLECTION, "1", 10);
+
+        set_context(BOB, 1, 20);
+        contract.unstake(collection(), "1".to_string());
+        assert!(contract.get_stake(collection(), "1".to_string()).is_none());
+
+        set_context(BOB, 1, 100);
+        assert_eq!(contract.get_rewards(bob()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner of the NFT can unstake it")]
+    fn only_the_owner_unstakes() {
+        let mut contract = new_contract();
+        stake(&mut contract, COLLECTION, "1", 10);
+
+        set_context(OWNER, 1, 20);
+        contract.unstake(collection(), "1".to_string());
+    }
+
+    #[test]
+    fn failed_unstake_stakes_again() {
+        let mut contract = new_contract();
+        stake(&mut contract, COLLECTION, "1", 10);
+        set_context(BOB, 1, 20);
+        contract.unstake(collection(), "1".to_string());
+
+        set_callback_result(PromiseResult::Failed, 21);
+        assert!(!contract.resolve_unstake(bob(), collection(), "1".to_string()));
+        assert_eq!(
+            contract
+                .get_stake(collection(), "1".to_string())
+                .unwrap()
+                .staked_at
+                .0,
+            21
+        );
+    }
+
+    #[test]
+    fn claims_the_rewards() {
+        let mut contract = new_contract();
+        stake(&mut contract, COLLECTION, "1", 10);
+
+        set_context(BOB, 1, 20);
+        contract.claim();
+        assert_eq!(contract.get_rewards(bob()).0, 0);
+
+        set_callback_result(PromiseResult::Failed, 20);
+        assert_eq!(contract.resolve_claim(bob(), U128(100)).0, 0);
+        assert_eq!(contract.get_rewards(bob()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn cannot_claim_nothing() {
+        let mut contract = new_contract();
+        set_context(BOB, 1, 20);
+        contract.claim();
+    }
+
+    fn bob() -> AccountId {
+        BOB.parse().unwrap()
+    }
+
+    fn collection() -> AccountId {
+        COLLECTION.parse().unwrap()
+    }
+
+    fn new_contract() -> Contract {
+        set_context(OWNER, 0, 0);
+        Contract::new(
+            OWNER.parse().unwrap(),
+            TOKEN.parse().unwrap(),
+            U128(10),
+            vec![collection()],
+        )
+    }
+
+    // Returns whether the NFT is returned
+    fn stake(
+        contract: &mut Contract,
+        collection: &str,
+        token_id: &str,
+        block: BlockHeight,
+    ) -> bool {
+        set_context(collection, 0, block);
+        match contract.nft_on_transfer(bob(), bob(), token_id.to_string(), String::new()) {
+            PromiseOrValue::Value(returned) => returned,
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    fn set_context(predecessor: &str, amount: Balance, block: BlockHeight) {
+        test_utils::set_context_with(predecessor, amount, |builder| {
+            builder.block_index(block);
+        });
+    }
+
+    fn set_callback_result(result: PromiseResult, block: BlockHeight) {
+        test_utils::set_callback_result_with(result, |builder| {
+            builder.block_index(block);
+        });
+    }
+}
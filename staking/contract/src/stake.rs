@@ -0,0 +1,63 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Balance, BlockHeight};
+use nep_171::token::TokenId;
+
+/// `<collection_id>:<token_id>`, account IDs can't contain `:`
+pub type StakeId = String;
+
+pub fn stake_id(collection_id: &AccountId, token_id: &TokenId) -> StakeId {
+    format!("{}:{}", collection_id, token_id)
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Stake {
+    pub owner_id: AccountId,
+    pub collection_id: AccountId,
+    pub token_id: TokenId,
+    pub staked_at: BlockHeight,
+    // Rewards accrue from this block, they were moved to the unclaimed rewards before it
+    pub accrued_until: BlockHeight,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeView {
+    pub owner_id: AccountId,
+    pub collection_id: AccountId,
+    pub token_id: TokenId,
+    pub staked_at: U64,
+    pub accrued: U128,
+}
+
+impl Stake {
+    pub fn new(
+        owner_id: AccountId,
+        collection_id: AccountId,
+        token_id: TokenId,
+        now: BlockHeight,
+    ) -> Self {
+        Self {
+            owner_id,
+            collection_id,
+            token_id,
+            staked_at: now,
+            accrued_until: now,
+        }
+    }
+
+    pub fn accrued(&self, reward_per_block: Balance, now: BlockHeight) -> Balance {
+        Balance::from(now.saturating_sub(self.accrued_until)) * reward_per_block
+    }
+
+    pub fn to_view(&self, reward_per_block: Balance, now: BlockHeight) -> StakeView {
+        StakeView {
+            owner_id: self.owner_id.clone(),
+            collection_id: self.collection_id.clone(),
+            token_id: self.token_id.clone(),
+            staked_at: U64(self.staked_at),
+            accrued: U128(self.accrued(reward_per_block, now)),
+        }
+    }
+}
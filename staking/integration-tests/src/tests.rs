@@ -0,0 +1,218 @@
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::{env, fs};
+use workspaces::network::Sandbox;
+use workspaces::{Account, Contract, Worker};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let staking_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let staking_wasm_filepath = fs::canonicalize(env::current_dir()?.join(staking_wasm_arg))?;
+    let ft_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let ft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(ft_wasm_arg))?;
+    let nft_wasm_arg: &str = &(env::args().nth(3).unwrap());
+    let nft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(nft_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let staking = worker.dev_deploy(&fs::read(staking_wasm_filepath)?).await?;
+    let ft = worker.dev_deploy(&fs::read(ft_wasm_filepath)?).await?;
+    let nft_wasm = fs::read(nft_wasm_filepath)?;
+    let collection = worker.dev_deploy(&nft_wasm).await?;
+    let other_collection = worker.dev_deploy(&nft_wasm).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice owns the reward token and funds the staking contract, bob owns an NFT of each collection
+    ft.call("new_default_meta")
+        .args_json(json!({ "owner_id": alice.id(), "total_supply": "1000000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    for account_id in [bob.id(), staking.id()] {
+        alice
+            .call(ft.id(), "storage_deposit")
+            .args_json(json!({ "account_id": account_id }))
+            .deposit(parse_near!("0.01 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    alice
+        .call(ft.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": staking.id(), "amount": "100000" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    for nft in [&collection, &other_collection] {
+        nft.call("new_default_meta")
+            .args_json(json!({ "owner_id": nft.id() }))
+            .transact()
+            .await?
+            .into_result()?;
+        nft.call("nft_mint")
+            .args_json(json!({
+                "token_id": "1",
+                "receiver_id": bob.id(),
+                "token_metadata": { "title": "Olympus Mons" },
+                "royalties": {},
+            }))
+            .deposit(parse_near!("0.1 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    staking
+        .call("new")
+        .args_json(json!({
+            "owner_id": alice.id(),
+            "reward_token_id": ft.id(),
+            "reward_per_block": "10",
+            "collections": [collection.id()],
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_bounces_other_collections(&bob, &staking, &other_collection).await?;
+    test_stake(&bob, &staking, &collection).await?;
+    test_rewards_accrue(&worker, &bob, &staking).await?;
+    test_claim(&bob, &staking, &ft).await?;
+    test_unstake(&bob, &staking, &collection).await?;
+    Ok(())
+}
+
+async fn nft_owner(user: &Account, nft: &Contract) -> anyhow::Result<Value> {
+    let token: Value = user
+        .call(nft.id(), "nft_token")
+        .args_json(json!({ "token_id": "1" }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(token["owner_id"].clone())
+}
+
+async fn rewards(user: &Account, staking: &Contract) -> anyhow::Result<u128> {
+    let rewards: String = user
+        .call(staking.id(), "get_rewards")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(rewards.parse()?)
+}
+
+// Returns whether the NFT was transferred
+async fn stake(user: &Account, staking: &Contract, nft: &Contract) -> anyhow::Result<bool> {
+    Ok(user
+        .call(nft.id(), "nft_transfer_call")
+        .args_json(json!({ "receiver_id": staking.id(), "token_id": "1", "msg": "" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?)
+}
+
+async fn test_bounces_other_collections(
+    user: &Account,
+    staking: &Contract,
+    other_collection: &Contract,
+) -> anyhow::Result<()> {
+    assert!(!stake(user, staking, other_collection).await?);
+    assert_eq!(nft_owner(user, other_collection).await?, json!(user.id()));
+    println!("      Passed ✅ NFTs of collections not whitelisted are returned");
+    Ok(())
+}
+
+async fn test_stake(
+    user: &Account,
+    staking: &Contract,
+    collection: &Contract,
+) -> anyhow::Result<()> {
+    assert!(stake(user, staking, collection).await?);
+    assert_eq!(nft_owner(user, collection).await?, json!(staking.id()));
+
+    let stakes: Value = user
+        .call(staking.id(), "get_stakes_for_owner")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(stakes[0]["collection_id"], json!(collection.id()));
+    assert_eq!(stakes[0]["token_id"], "1");
+    println!("      Passed ✅ stakes NFTs of whitelisted collections");
+    Ok(())
+}
+
+async fn test_rewards_accrue(
+    worker: &Worker<Sandbox>,
+    user: &Account,
+    staking: &Contract,
+) -> anyhow::Result<()> {
+    worker.fast_forward(20).await?;
+
+    assert!(rewards(user, staking).await? >= 200);
+    println!("      Passed ✅ rewards accrue every block");
+    Ok(())
+}
+
+async fn test_claim(user: &Account, staking: &Contract, ft: &Contract) -> anyhow::Result<()> {
+    let claimed: String = user
+        .call(staking.id(), "claim")
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    let claimed: u128 = claimed.parse()?;
+
+    assert!(claimed >= 200);
+    let balance: String = user
+        .call(ft.id(), "ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(balance.parse::<u128>()?, claimed);
+    println!("      Passed ✅ claims the rewards in the reward token");
+    Ok(())
+}
+
+async fn test_unstake(
+    user: &Account,
+    staking: &Contract,
+    collection: &Contract,
+) -> anyhow::Result<()> {
+    let unstaked: bool = user
+        .call(staking.id(), "unstake")
+        .args_json(json!({ "collection_id": collection.id(), "token_id": "1" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert!(unstaked);
+    assert_eq!(nft_owner(user, collection).await?, json!(user.id()));
+    // the blocks since the claim stay claimable, and no more accrue
+    let unclaimed = rewards(user, staking).await?;
+    assert!(unclaimed > 0);
+    assert_eq!(rewards(user, staking).await?, unclaimed);
+    println!("      Passed ✅ unstaking returns the NFT and keeps its rewards");
+    Ok(())
+}
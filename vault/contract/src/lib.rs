@@ -0,0 +1,413 @@
+/*!
+A vault holding deposits of fungible tokens, up to a cap per account and token.
+NOTES:
+  - Accounts deposit with `ft_transfer_call` on the token, with `msg` `{"action": "deposit"}`.
+    The vault keeps what fits under the cap and reports the rest as unused, so
+    `ft_resolve_transfer` refunds it to the sender.
+  - Only the tokens listed in `new` are accepted, anything else is refunded whole.
+  - `withdraw` sends deposited tokens back. If they can't be sent, e.g. the account isn't
+    registered on the token anymore, they are deposited again.
+  - Accounts must register with `storage_deposit` before depositing, which pays for the
+    storage of their deposits. The vault must be registered on the tokens.
+*/
+use ft_receiver::Deposits;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupSet, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Deserialize;
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, BorshStorageKey,
+    PanicOnDefault, Promise, PromiseOrValue, StorageUsage,
+};
+use nep_141::receiver::FungibleTokenReceiver;
+use nep_145::{StorageBalance, StorageBalanceBounds, StorageManagement};
+
+/// `msg` of the `ft_transfer_call` to the vault
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+pub enum VaultMsg {
+    Deposit,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token_ids: UnorderedSet<AccountId>,
+    // Most an account can deposit of each token
+    cap: Balance,
+    deposits: Deposits,
+    // Accounts registered with `storage_deposit`
+    accounts: LookupSet<AccountId>,
+    // Storage of an account with a deposit of every token
+    account_storage_usage: StorageUsage,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    TokenIds,
+    Deposits,
+    Accounts,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(token_ids: Vec<AccountId>, cap: U128) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        let mut this = Self {
+            token_ids: UnorderedSet::new(StorageKey::TokenIds),
+            cap: cap.0,
+            deposits: Deposits::new(StorageKey::Deposits),
+            accounts: LookupSet::new(StorageKey::Accounts),
+            account_storage_usage: 0,
+        };
+        this.token_ids.extend(token_ids);
+        this.measure_account_storage_usage();
+        this
+    }
+
+    /// Sends `amount` of the caller's deposit of `token_id` back to them. Returns the amount
+    /// sent, 0 if it couldn't be sent and was deposited again.
+    #[payable]
+    pub fn withdraw(&mut self, token_id: AccountId, amount: U128) -> Promise {
+        assert_one_yocto();
+        require!(amount.0 > 0, "Amount should be larger than 0");
+        self.deposits
+            .withdraw(token_id, env::predecessor_account_id(), amount.0)
+    }
+
+    pub fn get_deposit(&self, token_id: AccountId, account_id: AccountId) -> U128 {
+        U128(self.deposits.balance_of(&token_id, &account_id))
+    }
+
+    pub fn get_token_ids(&self) -> Vec<AccountId> {
+        self.token_ids.to_vec()
+    }
+
+    pub fn get_cap(&self) -> U128 {
+        U128(self.cap)
+    }
+
+    // Registers and deposits every token for the longest account ID, then removes it
+    fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id: AccountId = "a".repeat(64).parse().unwrap();
+        self.accounts.insert(&tmp_account_id);
+        for token_id in self.token_ids.iter() {
+            self.deposits.deposit(&token_id, &tmp_account_id, 1);
+        }
+        self.account_storage_usage = env::storage_usage() - initial_storage_usage;
+
+        for token_id in self.token_ids.iter() {
+            self.deposits
+                .internal_withdraw(&token_id, &tmp_account_id, 1);
+        }
+        self.accounts.remove(&tmp_account_id);
+    }
+
+    fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        if self.accounts.contains(account_id) {
+            Some(StorageBalance {
+                total: self.storage_balance_bounds().min,
+                available: U128(0),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn internal_deposit(
+        &mut self,
+        token_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Result<Balance, String> {
+        if !self.token_ids.contains(token_id) {
+            return Err(format!("{} is not accepted", token_id));
+        }
+        if !self.accounts.contains(account_id) {
+            return Err(format!(
+                "{} must register with storage_deposit first",
+                account_id
+            ));
+        }
+        let room = self
+            .cap
+            .saturating_sub(self.deposits.balance_of(token_id, account_id));
+        if room == 0 {
+            return Err("The deposit is full".to_string());
+        }
+        let accepted = amount.min(room);
+        self.deposits.deposit(token_id, account_id, accepted);
+        Ok(accepted)
+    }
+}
+
+ft_receiver::impl_ft_withdraw!(Contract, deposits);
+nep_330::impl_contract_source_metadata!(Contract);
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    /// Registers `account_id`, or the caller, refunding what is attached above the storage of
+    /// its deposits
+    #[payable]
+    #[allow(unused_variables)]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let refund = if self.accounts.contains(&account_id) {
+            log!("The account is already registered, refunding the deposit");
+            amount
+        } else {
+            let min_balance = self.storage_balance_bounds().min.0;
+            require!(
+                amount >= min_balance,
+                "The attached deposit is less than the minimum storage balance"
+            );
+            self.accounts.insert(&account_id);
+            amount - min_balance
+        };
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// The storage balance of an account is fixed, nothing is available to withdraw
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let storage_balance = self
+            .internal_storage_balance_of(&account_id)
+            .unwrap_or_else(|| env::panic_str("The account is not registered"));
+        if amount.map_or(false, |amount| amount.0 > 0) {
+            env::panic_str("The amount is greater than the available storage balance");
+        }
+        storage_balance
+    }
+
+    /// Unregisters the caller and refunds their storage balance. Deposits are never forfeited,
+    /// so `force` is ignored and they must be withdrawn first, and the withdrawals completed
+    /// since a failed one deposits the tokens again.
+    #[payable]
+    #[allow(unused_variables)]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        if !self.accounts.contains(&account_id) {
+            log!("The account is not registered");
+            return false;
+        }
+        require!(
+            self.token_ids
+                .iter()
+                .all(|token_id| self.deposits.balance_of(&token_id, &account_id) == 0),
+            "Withdraw the deposits before unregistering"
+        );
+        require!(
+            !self.deposits.has_pending(&account_id),
+            "Wait for the withdrawals to complete before unregistering"
+        );
+
+        self.accounts.remove(&account_id);
+        Promise::new(account_id).transfer(self.storage_balance_bounds().min.0 + 1);
+        true
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        let required_storage_balance =
+            Balance::from(self.account_storage_usage) * env::storage_byte_cost();
+        StorageBalanceBounds {
+            min: U128(required_storage_balance),
+            max: Some(U128(required_storage_balance)),
+        }
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(&account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Keeps what fits under the cap, the rest is returned as unused
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        ft_receiver::on_ft_transfer(amount, &msg, |msg| match msg {
+            VaultMsg::Deposit => self.internal_deposit(&token_id, &sender_id, amount.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::PromiseResult;
+    use test_utils::{set_callback_result, set_context, unused_amount};
+
+    const OWNER: &str = "owner";
+    const TOKEN: &str = "token";
+    const ALICE: &str = "alice";
+    const DEPOSIT: &str = r#"{"action": "deposit"}"#;
+
+    #[test]
+    fn keeps_up_to_the_cap() {
+        let mut contract = new_contract();
+
+        assert_eq!(on_transfer(&mut contract, TOKEN, 60, DEPOSIT), 0);
+        assert_eq!(on_transfer(&mut contract, TOKEN, 60, DEPOSIT), 20);
+        assert_eq!(contract.get_deposit(token(), alice()).0, 100);
+    }
+
+    #[test]
+    fn full_deposit_refunds_everything() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 100, DEPOSIT);
+
+        assert_eq!(on_transfer(&mut contract, TOKEN, 10, DEPOSIT), 10);
+        assert_eq!(contract.get_deposit(token(), alice()).0, 100);
+    }
+
+    #[test]
+    fn refunds_unknown_tokens_and_messages() {
+        let mut contract = new_contract();
+
+        assert_eq!(on_transfer(&mut contract, "other", 10, DEPOSIT), 10);
+        assert_eq!(on_transfer(&mut contract, TOKEN, 10, "deposit"), 10);
+        assert_eq!(
+            on_transfer(&mut contract, TOKEN, 10, r#"{"action": "steal"}"#),
+            10
+        );
+        assert_eq!(contract.get_deposit(token(), alice()).0, 0);
+    }
+
+    #[test]
+    fn refunds_unregistered_accounts() {
+        set_context(OWNER, 0);
+        let mut contract = Contract::new(vec![token()], U128(100));
+
+        assert_eq!(on_transfer(&mut contract, TOKEN, 10, DEPOSIT), 10);
+        assert_eq!(contract.get_deposit(token(), alice()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdraw the deposits before unregistering")]
+    fn keeps_accounts_with_deposits_registered() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 10, DEPOSIT);
+
+        set_context(ALICE, 1);
+        contract.storage_unregister(Some(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "Wait for the withdrawals to complete before unregistering")]
+    fn keeps_accounts_with_pending_withdrawals_registered() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 10, DEPOSIT);
+
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(10));
+        contract.storage_unregister(None);
+    }
+
+    #[test]
+    fn unregisters_once_withdrawals_complete() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 10, DEPOSIT);
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(10));
+
+        set_callback_result(PromiseResult::Successful(vec![]));
+        contract.ft_resolve_withdraw(token(), alice(), U128(10));
+
+        set_context(ALICE, 1);
+        assert!(contract.storage_unregister(None));
+        assert!(contract.storage_balance_of(alice()).is_none());
+    }
+
+    #[test]
+    fn withdraw_then_deposit_again() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 100, DEPOSIT);
+
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(30));
+        assert_eq!(contract.get_deposit(token(), alice()).0, 70);
+        assert_eq!(on_transfer(&mut contract, TOKEN, 50, DEPOSIT), 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough deposited")]
+    fn withdraw_at_most_the_deposit() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 10, DEPOSIT);
+
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(11));
+    }
+
+    #[test]
+    fn failed_withdrawal_is_deposited_again() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 100, DEPOSIT);
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(30));
+
+        set_callback_result(PromiseResult::Failed);
+        assert_eq!(
+            contract.ft_resolve_withdraw(token(), alice(), U128(30)).0,
+            0
+        );
+        assert_eq!(contract.get_deposit(token(), alice()).0, 100);
+    }
+
+    #[test]
+    fn sent_withdrawal_stays_withdrawn() {
+        let mut contract = new_contract();
+        on_transfer(&mut contract, TOKEN, 100, DEPOSIT);
+        set_context(ALICE, 1);
+        contract.withdraw(token(), U128(30));
+
+        set_callback_result(PromiseResult::Successful(vec![]));
+        assert_eq!(
+            contract.ft_resolve_withdraw(token(), alice(), U128(30)).0,
+            30
+        );
+        assert_eq!(contract.get_deposit(token(), alice()).0, 70);
+    }
+
+    fn token() -> AccountId {
+        TOKEN.parse().unwrap()
+    }
+
+    fn alice() -> AccountId {
+        ALICE.parse().unwrap()
+    }
+
+    fn new_contract() -> Contract {
+        set_context(OWNER, 0);
+        let mut contract = Contract::new(vec![token()], U128(100));
+
+        let storage_cost = contract.storage_balance_bounds().min.0;
+        set_context(ALICE, storage_cost);
+        contract.storage_deposit(None, None);
+        contract
+    }
+
+    // Returns the unused amount
+    fn on_transfer(contract: &mut Contract, token: &str, amount: Balance, msg: &str) -> Balance {
+        set_context(token, 0);
+        unused_amount(contract.ft_on_transfer(alice(), U128(amount), msg.to_string()))
+    }
+}
@@ -0,0 +1,295 @@
+use near_units::parse_near;
+use serde_json::json;
+use std::{env, fs};
+use workspaces::operations::Function;
+use workspaces::{Account, AccountId, Contract};
+
+const CAP: u128 = 600;
+const DEPOSIT: &str = r#"{"action": "deposit"}"#;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let vault_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let vault_wasm_filepath = fs::canonicalize(env::current_dir()?.join(vault_wasm_arg))?;
+    let ft_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let ft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(ft_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let vault = worker.dev_deploy(&fs::read(vault_wasm_filepath)?).await?;
+    let ft = worker.dev_deploy(&fs::read(ft_wasm_filepath)?).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice owns the tokens, bob and the vault are registered and bob gets 1000 tokens
+    ft.call("new_default_meta")
+        .args_json(json!({ "owner_id": alice.id(), "total_supply": "1000000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    for account_id in [bob.id(), vault.id()] {
+        alice
+            .call(ft.id(), "storage_deposit")
+            .args_json(json!({ "account_id": account_id }))
+            .deposit(parse_near!("0.01 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    alice
+        .call(ft.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "amount": "1000" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+    vault
+        .call("new")
+        .args_json(json!({
+            "token_ids": [ft.id()],
+            "cap": CAP.to_string(),
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_unregistered_account_is_refunded(&alice, &vault, &ft).await?;
+    for user in [&alice, &bob] {
+        user.call(vault.id(), "storage_deposit")
+            .deposit(parse_near!("0.01 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    test_remainder_is_refunded(&alice, &vault, &ft).await?;
+    test_full_deposit_is_refunded(&alice, &vault, &ft).await?;
+    test_invalid_msg_is_refunded(&alice, &vault, &ft).await?;
+    test_remainder_of_closed_account_is_burned(&bob, &vault, &ft).await?;
+    test_withdraw(&alice, &vault, &ft).await?;
+    test_failed_withdrawal_is_deposited_again(&bob, &vault, &ft).await?;
+    Ok(())
+}
+
+async fn ft_balance_of(
+    user: &Account,
+    ft: &Contract,
+    account_id: &AccountId,
+) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(ft.id(), "ft_balance_of")
+        .args_json(json!({ "account_id": account_id }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+async fn ft_total_supply(user: &Account, ft: &Contract) -> anyhow::Result<u128> {
+    let total_supply: String = user
+        .call(ft.id(), "ft_total_supply")
+        .transact()
+        .await?
+        .json()?;
+    Ok(total_supply.parse()?)
+}
+
+async fn get_deposit(user: &Account, vault: &Contract, ft: &Contract) -> anyhow::Result<u128> {
+    let deposit: String = user
+        .call(vault.id(), "get_deposit")
+        .args_json(json!({ "token_id": ft.id(), "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(deposit.parse()?)
+}
+
+// Returns the amount the vault kept, as reported by `ft_resolve_transfer`
+async fn deposit(
+    sender: &Account,
+    vault: &Contract,
+    ft: &Contract,
+    amount: u128,
+    msg: &str,
+) -> anyhow::Result<u128> {
+    let used: String = sender
+        .call(ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": vault.id(),
+            "amount": amount.to_string(),
+            "msg": msg,
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    Ok(used.parse()?)
+}
+
+async fn test_unregistered_account_is_refunded(
+    alice: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let balance = ft_balance_of(alice, ft, alice.id()).await?;
+
+    let used = deposit(alice, vault, ft, 100, DEPOSIT).await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(ft_balance_of(alice, ft, alice.id()).await?, balance);
+    println!("      Passed ✅ accounts must register before depositing");
+    Ok(())
+}
+
+async fn test_remainder_is_refunded(
+    alice: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let balance = ft_balance_of(alice, ft, alice.id()).await?;
+
+    let used = deposit(alice, vault, ft, 1000, DEPOSIT).await?;
+
+    assert_eq!(used, CAP);
+    assert_eq!(get_deposit(alice, vault, ft).await?, CAP);
+    assert_eq!(ft_balance_of(alice, ft, alice.id()).await?, balance - CAP);
+    assert_eq!(ft_balance_of(alice, ft, vault.id()).await?, CAP);
+    println!("      Passed ✅ what doesn't fit under the cap is refunded");
+    Ok(())
+}
+
+async fn test_full_deposit_is_refunded(
+    alice: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let balance = ft_balance_of(alice, ft, alice.id()).await?;
+
+    let used = deposit(alice, vault, ft, 100, DEPOSIT).await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(get_deposit(alice, vault, ft).await?, CAP);
+    assert_eq!(ft_balance_of(alice, ft, alice.id()).await?, balance);
+    println!("      Passed ✅ everything is refunded once the deposit is full");
+    Ok(())
+}
+
+async fn test_invalid_msg_is_refunded(
+    alice: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let balance = ft_balance_of(alice, ft, alice.id()).await?;
+
+    let used = deposit(alice, vault, ft, 100, "deposit").await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(ft_balance_of(alice, ft, alice.id()).await?, balance);
+    println!("      Passed ✅ tokens sent with an invalid msg are refunded");
+    Ok(())
+}
+
+async fn test_remainder_of_closed_account_is_burned(
+    bob: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let total_supply = ft_total_supply(bob, ft).await?;
+
+    // bob's account on the token is closed in the same transaction, before the remainder is
+    // refunded by `ft_resolve_transfer`
+    let result = bob
+        .batch(ft.id())
+        .call(
+            Function::new("ft_transfer_call")
+                .args_json(json!({
+                    "receiver_id": vault.id(),
+                    "amount": "1000",
+                    "msg": DEPOSIT,
+                }))
+                .deposit(1)
+                .gas(150_000_000_000_000),
+        )
+        .call(
+            Function::new("storage_unregister")
+                .args_json(json!({ "force": true }))
+                .deposit(1)
+                .gas(20_000_000_000_000),
+        )
+        .transact()
+        .await?;
+    assert!(result.is_success());
+    assert!(result
+        .logs()
+        .contains(&"The account of the sender was deleted"));
+
+    let storage_balance: Option<serde_json::Value> = bob
+        .call(ft.id(), "storage_balance_of")
+        .args_json(json!({ "account_id": bob.id() }))
+        .transact()
+        .await?
+        .json()?;
+    assert!(storage_balance.is_none());
+    assert_eq!(get_deposit(bob, vault, ft).await?, CAP);
+    assert_eq!(ft_total_supply(bob, ft).await?, total_supply - (1000 - CAP));
+    println!("      Passed ✅ the remainder of a closed account is burned");
+    Ok(())
+}
+
+async fn test_withdraw(alice: &Account, vault: &Contract, ft: &Contract) -> anyhow::Result<()> {
+    let balance = ft_balance_of(alice, ft, alice.id()).await?;
+
+    let withdrawn: String = alice
+        .call(vault.id(), "withdraw")
+        .args_json(json!({ "token_id": ft.id(), "amount": "200" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(withdrawn, "200");
+    assert_eq!(get_deposit(alice, vault, ft).await?, CAP - 200);
+    assert_eq!(ft_balance_of(alice, ft, alice.id()).await?, balance + 200);
+
+    // the room freed under the cap can be deposited again
+    let used = deposit(alice, vault, ft, 300, DEPOSIT).await?;
+    assert_eq!(used, 200);
+    println!("      Passed ✅ withdraw");
+    Ok(())
+}
+
+async fn test_failed_withdrawal_is_deposited_again(
+    bob: &Account,
+    vault: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    // bob isn't registered on the token anymore, so the transfer fails
+    let withdrawn: String = bob
+        .call(vault.id(), "withdraw")
+        .args_json(json!({ "token_id": ft.id(), "amount": "100" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(withdrawn, "0");
+    assert_eq!(get_deposit(bob, vault, ft).await?, CAP);
+    assert_eq!(ft_balance_of(bob, ft, vault.id()).await?, 2 * CAP);
+    println!("      Passed ✅ a failed withdrawal is deposited again");
+    Ok(())
+}
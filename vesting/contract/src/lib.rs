@@ -0,0 +1,457 @@
+/*!
+Vesting of the fungible tokens of the `ft` contract.
+NOTES:
+  - The owner funds a schedule with `ft_transfer_call` on the token, with `msg` holding it:
+    `{"beneficiary_id": "bob.near", "start": "<ns>", "cliff": "<ns>", "duration": "<ns>"}`.
+    Nothing vests before `start + cliff`, then the tokens vest linearly until `start + duration`.
+    `start` is optional and defaults to the current block.
+  - Tokens which aren't sent by the owner, or with an invalid `msg`, are reported as unused by
+    `ft_on_transfer`, so `ft_resolve_transfer` gives them back to the sender.
+  - Beneficiaries `claim` what vested so far. They must be registered on the token.
+  - The owner can `revoke` a schedule: what vested so far stays claimable, the rest is sent back
+    to the owner.
+  - The contract pays for the storage of the schedules, only the owner can create them.
+*/
+mod schedule;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{
+    assert_one_yocto, env, is_promise_success, log, near_bindgen, require, AccountId, Balance,
+    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue,
+};
+use nep_141::core::ext_ft_core;
+use nep_141::receiver::FungibleTokenReceiver;
+
+pub use schedule::{Schedule, ScheduleView, VestingMsg};
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGAS
+const GAS_FOR_RESOLVE: Gas = Gas(10_000_000_000_000); // 10 TGAS
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    pub owner_id: AccountId,
+    // The only token accepted
+    pub token_id: AccountId,
+    schedules: UnorderedMap<u64, Schedule>,
+    schedules_per_beneficiary: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_schedule_id: u64,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Schedules,
+    SchedulesPerBeneficiary,
+    BeneficiarySchedules { account_hash: Vec<u8> },
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, token_id: AccountId) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        Self {
+            owner_id,
+            token_id,
+            schedules: UnorderedMap::new(StorageKey::Schedules),
+            schedules_per_beneficiary: LookupMap::new(StorageKey::SchedulesPerBeneficiary),
+            next_schedule_id: 0,
+        }
+    }
+
+    /// Sends the caller what vested so far in all of their schedules
+    #[payable]
+    pub fn claim(&mut self) -> Promise {
+        assert_one_yocto();
+        let beneficiary_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+
+        let mut claims = vec![];
+        for schedule_id in self.internal_schedule_ids(&beneficiary_id) {
+            let mut schedule = self.schedules.get(&schedule_id).unwrap();
+            let amount = schedule.claimable(now);
+            if amount > 0 {
+                schedule.claimed += amount;
+                self.schedules.insert(&schedule_id, &schedule);
+                claims.push((U64(schedule_id), U128(amount)));
+            }
+        }
+        let amount: Balance = claims.iter().map(|(_, amount)| amount.0).sum();
+        require!(amount > 0, "Nothing to claim");
+
+        ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(
+                beneficiary_id,
+                U128(amount),
+                Some("Vested tokens".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE)
+                    .resolve_claim(claims),
+            )
+    }
+
+    /// Returns the claimed amount, or 0 if the transfer failed and the claims were undone
+    #[private]
+    pub fn resolve_claim(&mut self, claims: Vec<(U64, U128)>) -> U128 {
+        if is_promise_success() {
+            return U128(claims.iter().map(|(_, amount)| amount.0).sum());
+        }
+        for (schedule_id, amount) in claims {
+            let mut schedule = self.schedules.get(&schedule_id.0).unwrap();
+            schedule.claimed -= amount.0;
+            self.schedules.insert(&schedule_id.0, &schedule);
+        }
+        log!("The vested tokens could not be sent, is the beneficiary registered on the token?");
+        U128(0)
+    }
+
+    /// Stops a schedule: what vested so far stays claimable by the beneficiary, the rest is sent
+    /// back to the owner
+    #[payable]
+    pub fn revoke(&mut self, schedule_id: U64) -> Promise {
+        assert_one_yocto();
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can revoke"
+        );
+        let mut schedule = self.internal_get_schedule(schedule_id.0);
+        require!(!schedule.revoked, "The schedule is already revoked");
+
+        let vested = schedule.vested(env::block_timestamp());
+        let unvested = schedule.total - vested;
+        require!(unvested > 0, "Everything vested already");
+        schedule.total = vested;
+        schedule.revoked = true;
+        self.schedules.insert(&schedule_id.0, &schedule);
+
+        ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(
+                self.owner_id.clone(),
+                U128(unvested),
+                Some("Revoked tokens".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE)
+                    .resolve_revoke(schedule_id, U128(unvested)),
+            )
+    }
+
+    /// Returns whether the schedule was revoked, it keeps vesting if the transfer failed
+    #[private]
+    pub fn resolve_revoke(&mut self, schedule_id: U64, amount: U128) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        let mut schedule = self.schedules.get(&schedule_id.0).unwrap();
+        schedule.total += amount.0;
+        schedule.revoked = false;
+        self.schedules.insert(&schedule_id.0, &schedule);
+        false
+    }
+
+    pub fn get_schedule(&self, schedule_id: U64) -> Option<ScheduleView> {
+        self.schedules
+            .get(&schedule_id.0)
+            .map(|schedule| schedule.to_view(schedule_id.0, env::block_timestamp()))
+    }
+
+    pub fn get_schedules_for_beneficiary(
+        &self,
+        beneficiary_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<ScheduleView> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        let now = env::block_timestamp();
+
+        self.internal_schedule_ids(&beneficiary_id)
+            .into_iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|schedule_id| {
+                self.schedules
+                    .get(&schedule_id)
+                    .unwrap()
+                    .to_view(schedule_id, now)
+            })
+            .collect()
+    }
+
+    pub fn get_number_of_schedules(&self) -> u64 {
+        self.schedules.len()
+    }
+
+    /// What `claim` would send to `beneficiary_id` now
+    pub fn get_claimable(&self, beneficiary_id: AccountId) -> U128 {
+        let now = env::block_timestamp();
+        U128(
+            self.internal_schedule_ids(&beneficiary_id)
+                .into_iter()
+                .map(|schedule_id| self.schedules.get(&schedule_id).unwrap().claimable(now))
+                .sum(),
+        )
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Creates the schedule in `msg` for the tokens of the owner. Any other transfer is reported
+    /// as unused, for the token to return it.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        require!(
+            env::predecessor_account_id() == self.token_id,
+            "Only the vested token is accepted"
+        );
+        if sender_id != self.owner_id {
+            log!("Only the owner funds schedules, the tokens are returned");
+            return PromiseOrValue::Value(amount);
+        }
+        let schedule = near_sdk::serde_json::from_str::<VestingMsg>(&msg)
+            .map_err(|error| error.to_string())
+            .and_then(|msg| Schedule::new(msg, amount.0, env::block_timestamp()));
+
+        match schedule {
+            Ok(schedule) => {
+                let schedule_id = self.internal_add_schedule(&schedule);
+                log!(
+                    "Vesting {} to {} in schedule {}",
+                    amount.0,
+                    schedule.beneficiary_id,
+                    schedule_id
+                );
+                PromiseOrValue::Value(U128(0))
+            }
+            Err(error) => {
+                log!("Invalid schedule, the tokens are returned: {}", error);
+                PromiseOrValue::Value(amount)
+            }
+        }
+    }
+}
+
+nep_330::impl_contract_source_metadata!(Contract);
+
+impl Contract {
+    fn internal_get_schedule(&self, schedule_id: u64) -> Schedule {
+        self.schedules
+            .get(&schedule_id)
+            .unwrap_or_else(|| env::panic_str("Schedule not found"))
+    }
+
+    fn internal_schedule_ids(&self, beneficiary_id: &AccountId) -> Vec<u64> {
+        self.schedules_per_beneficiary
+            .get(beneficiary_id)
+            .map_or_else(Vec::new, |ids| ids.to_vec())
+    }
+
+    fn internal_add_schedule(&mut self, schedule: &Schedule) -> u64 {
+        let schedule_id = self.next_schedule_id;
+        self.next_schedule_id += 1;
+        self.schedules.insert(&schedule_id, schedule);
+
+        let beneficiary_id = &schedule.beneficiary_id;
+        let mut ids = self
+            .schedules_per_beneficiary
+            .get(beneficiary_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::BeneficiarySchedules {
+                    account_hash: env::sha256(beneficiary_id.as_bytes()),
+                })
+            });
+        ids.insert(&schedule_id);
+        self.schedules_per_beneficiary.insert(beneficiary_id, &ids);
+        schedule_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{PromiseResult, Timestamp};
+    use test_utils::unused_amount;
+
+    const OWNER: &str = "owner";
+    const TOKEN: &str = "token";
+    const BOB: &str = "bob";
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn vests_linearly_after_the_cliff() {
+        let mut contract = new_contract();
+        fund(
+            &mut contract,
+            OWNER,
+            1000,
+            schedule_msg(10 * SECOND, 100 * SECOND),
+        );
+
+        set_context(BOB, 1, 5 * SECOND);
+        assert_eq!(contract.get_claimable(bob()).0, 0);
+        set_context(BOB, 1, 10 * SECOND);
+        assert_eq!(contract.get_claimable(bob()).0, 100);
+        set_context(BOB, 1, 55 * SECOND);
+        assert_eq!(contract.get_claimable(bob()).0, 550);
+        set_context(BOB, 1, 200 * SECOND);
+        assert_eq!(contract.get_claimable(bob()).0, 1000);
+    }
+
+    #[test]
+    fn invalid_schedules_are_returned() {
+        let mut contract = new_contract();
+        let unused = fund(&mut contract, OWNER, 1000, "not a schedule".to_string());
+        assert_eq!(unused, 1000);
+
+        let unused = fund(
+            &mut contract,
+            OWNER,
+            1000,
+            schedule_msg(100 * SECOND, SECOND),
+        );
+        assert_eq!(unused, 1000);
+
+        let unused = fund(&mut contract, BOB, 1000, schedule_msg(0, SECOND));
+        assert_eq!(unused, 1000);
+        assert_eq!(contract.get_number_of_schedules(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the vested token is accepted")]
+    fn only_accepts_the_token() {
+        let mut contract = new_contract();
+        set_context(BOB, 0, 0);
+        contract.ft_on_transfer(owner(), U128(1000), schedule_msg(0, SECOND));
+    }
+
+    #[test]
+    fn claims_what_vested() {
+        let mut contract = new_contract();
+        fund(&mut contract, OWNER, 1000, schedule_msg(0, 100 * SECOND));
+        fund(&mut contract, OWNER, 500, schedule_msg(0, 10 * SECOND));
+
+        set_context(BOB, 1, 20 * SECOND);
+        contract.claim();
+        let schedules = contract.get_schedules_for_beneficiary(bob(), None, None);
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules[0].claimed.0, 200);
+        assert_eq!(schedules[1].claimed.0, 500);
+        assert_eq!(contract.get_claimable(bob()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn cannot_claim_before_the_cliff() {
+        let mut contract = new_contract();
+        fund(
+            &mut contract,
+            OWNER,
+            1000,
+            schedule_msg(10 * SECOND, 100 * SECOND),
+        );
+
+        set_context(BOB, 1, 5 * SECOND);
+        contract.claim();
+    }
+
+    #[test]
+    fn failed_claims_are_undone() {
+        let mut contract = new_contract();
+        fund(&mut contract, OWNER, 1000, schedule_msg(0, 100 * SECOND));
+        set_context(BOB, 1, 20 * SECOND);
+        contract.claim();
+
+        set_callback_result(PromiseResult::Failed, 20 * SECOND);
+        let claimed = contract.resolve_claim(vec![(U64(0), U128(200))]);
+        assert_eq!(claimed.0, 0);
+        assert_eq!(contract.get_claimable(bob()).0, 200);
+    }
+
+    #[test]
+    fn revoking_keeps_what_vested() {
+        let mut contract = new_contract();
+        fund(&mut contract, OWNER, 1000, schedule_msg(0, 100 * SECOND));
+
+        set_context(OWNER, 1, 30 * SECOND);
+        contract.revoke(U64(0));
+        set_context(BOB, 1, 200 * SECOND);
+        let schedule = contract.get_schedule(U64(0)).unwrap();
+        assert!(schedule.revoked);
+        assert_eq!(schedule.total.0, 300);
+        assert_eq!(contract.get_claimable(bob()).0, 300);
+    }
+
+    #[test]
+    fn failed_revocations_are_undone() {
+        let mut contract = new_contract();
+        fund(&mut contract, OWNER, 1000, schedule_msg(0, 100 * SECOND));
+        set_context(OWNER, 1, 30 * SECOND);
+        contract.revoke(U64(0));
+
+        set_callback_result(PromiseResult::Failed, 30 * SECOND);
+        assert!(!contract.resolve_revoke(U64(0), U128(700)));
+        let schedule = contract.get_schedule(U64(0)).unwrap();
+        assert!(!schedule.revoked);
+        assert_eq!(schedule.total.0, 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can revoke")]
+    fn only_the_owner_revokes() {
+        let mut contract = new_contract();
+        fund(&mut contract, OWNER, 1000, schedule_msg(0, 100 * SECOND));
+
+        set_context(BOB, 1, 0);
+        contract.revoke(U64(0));
+    }
+
+    fn owner() -> AccountId {
+        OWNER.parse().unwrap()
+    }
+
+    fn bob() -> AccountId {
+        BOB.parse().unwrap()
+    }
+
+    fn new_contract() -> Contract {
+        set_context(OWNER, 0, 0);
+        Contract::new(owner(), TOKEN.parse().unwrap())
+    }
+
+    fn schedule_msg(cliff: u64, duration: u64) -> String {
+        format!(
+            "{{\"beneficiary_id\": \"{}\", \"start\": \"0\", \"cliff\": \"{}\", \"duration\": \"{}\"}}",
+            BOB, cliff, duration
+        )
+    }
+
+    // Returns the unused amount
+    fn fund(contract: &mut Contract, sender: &str, amount: Balance, msg: String) -> Balance {
+        set_context(TOKEN, 0, 0);
+        unused_amount(contract.ft_on_transfer(sender.parse().unwrap(), U128(amount), msg))
+    }
+
+    fn set_context(predecessor: &str, amount: Balance, timestamp: Timestamp) {
+        test_utils::set_context_with(predecessor, amount, |builder| {
+            builder.block_timestamp(timestamp);
+        });
+    }
+
+    fn set_callback_result(result: PromiseResult, timestamp: Timestamp) {
+        test_utils::set_callback_result_with(result, |builder| {
+            builder.block_timestamp(timestamp);
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Balance, Timestamp};
+
+/// `msg` of the `ft_transfer_call` funding a schedule. Times are in nanoseconds, `cliff` and
+/// `duration` are counted from `start`, which defaults to the current block.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingMsg {
+    pub beneficiary_id: AccountId,
+    pub start: Option<U64>,
+    pub cliff: U64,
+    pub duration: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Schedule {
+    pub beneficiary_id: AccountId,
+    pub total: Balance,
+    pub claimed: Balance,
+    pub start: Timestamp,
+    pub cliff: u64,
+    pub duration: u64,
+    // Revoked schedules keep what had vested, which is now their total
+    pub revoked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ScheduleView {
+    pub id: U64,
+    pub beneficiary_id: AccountId,
+    pub total: U128,
+    pub claimed: U128,
+    pub vested: U128,
+    pub start: U64,
+    pub cliff: U64,
+    pub duration: U64,
+    pub revoked: bool,
+}
+
+impl Schedule {
+    pub fn new(msg: VestingMsg, total: Balance, now: Timestamp) -> Result<Self, String> {
+        if msg.duration.0 == 0 {
+            return Err("The duration must be positive".to_string());
+        }
+        if msg.cliff.0 > msg.duration.0 {
+            return Err("The cliff can't be longer than the duration".to_string());
+        }
+        Ok(Self {
+            beneficiary_id: msg.beneficiary_id,
+            total,
+            claimed: 0,
+            start: msg.start.map_or(now, |start| start.0),
+            cliff: msg.cliff.0,
+            duration: msg.duration.0,
+            revoked: false,
+        })
+    }
+
+    /// Nothing vests before the cliff, then the total vests linearly over the duration
+    pub fn vested(&self, now: Timestamp) -> Balance {
+        if self.revoked {
+            return self.total;
+        }
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            0
+        } else if elapsed >= self.duration {
+            self.total
+        } else {
+            // total * elapsed / duration, split so it can't overflow
+            let duration = self.duration as u128;
+            let elapsed = elapsed as u128;
+            self.total / duration * elapsed + self.total % duration * elapsed / duration
+        }
+    }
+
+    pub fn claimable(&self, now: Timestamp) -> Balance {
+        self.vested(now) - self.claimed
+    }
+
+    pub fn to_view(&self, id: u64, now: Timestamp) -> ScheduleView {
+        ScheduleView {
+            id: U64(id),
+            beneficiary_id: self.beneficiary_id.clone(),
+            total: U128(self.total),
+            claimed: U128(self.claimed),
+            vested: U128(self.vested(now)),
+            start: U64(self.start),
+            cliff: U64(self.cliff),
+            duration: U64(self.duration),
+            revoked: self.revoked,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use near_units::parse_near;
+use serde_json::{json, Value};
+use std::{env, fs};
+use workspaces::{Account, Contract};
+
+// Starts long after the tests end
+const FAR_FUTURE: &str = "4000000000000000000";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let vesting_wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let vesting_wasm_filepath = fs::canonicalize(env::current_dir()?.join(vesting_wasm_arg))?;
+    let ft_wasm_arg: &str = &(env::args().nth(2).unwrap());
+    let ft_wasm_filepath = fs::canonicalize(env::current_dir()?.join(ft_wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let vesting = worker.dev_deploy(&fs::read(vesting_wasm_filepath)?).await?;
+    let ft = worker.dev_deploy(&fs::read(ft_wasm_filepath)?).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // alice owns the tokens and the vesting contract, bob and the vesting contract are registered
+    ft.call("new_default_meta")
+        .args_json(json!({ "owner_id": alice.id(), "total_supply": "1000000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    for account_id in [bob.id(), vesting.id()] {
+        alice
+            .call(ft.id(), "storage_deposit")
+            .args_json(json!({ "account_id": account_id }))
+            .deposit(parse_near!("0.01 N"))
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    vesting
+        .call("new")
+        .args_json(json!({ "owner_id": alice.id(), "token_id": ft.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // begin tests
+    test_invalid_schedule_is_refunded(&alice, &vesting, &ft).await?;
+    test_only_owner_funds(&alice, &bob, &vesting, &ft).await?;
+    test_claim_vested(&alice, &bob, &vesting, &ft).await?;
+    test_revoke_unvested(&alice, &bob, &vesting, &ft).await?;
+    test_schedules_for_beneficiary(&bob, &vesting).await?;
+    Ok(())
+}
+
+async fn ft_balance_of(user: &Account, ft: &Contract) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(ft.id(), "ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+// Returns the amount the vesting contract kept
+async fn fund(
+    sender: &Account,
+    vesting: &Contract,
+    ft: &Contract,
+    amount: u128,
+    msg: Value,
+) -> anyhow::Result<u128> {
+    let used: String = sender
+        .call(ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": vesting.id(),
+            "amount": amount.to_string(),
+            "msg": msg.to_string(),
+        }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    Ok(used.parse()?)
+}
+
+async fn test_invalid_schedule_is_refunded(
+    alice: &Account,
+    vesting: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let used = fund(
+        alice,
+        vesting,
+        ft,
+        1000,
+        json!({ "beneficiary_id": "nobody" }),
+    )
+    .await?;
+    assert_eq!(used, 0);
+
+    let cliff_after_the_end =
+        json!({ "beneficiary_id": alice.id(), "cliff": "10", "duration": "1" });
+    let used = fund(alice, vesting, ft, 1000, cliff_after_the_end).await?;
+    assert_eq!(used, 0);
+
+    assert_eq!(ft_balance_of(alice, ft).await?, 1000000);
+    println!("      Passed ✅ tokens sent with an invalid schedule are refunded");
+    Ok(())
+}
+
+async fn test_only_owner_funds(
+    alice: &Account,
+    bob: &Account,
+    vesting: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    alice
+        .call(ft.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "amount": "100" }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let schedule = json!({ "beneficiary_id": bob.id(), "cliff": "0", "duration": "1" });
+    let used = fund(bob, vesting, ft, 100, schedule).await?;
+
+    assert_eq!(used, 0);
+    assert_eq!(ft_balance_of(bob, ft).await?, 100);
+    println!("      Passed ✅ only the owner funds schedules");
+    Ok(())
+}
+
+async fn test_claim_vested(
+    alice: &Account,
+    bob: &Account,
+    vesting: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let vested = json!({ "beneficiary_id": bob.id(), "start": "0", "cliff": "0", "duration": "1" });
+    assert_eq!(fund(alice, vesting, ft, 1000, vested).await?, 1000);
+
+    let claimed: String = bob
+        .call(vesting.id(), "claim")
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(claimed, "1000");
+    assert_eq!(ft_balance_of(bob, ft).await?, 1100);
+    let result = bob
+        .call(vesting.id(), "claim")
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+    println!("      Passed ✅ beneficiaries claim what vested");
+    Ok(())
+}
+
+async fn test_revoke_unvested(
+    alice: &Account,
+    bob: &Account,
+    vesting: &Contract,
+    ft: &Contract,
+) -> anyhow::Result<()> {
+    let unvested = json!({
+        "beneficiary_id": bob.id(),
+        "start": FAR_FUTURE,
+        "cliff": "0",
+        "duration": "1000",
+    });
+    assert_eq!(fund(alice, vesting, ft, 500, unvested).await?, 500);
+    let alice_balance = ft_balance_of(alice, ft).await?;
+
+    let result = bob
+        .call(vesting.id(), "revoke")
+        .args_json(json!({ "schedule_id": "1" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    let revoked: bool = alice
+        .call(vesting.id(), "revoke")
+        .args_json(json!({ "schedule_id": "1" }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+
+    assert!(revoked);
+    assert_eq!(ft_balance_of(alice, ft).await?, alice_balance + 500);
+    println!("      Passed ✅ the owner takes back what has not vested");
+    Ok(())
+}
+
+async fn test_schedules_for_beneficiary(bob: &Account, vesting: &Contract) -> anyhow::Result<()> {
+    let schedules: Value = bob
+        .call(vesting.id(), "get_schedules_for_beneficiary")
+        .args_json(json!({ "beneficiary_id": bob.id() }))
+        .transact()
+        .await?
+        .json()?;
+
+    assert_eq!(schedules.as_array().unwrap().len(), 2);
+    assert_eq!(schedules[0]["claimed"], "1000");
+    assert_eq!(schedules[1]["total"], "0");
+    assert_eq!(schedules[1]["revoked"], true);
+    println!("      Passed ✅ lists the schedules of a beneficiary");
+    Ok(())
+}
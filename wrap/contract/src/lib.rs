@@ -0,0 +1,236 @@
+/*!
+Wrapped NEAR, a fungible token backed 1:1 by the NEAR the contract holds.
+NOTES:
+  - Contracts which only accept NEP-141 tokens take NEAR through it: `near_deposit` mints as much
+    wNEAR as the attached deposit and `near_withdraw` burns wNEAR to send back as much NEAR.
+  - The first `near_deposit` of an account pays for its storage out of the deposit, and mints the
+    rest. Accounts can also register beforehand with `storage_deposit`.
+  - If the NEAR of `near_withdraw` can't be sent, the burned wNEAR is minted back.
+  - Unregistering an account with `force` burns its wNEAR, the NEAR backing it stays locked.
+*/
+use ft::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, is_promise_success, near_bindgen, require, AccountId, Balance, Gas,
+    PanicOnDefault, Promise, PromiseOrValue,
+};
+use nep_148::{FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC};
+use nep_297::nep_141::{FtBurn, FtMint};
+
+const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(5_000_000_000_000);
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Wrapped NEAR fungible token".to_string(),
+            symbol: "wNEAR".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        };
+        Self {
+            token: FungibleToken::new(b"t".to_vec()),
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+        }
+    }
+
+    /// Mints as much wNEAR as the attached deposit to the caller. If the caller isn't registered
+    /// yet, the storage of their account is paid out of the deposit first.
+    #[payable]
+    pub fn near_deposit(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let mut amount = env::attached_deposit();
+        require!(amount > 0, "Requires positive attached deposit");
+        if !self.token.balance_map.contains_key(&account_id) {
+            let storage_cost = self.token.storage_balance_bounds().min.0;
+            require!(
+                amount > storage_cost,
+                "The deposit must be larger than the storage of the account"
+            );
+            self.token.internal_register_account(&account_id);
+            amount -= storage_cost;
+        }
+        self.token.internal_deposit(&account_id, amount);
+        FtMint {
+            owner_id: &account_id,
+            amount: &U128(amount),
+            memo: Some("Deposit"),
+        }
+        .emit();
+    }
+
+    /// Burns `amount` of the caller's wNEAR and sends them as much NEAR. Returns whether the NEAR
+    /// was sent, the wNEAR is minted back otherwise.
+    #[payable]
+    pub fn near_withdraw(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        require!(amount.0 > 0, "Amount should be larger than 0");
+        let account_id = env::predecessor_account_id();
+        self.token.internal_withdraw(&account_id, amount.0);
+        FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: Some("Withdraw"),
+        }
+        .emit();
+
+        Promise::new(account_id.clone()).transfer(amount.0).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_WITHDRAW)
+                .near_resolve_withdraw(account_id, amount),
+        )
+    }
+
+    #[private]
+    pub fn near_resolve_withdraw(&mut self, account_id: AccountId, amount: U128) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        // The account may have unregistered since, its storage is then paid by the contract
+        if !self.token.balance_map.contains_key(&account_id) {
+            self.token.internal_register_account(&account_id);
+        }
+        self.token.internal_deposit(&account_id, amount.0);
+        FtMint {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: Some("Refund"),
+        }
+        .emit();
+        false
+    }
+
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        if balance > 0 {
+            FtBurn {
+                owner_id: &account_id,
+                amount: &U128(balance),
+                memo: Some("Account closed"),
+            }
+            .emit();
+        }
+    }
+}
+
+nep_141::impl_fungible_token_core!(Contract, token);
+nep_141::impl_fungible_token_storage!(Contract, token, on_account_closed);
+nep_330::impl_contract_source_metadata!(
+    Contract,
+    [
+        ("nep141", "1.0.0"),
+        ("nep145", "1.0.0"),
+        ("nep148", "1.0.0"),
+        ("nep297", "1.0.0"),
+    ]
+);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::PromiseResult;
+    use test_utils::{set_callback_result, set_context};
+
+    const ALICE: &str = "alice";
+    const NEAR: u128 = 1000000000000000000000000;
+
+    #[test]
+    fn deposit_pays_storage_and_mints_the_rest() {
+        set_context(ALICE, NEAR);
+        let mut contract = Contract::new();
+        let storage_cost = contract.storage_balance_bounds().min.0;
+
+        contract.near_deposit();
+        assert_eq!(contract.ft_balance_of(alice()).0, NEAR - storage_cost);
+
+        // once registered, the whole deposit is minted
+        contract.near_deposit();
+        assert_eq!(contract.ft_balance_of(alice()).0, 2 * NEAR - storage_cost);
+        assert_eq!(contract.ft_total_supply().0, 2 * NEAR - storage_cost);
+    }
+
+    #[test]
+    #[should_panic(expected = "The deposit must be larger than the storage of the account")]
+    fn deposit_must_cover_storage() {
+        set_context(ALICE, 1);
+        let mut contract = Contract::new();
+        contract.near_deposit();
+    }
+
+    #[test]
+    fn withdraw_burns() {
+        set_context(ALICE, NEAR);
+        let mut contract = Contract::new();
+        contract.near_deposit();
+        let balance = contract.ft_balance_of(alice()).0;
+
+        set_context(ALICE, 1);
+        contract.near_withdraw(U128(NEAR / 2));
+        assert_eq!(contract.ft_balance_of(alice()).0, balance - NEAR / 2);
+        assert_eq!(contract.ft_total_supply().0, balance - NEAR / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn withdraw_requires_one_yocto() {
+        set_context(ALICE, NEAR);
+        let mut contract = Contract::new();
+        contract.near_deposit();
+
+        set_context(ALICE, 0);
+        contract.near_withdraw(U128(1));
+    }
+
+    #[test]
+    fn failed_withdrawal_is_minted_back() {
+        set_context(ALICE, NEAR);
+        let mut contract = Contract::new();
+        contract.near_deposit();
+        let balance = contract.ft_balance_of(alice()).0;
+        set_context(ALICE, 1);
+        contract.near_withdraw(U128(NEAR / 2));
+
+        set_callback_result(PromiseResult::Failed);
+        assert!(!contract.near_resolve_withdraw(alice(), U128(NEAR / 2)));
+        assert_eq!(contract.ft_balance_of(alice()).0, balance);
+        assert_eq!(contract.ft_total_supply().0, balance);
+    }
+
+    #[test]
+    fn sent_withdrawal_stays_burned() {
+        set_context(ALICE, NEAR);
+        let mut contract = Contract::new();
+        contract.near_deposit();
+        let balance = contract.ft_balance_of(alice()).0;
+        set_context(ALICE, 1);
+        contract.near_withdraw(U128(NEAR / 2));
+
+        set_callback_result(PromiseResult::Successful(vec![]));
+        assert!(contract.near_resolve_withdraw(alice(), U128(NEAR / 2)));
+        assert_eq!(contract.ft_balance_of(alice()).0, balance - NEAR / 2);
+    }
+
+    fn alice() -> AccountId {
+        ALICE.parse().unwrap()
+    }
+}
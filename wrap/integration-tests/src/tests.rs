@@ -0,0 +1,159 @@
+use near_units::parse_near;
+use serde_json::json;
+use std::{env, fs};
+use workspaces::{Account, Contract};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let wasm_arg: &str = &(env::args().nth(1).unwrap());
+    let wasm_filepath = fs::canonicalize(env::current_dir()?.join(wasm_arg))?;
+
+    let worker = workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&fs::read(wasm_filepath)?).await?;
+
+    // create accounts
+    let account = worker.dev_create_account().await?;
+    let alice = account
+        .create_subaccount("alice")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = account
+        .create_subaccount("bob")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    contract.call("new").transact().await?.into_result()?;
+
+    // begin tests
+    test_deposit_registers(&alice, &contract).await?;
+    test_deposit_after_storage_deposit(&bob, &contract).await?;
+    test_transfer(&alice, &bob, &contract).await?;
+    test_withdraw(&alice, &contract).await?;
+    test_withdraw_more_than_balance(&bob, &contract).await?;
+    Ok(())
+}
+
+async fn ft_balance_of(user: &Account, contract: &Contract) -> anyhow::Result<u128> {
+    let balance: String = user
+        .call(contract.id(), "ft_balance_of")
+        .args_json(json!({ "account_id": user.id() }))
+        .transact()
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
+
+async fn storage_cost(user: &Account, contract: &Contract) -> anyhow::Result<u128> {
+    let bounds: serde_json::Value = user
+        .call(contract.id(), "storage_balance_bounds")
+        .args_json(json!({}))
+        .transact()
+        .await?
+        .json()?;
+    Ok(bounds["min"].as_str().unwrap().parse()?)
+}
+
+async fn test_deposit_registers(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    let storage_cost = storage_cost(user, contract).await?;
+    let result = user
+        .call(contract.id(), "near_deposit")
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let minted = parse_near!("1 N") - storage_cost;
+    assert_eq!(ft_balance_of(user, contract).await?, minted);
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event\":\"ft_mint\"")
+            && log.contains(&format!("\"amount\":\"{}\"", minted))));
+    println!("      Passed ✅ near_deposit registers the account out of the deposit");
+    Ok(())
+}
+
+async fn test_deposit_after_storage_deposit(
+    user: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    user.call(contract.id(), "storage_deposit")
+        .args_json(json!({}))
+        .deposit(parse_near!("0.01 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    user.call(contract.id(), "near_deposit")
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(ft_balance_of(user, contract).await?, parse_near!("1 N"));
+    println!("      Passed ✅ near_deposit mints 1:1 for registered accounts");
+    Ok(())
+}
+
+async fn test_transfer(alice: &Account, bob: &Account, contract: &Contract) -> anyhow::Result<()> {
+    alice
+        .call(contract.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": bob.id(), "amount": parse_near!("0.1 N").to_string() }))
+        .deposit(1)
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(ft_balance_of(bob, contract).await?, parse_near!("1.1 N"));
+    println!("      Passed ✅ wNEAR is a fungible token");
+    Ok(())
+}
+
+async fn test_withdraw(user: &Account, contract: &Contract) -> anyhow::Result<()> {
+    let wnear = ft_balance_of(user, contract).await?;
+    let near = user.view_account().await?.balance;
+
+    let result = user
+        .call(contract.id(), "near_withdraw")
+        .args_json(json!({ "amount": parse_near!("0.5 N").to_string() }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+    let burned = result
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event\":\"ft_burn\""));
+    let sent: bool = result.json()?;
+
+    assert!(sent && burned);
+    assert_eq!(
+        ft_balance_of(user, contract).await?,
+        wnear - parse_near!("0.5 N")
+    );
+    // less the gas of the transaction
+    assert!(user.view_account().await?.balance > near + parse_near!("0.49 N"));
+    println!("      Passed ✅ near_withdraw burns wNEAR and sends NEAR");
+    Ok(())
+}
+
+async fn test_withdraw_more_than_balance(
+    user: &Account,
+    contract: &Contract,
+) -> anyhow::Result<()> {
+    let result = user
+        .call(contract.id(), "near_withdraw")
+        .args_json(json!({ "amount": parse_near!("2 N").to_string() }))
+        .deposit(1)
+        .max_gas()
+        .transact()
+        .await?;
+
+    assert!(result.is_failure());
+    assert_eq!(ft_balance_of(user, contract).await?, parse_near!("1.1 N"));
+    println!("      Passed ✅ cannot withdraw more than the balance");
+    Ok(())
+}